@@ -0,0 +1,24 @@
+#version 150
+
+in vec3 v_normal;
+in vec2 v_tex_coords;
+in vec2 v_shade;
+in vec3 v_tint;
+
+out vec4 color;
+
+uniform sampler2D atlas;
+uniform vec3 u_light;
+
+void main(){
+    vec4 texel = texture(atlas, v_tex_coords);
+    // leaves and plants are cut out rather than blended
+    if (texel.a < 0.5) {
+        discard;
+    }
+    float facing = max(dot(normalize(v_normal), normalize(u_light)), 0.0);
+    float ambient_occlusion = v_shade.x;
+    float sky = 0.3 + 0.7 * v_shade.y;
+    float brightness = ambient_occlusion * sky * (0.6 + 0.4 * facing);
+    color = vec4(texel.rgb * v_tint * brightness, 1.0);
+}
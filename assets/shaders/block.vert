@@ -0,0 +1,53 @@
+#version 150
+
+// two u32s a vertex, packed by src/support/packed.rs
+in uvec2 data;
+
+out vec3 v_normal;
+out vec2 v_tex_coords;
+out vec2 v_shade;
+out vec3 v_tint;
+
+uniform mat4 perspective;
+uniform mat4 view;
+// world position of the chunk's corner
+uniform vec3 chunk_origin;
+// the biome colour for the chunk's grass and leaves
+uniform vec3 tint;
+
+const float DIAGONAL = 0.70710678;
+const vec3 FACE_NORMALS[10] = vec3[10](
+    vec3(-1.0, 0.0, 0.0),
+    vec3(1.0, 0.0, 0.0),
+    vec3(0.0, -1.0, 0.0),
+    vec3(0.0, 1.0, 0.0),
+    vec3(0.0, 0.0, -1.0),
+    vec3(0.0, 0.0, 1.0),
+    vec3(DIAGONAL, 0.0, -DIAGONAL),
+    vec3(-DIAGONAL, 0.0, DIAGONAL),
+    vec3(-DIAGONAL, 0.0, -DIAGONAL),
+    vec3(DIAGONAL, 0.0, DIAGONAL)
+);
+const float AO_LEVELS[4] = float[4](1.0, 0.82, 0.65, 0.45);
+const float ATLAS_TILES = 16.0;
+
+void main(){
+    uint a = data.x;
+    uint b = data.y;
+
+    vec3 local = vec3(a & 1023u, (a >> 10) & 1023u, (a >> 20) & 1023u) / 16.0;
+    uint face = b & 15u;
+    uint tile = (b >> 4) & 255u;
+    vec2 uv = vec2((b >> 12) & 31u, (b >> 17) & 31u) / 16.0;
+
+    // rows count down from the top of the atlas, which is uploaded bottom row first
+    vec2 corner = vec2(tile & 15u, tile >> 4);
+    v_tex_coords = vec2(
+        (corner.x + uv.x) / ATLAS_TILES,
+        1.0 - (corner.y + 1.0 - uv.y) / ATLAS_TILES
+    );
+    v_normal = FACE_NORMALS[face];
+    v_shade = vec2(AO_LEVELS[a >> 30], float((b >> 22) & 15u) / 15.0);
+    v_tint = ((b >> 26) & 1u) == 1u ? tint : vec3(1.0);
+    gl_Position = perspective * view * vec4(chunk_origin + local, 1.0);
+}
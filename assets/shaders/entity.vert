@@ -0,0 +1,14 @@
+#version 150
+
+in vec3 position;
+in vec4 normal;
+
+out vec3 v_normal;
+
+uniform mat4 perspective;
+uniform mat4 view;
+
+void main(){
+    v_normal = normal.xyz;
+    gl_Position = perspective * view * vec4(position, 1.0);
+}
@@ -0,0 +1,16 @@
+#version 150
+
+// textured and unlit, for things drawn over blocks like the mining cracks
+
+in vec3 position;
+in vec2 tex_coords;
+
+out vec2 v_tex_coords;
+
+uniform mat4 perspective;
+uniform mat4 view;
+
+void main(){
+    v_tex_coords = tex_coords;
+    gl_Position = perspective * view * vec4(position, 1.0);
+}
@@ -0,0 +1,12 @@
+#version 150
+
+in vec2 v_tex_coords;
+in vec4 v_colour;
+out vec4 color;
+
+// white glyphs, only the alpha matters
+uniform sampler2D font;
+
+void main(){
+    color = vec4(v_colour.rgb, v_colour.a * texture(font, v_tex_coords).a);
+}
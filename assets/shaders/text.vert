@@ -0,0 +1,19 @@
+#version 150
+
+// flat over the screen, positions are in pixels from the top left
+
+in vec2 position;
+in vec2 tex_coords;
+in vec4 colour;
+
+out vec2 v_tex_coords;
+out vec4 v_colour;
+
+uniform vec2 screen_size;
+
+void main(){
+    v_tex_coords = tex_coords;
+    v_colour = colour;
+    vec2 clip = position / screen_size * 2.0 - 1.0;
+    gl_Position = vec4(clip.x, -clip.y, 0.0, 1.0);
+}
@@ -0,0 +1,56 @@
+// generates a patch of world without a window and prints how much of each depth band
+// under the surface is air, for tuning the cave settings.
+//
+//     cargo run --release --example cave_report -- [seed] [radius in chunks]
+
+use survival_project::world::chunk::CHUNK_SIZE;
+use survival_project::world::World;
+use survival_project::worldgen::WorldGenerator;
+
+const BAND: i32 = 8;
+const BANDS: usize = 10;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let seed: u64 = args.next().and_then(|s| s.parse().ok()).unwrap_or(0);
+    let radius: i32 = args.next().and_then(|s| s.parse().ok()).unwrap_or(2);
+
+    let mut world = World::default();
+    let mut generator = WorldGenerator::new(seed);
+    for x in -radius..radius {
+        for y in -2..3 {
+            for z in -radius..radius {
+                generator.generate_chunk(&mut world, [x, y, z]);
+            }
+        }
+    }
+
+    let mut air = [0u32; BANDS];
+    let mut total = [0u32; BANDS];
+    let size = CHUNK_SIZE as i32;
+    for x in -radius * size..radius * size {
+        for z in -radius * size..radius * size {
+            let surface = generator.surface_height(x, z);
+            for depth in 1..(BAND * BANDS as i32) {
+                let band = (depth / BAND) as usize;
+                total[band] += 1;
+                if world.get_voxel([x, surface - depth, z]).block_type.is_air() {
+                    air[band] += 1;
+                }
+            }
+        }
+    }
+
+    println!("seed {seed}, {} chunks across", radius * 2);
+    println!("depth    air");
+    for band in 0..BANDS {
+        let fraction = air[band] as f32 / total[band].max(1) as f32;
+        let bar = "#".repeat((fraction * 50.0).round() as usize);
+        println!(
+            "{:>3}-{:<3} {:>5.1}% {bar}",
+            band as i32 * BAND,
+            (band as i32 + 1) * BAND - 1,
+            fraction * 100.0
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::game::Game;
+use crate::render::text::{text_width, TextMesh, GLYPH_SIZE};
+use crate::render::RenderStats;
+use crate::world::light::{daylight, light_level};
+use crate::world::split_pos;
+
+// the F3 screen: what the game and renderer are up to, over the top left of the window
+
+// frame times are averaged over this long, so the numbers can be read
+const FRAME_WINDOW: Duration = Duration::from_secs(1);
+// from the corner of the window, in pixels
+const MARGIN: f32 = 4.0;
+// the background reaches this far past the text on each side
+const PADDING: f32 = 2.0;
+const TEXT_COLOUR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const BACKGROUND: [f32; 4] = [0.0, 0.0, 0.0, 0.5];
+
+// how long the last second of frames took
+#[derive(Default)]
+pub struct FrameTimes {
+    frames: VecDeque<Duration>,
+    total: Duration,
+}
+
+impl FrameTimes {
+    pub fn push(&mut self, frame: Duration) {
+        self.frames.push_back(frame);
+        self.total += frame;
+        while self.total > FRAME_WINDOW && self.frames.len() > 1 {
+            let oldest = self.frames.pop_front().unwrap();
+            self.total -= oldest;
+        }
+    }
+
+    pub fn fps(&self) -> f32 {
+        if self.total.is_zero() {
+            return 0.0;
+        }
+        self.frames.len() as f32 / self.total.as_secs_f32()
+    }
+
+    pub fn average(&self) -> Duration {
+        self.total / self.frames.len().max(1) as u32
+    }
+
+    pub fn slowest(&self) -> Duration {
+        self.frames.iter().copied().max().unwrap_or_default()
+    }
+}
+
+// the compass direction closest to `direction` along the ground. north is -z
+fn compass(direction: (f32, f32, f32)) -> &'static str {
+    let (x, _, z) = direction;
+    if x.abs() > z.abs() {
+        if x > 0.0 {
+            "east (+x)"
+        } else {
+            "west (-x)"
+        }
+    } else if z > 0.0 {
+        "south (+z)"
+    } else {
+        "north (-z)"
+    }
+}
+
+fn ms(duration: Duration) -> f32 {
+    duration.as_secs_f32() * 1000.0
+}
+
+pub fn debug_lines(game: &Game, frames: &FrameTimes, stats: RenderStats) -> Vec<String> {
+    let (x, y, z) = game.camera.position();
+    let direction = game.camera.direction();
+    let block = game.camera_block();
+    let ([cx, cy, cz], [lx, ly, lz]) = split_pos(block);
+    let [bx, by, bz] = block;
+    let target = match game.target() {
+        Some(hit) => {
+            let [x, y, z] = hit.block;
+            let voxel = game.world.get_voxel(hit.block);
+            format!("looking at {:?} at {x} {y} {z}", voxel.block_type)
+        }
+        None => "looking at nothing".to_string(),
+    };
+    let day = daylight(game.time);
+
+    vec![
+        format!(
+            "{:.0} fps, {:.1} ms a frame, {:.1} ms slowest",
+            frames.fps(),
+            ms(frames.average()),
+            ms(frames.slowest())
+        ),
+        format!("position {x:.2} {y:.2} {z:.2}"),
+        format!("block {bx} {by} {bz}, chunk {cx} {cy} {cz}, {lx} {ly} {lz} in the chunk"),
+        format!(
+            "facing {} ({:.2} {:.2} {:.2})",
+            compass(direction),
+            direction.0,
+            direction.1,
+            direction.2
+        ),
+        target,
+        format!("biome {}", game.generator.biome_at(bx, bz).name()),
+        format!(
+            "light {}, daylight {day}",
+            light_level(&game.world, block, day)
+        ),
+        format!(
+            "chunks {} loaded, {} drawn",
+            stats.loaded_chunks, stats.drawn_chunks
+        ),
+        format!(
+            "waiting: {} chunks to generate, {} level of detail changes, {} fluid updates",
+            game.chunks_to_generate,
+            stats.lod_changes,
+            game.fluids.pending()
+        ),
+    ]
+}
+
+// each line on its own dark strip so it can be read over bright sky
+pub fn debug_text(lines: &[String]) -> TextMesh {
+    let mut mesh = TextMesh::new();
+    for (row, line) in lines.iter().enumerate() {
+        let top = MARGIN + row as f32 * GLYPH_SIZE[1];
+        mesh.push_rect(
+            [MARGIN - PADDING, top],
+            [MARGIN + text_width(line) + PADDING, top + GLYPH_SIZE[1]],
+            BACKGROUND,
+        );
+        mesh.push_text(line, [MARGIN, top], TEXT_COLOUR);
+    }
+    mesh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_times_cover_the_last_second() {
+        let mut frames = FrameTimes::default();
+        assert_eq!(frames.fps(), 0.0);
+        for _ in 0..120 {
+            frames.push(Duration::from_millis(10));
+        }
+        // only the last hundred frames fit in a second
+        assert!((frames.fps() - 100.0).abs() < 0.01);
+        assert_eq!(frames.average(), Duration::from_millis(10));
+
+        frames.push(Duration::from_millis(50));
+        assert_eq!(frames.slowest(), Duration::from_millis(50));
+        // a single long frame is still counted
+        frames.push(Duration::from_secs(2));
+        assert_eq!(frames.average(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn compass_points_the_nearest_way() {
+        assert_eq!(compass((0.1, 0.0, -0.9)), "north (-z)");
+        assert_eq!(compass((0.8, -0.5, 0.3)), "east (+x)");
+        assert_eq!(compass((-0.6, 0.9, 0.5)), "west (-x)");
+        assert_eq!(compass((0.0, -1.0, 0.0)), "north (-z)");
+    }
+}
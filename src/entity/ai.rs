@@ -0,0 +1,359 @@
+use crate::entity::path::{find_path, floor_height, ground_near, PathRules};
+use crate::entity::{Entity, EntityKind};
+use crate::support::rng::Rng;
+use crate::world::collision::Body;
+use crate::world::World;
+
+// blocks
+const CHASE_RANGE: f32 = 16.0;
+const ATTACK_RANGE: f32 = 1.2;
+const DESPAWN_DISTANCE: f32 = 128.0;
+const WANDER_RANGE: i32 = 6;
+const FLEE_DISTANCE: f32 = 8.0;
+// ticks
+const FLEE_TIME: u32 = 100;
+const CHASE_REPATH: u32 = 20;
+const ATTACK_COOLDOWN: u32 = 20;
+// a generous time to reach each node before deciding the way is blocked
+const STUCK_TIME: u32 = 40;
+// chance each tick that an idle mob sets off somewhere
+const WANDER_CHANCE: f32 = 1.0 / 80.0;
+const JUMP_SPEED: f32 = 0.42;
+// close enough to a path node to move on to the next one
+const NODE_REACHED: f32 = 0.35;
+const ATTACK_DAMAGE: u32 = 3;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Behaviour {
+    Idle,
+    Wander,
+    Flee,
+    Chase,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Brain {
+    pub behaviour: Behaviour,
+    // block positions still to walk through, next one first
+    pub path: Vec<[i32; 3]>,
+    // ticks spent heading for the next node, so mobs that get stuck give up on the path
+    path_time: u32,
+    // health last tick, for noticing being hurt
+    last_health: u32,
+    scared: u32,
+    cooldown: u32,
+}
+
+impl Brain {
+    pub fn new(health: u32) -> Brain {
+        Brain {
+            behaviour: Behaviour::Idle,
+            path: Vec::new(),
+            path_time: 0,
+            last_health: health,
+            scared: 0,
+            cooldown: 0,
+        }
+    }
+
+    fn set_path(&mut self, path: Option<Vec<[i32; 3]>>) {
+        self.path = path.unwrap_or_default();
+        self.path_time = 0;
+    }
+}
+
+// what a mob decided this tick that needs more than the mob itself to carry out
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Decision {
+    pub despawn: bool,
+    // damage dealt to the player
+    pub attack: u32,
+}
+
+fn distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+    let d = [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+    (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt()
+}
+
+fn block_of(position: [f32; 3]) -> [i32; 3] {
+    position.map(|v| v.floor() as i32)
+}
+
+// picks a behaviour for one mob and steers it along its path. `player` is the player's
+// feet
+pub fn think(entity: &mut Entity, world: &World, player: [f32; 3], rng: &mut Rng) -> Decision {
+    let EntityKind::Mob { kind, health } = entity.kind else {
+        return Decision::default();
+    };
+    let Some(brain) = &mut entity.brain else {
+        return Decision::default();
+    };
+    let body = &mut entity.body;
+    let mut decision = Decision::default();
+
+    let to_player = distance(body.position, player);
+    if kind.is_hostile() && to_player > DESPAWN_DISTANCE {
+        decision.despawn = true;
+        return decision;
+    }
+
+    if health < brain.last_health && !kind.is_hostile() {
+        brain.scared = FLEE_TIME;
+        brain.set_path(None);
+    }
+    brain.last_health = health;
+    brain.scared = brain.scared.saturating_sub(1);
+    brain.cooldown = brain.cooldown.saturating_sub(1);
+
+    let rules = PathRules {
+        height: body.height.ceil() as i32,
+        ..PathRules::default()
+    };
+    let here = block_of(body.position);
+
+    let behaviour = if kind.is_hostile() && to_player < CHASE_RANGE {
+        Behaviour::Chase
+    } else if brain.scared > 0 {
+        Behaviour::Flee
+    } else if !brain.path.is_empty() {
+        Behaviour::Wander
+    } else {
+        Behaviour::Idle
+    };
+    // wandering keeps whatever path it picked while idle, anything else starts afresh
+    if behaviour != brain.behaviour {
+        if behaviour != Behaviour::Wander {
+            brain.set_path(None);
+        }
+        brain.behaviour = behaviour;
+    }
+
+    match behaviour {
+        Behaviour::Chase => {
+            if to_player < ATTACK_RANGE && brain.cooldown == 0 {
+                decision.attack = ATTACK_DAMAGE;
+                brain.cooldown = ATTACK_COOLDOWN;
+            }
+            if brain.path.is_empty() || brain.path_time >= CHASE_REPATH {
+                brain.set_path(find_path(world, here, block_of(player), &rules));
+            }
+        }
+        Behaviour::Flee => {
+            if brain.path.is_empty() {
+                // somewhere straight away from the player
+                let away = [body.position[0] - player[0], body.position[2] - player[2]];
+                let len = away[0].hypot(away[1]).max(0.01);
+                let target = [
+                    body.position[0] + away[0] / len * FLEE_DISTANCE,
+                    body.position[1],
+                    body.position[2] + away[1] / len * FLEE_DISTANCE,
+                ];
+                let goal = ground_near(world, block_of(target), &rules);
+                brain.set_path(goal.and_then(|goal| find_path(world, here, goal, &rules)));
+            }
+        }
+        Behaviour::Idle | Behaviour::Wander => {
+            if brain.path.is_empty() && rng.chance(WANDER_CHANCE) {
+                let target = [
+                    here[0] + rng.range(-WANDER_RANGE, WANDER_RANGE + 1),
+                    here[1],
+                    here[2] + rng.range(-WANDER_RANGE, WANDER_RANGE + 1),
+                ];
+                let goal = ground_near(world, target, &rules);
+                brain.set_path(goal.and_then(|goal| find_path(world, here, goal, &rules)));
+            }
+        }
+    }
+
+    follow_path(brain, body, world, kind.speed());
+    decision
+}
+
+// walks towards the next node on the path, jumping up steps and slabs on the way
+fn follow_path(brain: &mut Brain, body: &mut Body, world: &World, speed: f32) {
+    brain.path_time += 1;
+    if brain.path_time > STUCK_TIME {
+        brain.set_path(None);
+    }
+    let Some(&next) = brain.path.first() else {
+        return;
+    };
+
+    let target = [next[0] as f32 + 0.5, next[2] as f32 + 0.5];
+    let d = [target[0] - body.position[0], target[1] - body.position[2]];
+    let len = d[0].hypot(d[1]);
+    let here = block_of(body.position);
+    if len < NODE_REACHED && here[1] == next[1] {
+        brain.path.remove(0);
+        brain.path_time = 0;
+        return;
+    }
+
+    if len > 0.0 {
+        let step = speed.min(len);
+        body.velocity[0] = d[0] / len * step;
+        body.velocity[2] = d[1] / len * step;
+    }
+    // bodies don't step up by themselves, so even half a block takes a jump
+    let next_floor = next[1] as f32 + floor_height(world, next);
+    if next_floor > body.position[1] + 0.01 && body.on_ground {
+        body.velocity[1] = JUMP_SPEED;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::MobKind;
+    use crate::world::block_model::BlockModels;
+    use crate::world::registry::BlockRegistry;
+    use crate::world::voxel::{BlockType, Voxel};
+
+    // a stone floor at y = 0 from (0, 0) to (size - 1, size - 1), walked on at y = 1
+    fn floor(size: i32) -> World {
+        let mut world = World::new(BlockRegistry::default(), BlockModels::default());
+        for x in 0..size {
+            for z in 0..size {
+                let voxel = Voxel {
+                    block_type: BlockType::Stone,
+                    ..Voxel::default()
+                };
+                world.set_voxel([x, 0, z], voxel);
+            }
+        }
+        world
+    }
+
+    fn brain(entity: &Entity) -> &Brain {
+        entity.brain.as_ref().unwrap()
+    }
+
+    fn hurt(entity: &mut Entity) {
+        if let EntityKind::Mob { health, .. } = &mut entity.kind {
+            *health -= 1;
+        }
+    }
+
+    #[test]
+    fn idle_mobs_wander_off_now_and_then() {
+        let world = floor(16);
+        let mut rng = Rng::new(1);
+        let mut pig = Entity::mob(MobKind::Pig, [8.5, 1.0, 8.5]);
+        let player = [0.5, 1.0, 0.5];
+
+        think(&mut pig, &world, player, &mut rng);
+        assert_eq!(brain(&pig).behaviour, Behaviour::Idle);
+        for _ in 0..1000 {
+            think(&mut pig, &world, player, &mut rng);
+            if !brain(&pig).path.is_empty() {
+                break;
+            }
+        }
+        let path = &brain(&pig).path;
+        assert!(!path.is_empty());
+        let end = path.last().unwrap();
+        assert!((end[0] - 8).abs() <= WANDER_RANGE && (end[2] - 8).abs() <= WANDER_RANGE);
+        assert!(pig.body.velocity[0] != 0.0 || pig.body.velocity[2] != 0.0);
+
+        // the next tick carries on along the same path
+        think(&mut pig, &world, player, &mut rng);
+        assert_eq!(brain(&pig).behaviour, Behaviour::Wander);
+    }
+
+    #[test]
+    fn hurt_animals_run_from_the_player() {
+        let world = floor(32);
+        let mut rng = Rng::new(2);
+        let mut pig = Entity::mob(MobKind::Pig, [16.5, 1.0, 16.5]);
+        let player = [14.5, 1.0, 16.5];
+
+        hurt(&mut pig);
+        think(&mut pig, &world, player, &mut rng);
+        assert_eq!(brain(&pig).behaviour, Behaviour::Flee);
+        let end = *brain(&pig).path.last().unwrap();
+        assert!(end[0] > 16 + FLEE_DISTANCE as i32 / 2);
+        assert!(pig.body.velocity[0] > 0.0);
+
+        // and calm down once they've had time to get away
+        for _ in 0..FLEE_TIME {
+            think(&mut pig, &world, player, &mut rng);
+        }
+        assert_ne!(brain(&pig).behaviour, Behaviour::Flee);
+    }
+
+    #[test]
+    fn hostile_mobs_chase_a_nearby_player() {
+        let world = floor(16);
+        let mut rng = Rng::new(3);
+        let mut zombie = Entity::mob(MobKind::Zombie, [2.5, 1.0, 2.5]);
+
+        think(&mut zombie, &world, [10.5, 1.0, 2.5], &mut rng);
+        assert_eq!(brain(&zombie).behaviour, Behaviour::Chase);
+        assert_eq!(brain(&zombie).path.last(), Some(&[10, 1, 2]));
+
+        // but leave one that's out of range alone
+        let mut zombie = Entity::mob(MobKind::Zombie, [2.5, 1.0, 2.5]);
+        think(&mut zombie, &world, [2.5, 1.0, 40.5], &mut rng);
+        assert_ne!(brain(&zombie).behaviour, Behaviour::Chase);
+
+        // animals never chase
+        let mut pig = Entity::mob(MobKind::Pig, [2.5, 1.0, 2.5]);
+        think(&mut pig, &world, [10.5, 1.0, 2.5], &mut rng);
+        assert_ne!(brain(&pig).behaviour, Behaviour::Chase);
+    }
+
+    #[test]
+    fn mobs_hop_up_onto_slabs_in_their_way() {
+        let mut world = floor(16);
+        for z in 0..16 {
+            let slab = Voxel {
+                block_type: BlockType::PlankSlab,
+                ..Voxel::default()
+            };
+            world.set_voxel([5, 1, z], slab);
+        }
+        let mut rng = Rng::new(6);
+        let mut zombie = Entity::mob(MobKind::Zombie, [2.5, 1.0, 2.5]);
+        let player = [9.5, 1.0, 2.5];
+        for _ in 0..200 {
+            think(&mut zombie, &world, player, &mut rng);
+            zombie.tick(&world);
+        }
+        assert!(zombie.body.position[0] > 8.0);
+    }
+
+    #[test]
+    fn attacks_wait_for_the_cooldown() {
+        let world = floor(4);
+        let mut rng = Rng::new(4);
+        let mut zombie = Entity::mob(MobKind::Zombie, [1.5, 1.0, 1.5]);
+        let player = [2.0, 1.0, 1.5];
+
+        let attacks: Vec<u32> = (0..=ATTACK_COOLDOWN * 2)
+            .map(|_| think(&mut zombie, &world, player, &mut rng).attack)
+            .collect();
+        let hits: Vec<usize> = (0..attacks.len()).filter(|&i| attacks[i] > 0).collect();
+        let cooldown = ATTACK_COOLDOWN as usize;
+        assert_eq!(hits, vec![0, cooldown, cooldown * 2]);
+        assert!(attacks.iter().all(|&a| a == 0 || a == ATTACK_DAMAGE));
+
+        // out of reach it doesn't hit at all
+        let mut zombie = Entity::mob(MobKind::Zombie, [1.5, 1.0, 1.5]);
+        let far = [1.5 + ATTACK_RANGE * 2.0, 1.0, 1.5];
+        assert_eq!(think(&mut zombie, &world, far, &mut rng).attack, 0);
+    }
+
+    #[test]
+    fn hostile_mobs_left_far_behind_despawn() {
+        let world = floor(4);
+        let mut rng = Rng::new(5);
+        let far = [DESPAWN_DISTANCE + 10.0, 1.0, 0.5];
+
+        let mut zombie = Entity::mob(MobKind::Zombie, [0.5, 1.0, 0.5]);
+        assert!(think(&mut zombie, &world, far, &mut rng).despawn);
+        assert!(!think(&mut zombie, &world, [0.5, 1.0, 8.5], &mut rng).despawn);
+
+        let mut pig = Entity::mob(MobKind::Pig, [0.5, 1.0, 0.5]);
+        assert!(!think(&mut pig, &world, far, &mut rng).despawn);
+    }
+}
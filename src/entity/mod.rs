@@ -0,0 +1,533 @@
+pub mod ai;
+pub mod path;
+pub mod spawn;
+
+use std::collections::BTreeMap;
+
+use crate::entity::ai::Brain;
+use crate::item::{ItemId, ItemStack};
+use crate::support::rng::Rng;
+use crate::world::chunk::{ChunkDecodeError, Reader};
+use crate::world::collision::{Aabb, Body};
+use crate::world::mesher::ChunkMesh;
+use crate::world::{split_pos, ChunkPos, World};
+
+// per tick, at 20 ticks a second
+const ITEM_GRAVITY: f32 = 0.04;
+const ARROW_GRAVITY: f32 = 0.05;
+const MOB_GRAVITY: f32 = 0.08;
+const TERMINAL_VELOCITY: f32 = 1.0;
+const AIR_DRAG: f32 = 0.98;
+const GROUND_FRICTION: f32 = 0.6;
+// dropped items can't be picked straight back up, about half a second
+const PICKUP_DELAY: u32 = 10;
+// five minutes for items, one for arrows stuck in something
+const ITEM_LIFETIME: u32 = 20 * 60 * 5;
+const ARROW_LIFETIME: u32 = 20 * 60;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EntityId(pub u32);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MobKind {
+    Pig,
+    Cow,
+    Sheep,
+    Chicken,
+    Zombie,
+}
+
+impl MobKind {
+    pub const ALL: [MobKind; 5] = [
+        MobKind::Pig,
+        MobKind::Cow,
+        MobKind::Sheep,
+        MobKind::Chicken,
+        MobKind::Zombie,
+    ];
+
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_id(id: u8) -> Option<MobKind> {
+        MobKind::ALL.get(id as usize).copied()
+    }
+
+    pub fn max_health(self) -> u32 {
+        match self {
+            MobKind::Pig | MobKind::Sheep => 8,
+            MobKind::Cow => 10,
+            MobKind::Chicken => 4,
+            MobKind::Zombie => 20,
+        }
+    }
+
+    // hostile mobs chase the player instead of running away from them
+    pub fn is_hostile(self) -> bool {
+        self == MobKind::Zombie
+    }
+
+    // walking speed in blocks per tick
+    pub fn speed(self) -> f32 {
+        match self {
+            MobKind::Chicken => 0.08,
+            MobKind::Zombie => 0.11,
+            _ => 0.09,
+        }
+    }
+
+    // half width and height
+    fn size(self) -> (f32, f32) {
+        match self {
+            MobKind::Pig => (0.45, 0.9),
+            MobKind::Cow => (0.45, 1.4),
+            MobKind::Sheep => (0.45, 1.3),
+            MobKind::Chicken => (0.2, 0.7),
+            MobKind::Zombie => (0.3, 1.95),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum EntityKind {
+    Item(ItemStack),
+    Mob { kind: MobKind, health: u32 },
+    Arrow { damage: u32, stuck: bool },
+}
+
+impl EntityKind {
+    // half width and height of the collision box
+    fn size(&self) -> (f32, f32) {
+        match self {
+            EntityKind::Item(_) => (0.125, 0.25),
+            EntityKind::Mob { kind, .. } => kind.size(),
+            EntityKind::Arrow { .. } => (0.05, 0.1),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entity {
+    pub kind: EntityKind,
+    pub body: Body,
+    pub age: u32,
+    // only mobs think
+    pub brain: Option<Brain>,
+}
+
+impl Entity {
+    pub fn new(kind: EntityKind, position: [f32; 3], velocity: [f32; 3]) -> Entity {
+        let (half_width, height) = kind.size();
+        let mut body = Body::new(position, half_width, height);
+        body.velocity = velocity;
+        let brain = match kind {
+            EntityKind::Mob { health, .. } => Some(Brain::new(health)),
+            _ => None,
+        };
+        Entity {
+            kind,
+            body,
+            age: 0,
+            brain,
+        }
+    }
+
+    pub fn item(stack: ItemStack, position: [f32; 3], velocity: [f32; 3]) -> Entity {
+        Entity::new(EntityKind::Item(stack), position, velocity)
+    }
+
+    pub fn mob(kind: MobKind, position: [f32; 3]) -> Entity {
+        let health = kind.max_health();
+        Entity::new(EntityKind::Mob { kind, health }, position, [0.0; 3])
+    }
+
+    pub fn arrow(position: [f32; 3], velocity: [f32; 3], damage: u32) -> Entity {
+        let kind = EntityKind::Arrow {
+            damage,
+            stuck: false,
+        };
+        Entity::new(kind, position, velocity)
+    }
+
+    // the boxes to draw it with, relative to its position. this is where each kind of
+    // entity decides what it looks like
+    pub fn model(&self) -> Vec<Aabb> {
+        let (w, h) = self.kind.size();
+        match self.kind {
+            EntityKind::Item(_) | EntityKind::Arrow { .. } => {
+                vec![Aabb::new([-w, 0.0, -w], [w, h, w])]
+            }
+            EntityKind::Mob { kind, .. } => {
+                // a body with a head on the front. facing isn't tracked yet so the head
+                // always points along +z
+                let head = match kind {
+                    MobKind::Chicken => 0.15,
+                    _ => 0.25,
+                };
+                let body_top = h - head;
+                // zombies stand upright with their legs under them
+                let body_bottom = if kind == MobKind::Zombie {
+                    0.0
+                } else {
+                    h * 0.3
+                };
+                vec![
+                    Aabb::new([-w, body_bottom, -w], [w, body_top, w]),
+                    Aabb::new([-head, body_top - head, w], [head, h, w + head * 1.5]),
+                ]
+            }
+        }
+    }
+
+    fn tick(&mut self, world: &World) {
+        self.age += 1;
+        let body = &mut self.body;
+        body.previous_position = body.position;
+        match &mut self.kind {
+            EntityKind::Item(_) => {
+                body.velocity[1] = (body.velocity[1] - ITEM_GRAVITY).max(-TERMINAL_VELOCITY);
+                body.step(world);
+                if body.on_ground {
+                    body.velocity[0] *= GROUND_FRICTION;
+                    body.velocity[2] *= GROUND_FRICTION;
+                }
+            }
+            EntityKind::Mob { .. } => {
+                // gravity after moving like the player, so mobs can jump up a block
+                body.step(world);
+                body.velocity[1] = (body.velocity[1] - MOB_GRAVITY) * AIR_DRAG;
+                if body.on_ground {
+                    body.velocity[0] *= GROUND_FRICTION;
+                    body.velocity[2] *= GROUND_FRICTION;
+                }
+            }
+            EntityKind::Arrow { stuck, .. } => {
+                if *stuck {
+                    return;
+                }
+                body.velocity[1] -= ARROW_GRAVITY;
+                body.velocity = body.velocity.map(|v| v * AIR_DRAG);
+                // arrows stop dead in whatever they hit
+                if body.step(world).blocked.contains(&true) {
+                    body.velocity = [0.0; 3];
+                    *stuck = true;
+                    self.age = 0;
+                }
+            }
+        }
+    }
+
+    fn is_expired(&self) -> bool {
+        match self.kind {
+            EntityKind::Item(stack) => stack.count == 0 || self.age >= ITEM_LIFETIME,
+            EntityKind::Mob { health, .. } => health == 0,
+            EntityKind::Arrow { stuck, .. } => stuck && self.age >= ARROW_LIFETIME,
+        }
+    }
+
+    // layout: kind tag, position, velocity, age, then whatever the kind needs
+    pub fn encode(&self, bytes: &mut Vec<u8>) {
+        let tag = match self.kind {
+            EntityKind::Item(_) => 0,
+            EntityKind::Mob { .. } => 1,
+            EntityKind::Arrow { .. } => 2,
+        };
+        bytes.push(tag);
+        for v in self.body.position.iter().chain(&self.body.velocity) {
+            bytes.extend(v.to_le_bytes());
+        }
+        bytes.extend(self.age.to_le_bytes());
+
+        match self.kind {
+            EntityKind::Item(stack) => {
+                bytes.extend(stack.item.0.to_le_bytes());
+                bytes.extend(stack.count.to_le_bytes());
+                bytes.extend(stack.max_stack.to_le_bytes());
+            }
+            EntityKind::Mob { kind, health } => {
+                bytes.push(kind.id());
+                bytes.extend(health.to_le_bytes());
+            }
+            EntityKind::Arrow { damage, stuck } => {
+                bytes.extend(damage.to_le_bytes());
+                bytes.push(stuck as u8);
+            }
+        }
+    }
+
+    pub(crate) fn decode(reader: &mut Reader) -> Result<Entity, ChunkDecodeError> {
+        let tag = reader.u8()?;
+        let position = [reader.f32()?, reader.f32()?, reader.f32()?];
+        let velocity = [reader.f32()?, reader.f32()?, reader.f32()?];
+        let age = reader.u32()?;
+
+        let kind = match tag {
+            0 => EntityKind::Item(ItemStack::new(
+                ItemId(reader.u16()?),
+                reader.u32()?,
+                reader.u32()?,
+            )),
+            1 => {
+                let id = reader.u8()?;
+                let kind = MobKind::from_id(id).ok_or(ChunkDecodeError::UnknownMob(id))?;
+                EntityKind::Mob {
+                    kind,
+                    health: reader.u32()?,
+                }
+            }
+            2 => EntityKind::Arrow {
+                damage: reader.u32()?,
+                stuck: reader.u8()? != 0,
+            },
+            _ => return Err(ChunkDecodeError::UnknownEntity(tag)),
+        };
+
+        let mut entity = Entity::new(kind, position, velocity);
+        entity.age = age;
+        Ok(entity)
+    }
+}
+
+// every loaded entity. ids are never reused
+#[derive(Default)]
+pub struct Entities {
+    next_id: u32,
+    entities: BTreeMap<EntityId, Entity>,
+}
+
+impl Entities {
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    pub fn spawn(&mut self, entity: Entity) -> EntityId {
+        let id = EntityId(self.next_id);
+        self.next_id += 1;
+        self.entities.insert(id, entity);
+        id
+    }
+
+    pub fn get(&self, id: EntityId) -> Option<&Entity> {
+        self.entities.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: EntityId) -> Option<&mut Entity> {
+        self.entities.get_mut(&id)
+    }
+
+    pub fn remove(&mut self, id: EntityId) -> Option<Entity> {
+        self.entities.remove(&id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (EntityId, &Entity)> {
+        self.entities.iter().map(|(&id, entity)| (id, entity))
+    }
+
+    pub fn tick(&mut self, world: &World) {
+        for entity in self.entities.values_mut() {
+            entity.tick(world);
+        }
+        self.hit_with_arrows();
+        self.entities.retain(|_, entity| !entity.is_expired());
+    }
+
+    // runs every mob's ai ahead of the physics tick, dropping hostile mobs that have
+    // been left far behind. returns the damage they did to the player at `player`
+    pub fn think(&mut self, world: &World, player: [f32; 3], rng: &mut Rng) -> u32 {
+        let mut damage = 0;
+        self.entities.retain(|_, entity| {
+            let decision = ai::think(entity, world, player, rng);
+            damage += decision.attack;
+            !decision.despawn
+        });
+        damage
+    }
+
+    // flying arrows that end up inside a mob hurt it and are used up
+    fn hit_with_arrows(&mut self) {
+        let arrows: Vec<(EntityId, Aabb, u32)> = self
+            .entities
+            .iter()
+            .filter_map(|(&id, entity)| match entity.kind {
+                EntityKind::Arrow {
+                    damage,
+                    stuck: false,
+                } => Some((id, entity.body.aabb(), damage)),
+                _ => None,
+            })
+            .collect();
+
+        for (arrow, aabb, damage) in arrows {
+            let target = self.entities.values_mut().find(|entity| {
+                matches!(entity.kind, EntityKind::Mob { .. })
+                    && entity.body.aabb().intersects(&aabb)
+            });
+            if let Some(Entity {
+                kind: EntityKind::Mob { health, .. },
+                ..
+            }) = target
+            {
+                *health = health.saturating_sub(damage);
+                self.entities.remove(&arrow);
+            }
+        }
+    }
+
+    // hands every item entity within `radius` of `position` to `take`, which returns
+    // whatever it couldn't hold. leftovers stay on the ground
+    pub fn pick_up_items(
+        &mut self,
+        position: [f32; 3],
+        radius: f32,
+        mut take: impl FnMut(ItemStack) -> Option<ItemStack>,
+    ) {
+        for entity in self.entities.values_mut() {
+            let EntityKind::Item(stack) = &mut entity.kind else {
+                continue;
+            };
+            if entity.age < PICKUP_DELAY {
+                continue;
+            }
+            let p = entity.body.position;
+            let d = [p[0] - position[0], p[1] - position[1], p[2] - position[2]];
+            if d[0] * d[0] + d[1] * d[1] + d[2] * d[2] > radius * radius {
+                continue;
+            }
+            match take(*stack) {
+                Some(left) => *stack = left,
+                None => stack.count = 0,
+            }
+        }
+        self.entities.retain(|_, entity| !entity.is_expired());
+    }
+
+    // moves every entity standing in a chunk into that chunk's saved data, ready for the
+    // chunk to be written out or unloaded
+    pub fn store_in_chunk(&mut self, world: &mut World, chunk_pos: ChunkPos) {
+        let Some(chunk) = world.chunks.get_mut(&chunk_pos) else {
+            return;
+        };
+        let inside: Vec<EntityId> = self
+            .entities
+            .iter()
+            .filter(|(_, entity)| entity_chunk(entity) == chunk_pos)
+            .map(|(&id, _)| id)
+            .collect();
+        for id in inside {
+            if let Some(entity) = self.entities.remove(&id) {
+                chunk.entities.push(entity);
+            }
+        }
+    }
+
+    // brings a loaded chunk's saved entities back to life
+    pub fn restore_from_chunk(&mut self, world: &mut World, chunk_pos: ChunkPos) {
+        let Some(chunk) = world.chunks.get_mut(&chunk_pos) else {
+            return;
+        };
+        for entity in std::mem::take(&mut chunk.entities) {
+            self.spawn(entity);
+        }
+    }
+
+    // every entity's model in world space, `alpha` of the way through the current tick
+    pub fn mesh(&self, alpha: f32) -> ChunkMesh {
+        let mut mesh = ChunkMesh::default();
+        for entity in self.entities.values() {
+            let position = entity.body.interpolated(alpha);
+            for b in entity.model() {
+                mesh.push_aabb(&b.offset(position));
+            }
+        }
+        mesh
+    }
+}
+
+fn entity_chunk(entity: &Entity) -> ChunkPos {
+    split_pos(entity.body.position.map(|v| v.floor() as i32)).0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::chunk::Chunk;
+
+    fn one_of_each() -> Vec<Entity> {
+        let stack = ItemStack::new(ItemId(7), 12, 64);
+        let mut item = Entity::item(stack, [1.5, 2.0, 3.25], [0.1, 0.2, -0.1]);
+        item.age = 42;
+        let mut mob = Entity::mob(MobKind::Cow, [-4.5, 10.0, 20.5]);
+        if let EntityKind::Mob { health, .. } = &mut mob.kind {
+            *health = 3;
+        }
+        // brains aren't saved, a loaded mob starts with a fresh one
+        mob.brain = Some(Brain::new(3));
+        let mut arrow = Entity::arrow([0.5, 64.0, 0.5], [0.0, -0.5, 1.0], 5);
+        arrow.kind = EntityKind::Arrow {
+            damage: 5,
+            stuck: true,
+        };
+        vec![item, mob, arrow]
+    }
+
+    #[test]
+    fn entities_survive_a_round_trip_through_a_chunk() {
+        let mut chunk = Chunk::new();
+        chunk.entities = one_of_each();
+        let read = Chunk::from_bytes(&chunk.to_bytes()).unwrap();
+        assert_eq!(read.entities, chunk.entities);
+    }
+
+    #[test]
+    fn cut_off_entities_are_an_error() {
+        let mut chunk = Chunk::new();
+        chunk.entities = one_of_each();
+        let bytes = chunk.to_bytes();
+        let without = Chunk::new().to_bytes().len();
+        for len in without..bytes.len() {
+            assert_eq!(
+                Chunk::from_bytes(&bytes[..len]).err(),
+                Some(ChunkDecodeError::UnexpectedEnd),
+                "{len} bytes"
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_mobs_report_their_id() {
+        let mut chunk = Chunk::new();
+        chunk.entities = vec![Entity::mob(MobKind::Pig, [0.0; 3])];
+        let mut bytes = chunk.to_bytes();
+        // the mob kind is followed by its four byte health, then the chunk's tint
+        let at = bytes.len() - 5 - 12;
+        assert_eq!(bytes[at], MobKind::Pig.id());
+        bytes[at] = 200;
+        assert_eq!(
+            Chunk::from_bytes(&bytes).err(),
+            Some(ChunkDecodeError::UnknownMob(200))
+        );
+    }
+
+    #[test]
+    fn entities_are_stored_with_their_chunk_and_come_back() {
+        let mut world = World::default();
+        world.chunks.insert([0, 0, 0], Chunk::new());
+        world.chunks.insert([1, 0, 0], Chunk::new());
+        let mut entities = Entities::default();
+        entities.spawn(Entity::mob(MobKind::Pig, [5.5, 1.0, 5.5]));
+        entities.spawn(Entity::mob(MobKind::Cow, [40.5, 1.0, 5.5]));
+
+        entities.store_in_chunk(&mut world, [0, 0, 0]);
+        assert_eq!(entities.len(), 1);
+        assert_eq!(world.chunks[&[0, 0, 0]].entities.len(), 1);
+
+        entities.restore_from_chunk(&mut world, [0, 0, 0]);
+        assert_eq!(entities.len(), 2);
+        assert!(world.chunks[&[0, 0, 0]].entities.is_empty());
+    }
+}
@@ -0,0 +1,503 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::world::block_state::Property;
+use crate::world::collision::Aabb;
+use crate::world::fluid::FluidKind;
+use crate::world::World;
+
+// costs are in tenths of a block walked so they stay whole numbers
+const WALK_COST: u32 = 10;
+const JUMP_COST: u32 = 20;
+const FALL_COST_PER_BLOCK: u32 = 5;
+const WATER_COST: u32 = 50;
+const DOOR_COST: u32 = 20;
+
+const NEIGHBOURS: [[i32; 2]; 4] = [[1, 0], [-1, 0], [0, 1], [0, -1]];
+// blocks no taller than this, like slabs, are stepped onto rather than jumped
+const STEP_HEIGHT: f32 = 0.5;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PathRules {
+    // how many blocks tall the walker is
+    pub height: i32,
+    // the furthest it'll drop off a ledge
+    pub max_fall: i32,
+    // gives up after looking at this many positions
+    pub max_nodes: usize,
+}
+
+impl Default for PathRules {
+    fn default() -> Self {
+        PathRules {
+            height: 2,
+            max_fall: 3,
+            max_nodes: 2000,
+        }
+    }
+}
+
+// the extra cost of having a body in this block, or None if it can't be walked through
+fn passable(world: &World, pos: [i32; 3]) -> Option<u32> {
+    let voxel = world.get_voxel(pos);
+    match FluidKind::of(voxel.block_type) {
+        Some(FluidKind::Lava) => return None,
+        Some(FluidKind::Water) => return Some(WATER_COST),
+        None => (),
+    }
+    if world.models.collision_boxes(&voxel).is_empty() {
+        return Some(0);
+    }
+    // doors and gates are fine once they're open
+    let openable = world.blocks.has_property(voxel.block_type, Property::Open);
+    (openable && voxel.state.is_open()).then_some(DOOR_COST)
+}
+
+// the top of the highest thing in the block that collides, 0 if nothing does
+fn collision_top(world: &World, pos: [i32; 3]) -> f32 {
+    let voxel = world.get_voxel(pos);
+    let boxes = world.models.collision_boxes(&voxel);
+    boxes.iter().fold(0.0, |top, b| top.max(b.max[1]))
+}
+
+// like collision_top, but only what's in the half of the block nearest something coming
+// in along `dir`. stairs come out half a block tall from their low side
+fn edge_top(world: &World, pos: [i32; 3], [dx, dz]: [i32; 2]) -> f32 {
+    let voxel = world.get_voxel(pos);
+    let boxes = world.models.collision_boxes(&voxel);
+    let near = |b: &&Aabb| match (dx, dz) {
+        (1, _) => b.min[0] < 0.5,
+        (-1, _) => b.max[0] > 0.5,
+        (_, 1) => b.min[2] < 0.5,
+        _ => b.max[2] > 0.5,
+    };
+    boxes
+        .iter()
+        .filter(near)
+        .fold(0.0, |top, b| top.max(b.max[1]))
+}
+
+// how far up its own block something with its feet in `pos` stands. more than 0 when
+// it's on top of a slab or something else low enough to step onto
+pub(crate) fn floor_height(world: &World, pos: [i32; 3]) -> f32 {
+    let top = collision_top(world, pos);
+    if top <= STEP_HEIGHT {
+        top
+    } else {
+        0.0
+    }
+}
+
+// whether something can stand in the block on top of this one. low blocks are stood in
+// rather than on, see floor_height, and anything that collides taller than a block, like
+// a fence, can't be stood on
+fn supports(world: &World, pos: [i32; 3]) -> bool {
+    let top = collision_top(world, pos);
+    top > STEP_HEIGHT && top <= 1.0
+}
+
+fn above(pos: [i32; 3], by: i32) -> [i32; 3] {
+    [pos[0], pos[1] + by, pos[2]]
+}
+
+// the cost of fitting the whole body in with its feet at `pos`
+fn body_cost(world: &World, pos: [i32; 3], rules: &PathRules) -> Option<u32> {
+    (0..rules.height).try_fold(0, |cost, y| Some(cost + passable(world, above(pos, y))?))
+}
+
+// a place something can stand: room for its body, and either ground underneath or water
+// to swim in
+pub(crate) fn standable(world: &World, pos: [i32; 3], rules: &PathRules) -> Option<u32> {
+    // standing on a low block puts the head a block higher
+    if floor_height(world, pos) > 0.0 {
+        return body_cost(world, above(pos, 1), rules);
+    }
+    let cost = body_cost(world, pos, rules)?;
+    let swimming = FluidKind::of(world.get_voxel(pos).block_type) == Some(FluidKind::Water);
+    (swimming || supports(world, above(pos, -1))).then_some(cost)
+}
+
+// the height of the feet of something standing at `pos`
+fn floor(world: &World, pos: [i32; 3]) -> f32 {
+    pos[1] as f32 + floor_height(world, pos)
+}
+
+// whether there's room to jump or step up from `pos` without hitting anything overhead
+fn headroom(world: &World, pos: [i32; 3], rules: &PathRules) -> bool {
+    let raised = (floor_height(world, pos) > 0.0) as i32;
+    passable(world, above(pos, rules.height + raised)).is_some()
+}
+
+// every position reachable in one move from `pos`, with what it costs to get there
+fn moves(world: &World, pos: [i32; 3], rules: &PathRules) -> Vec<([i32; 3], u32)> {
+    let mut moves = Vec::new();
+    let here = floor(world, pos);
+    for [dx, dz] in NEIGHBOURS {
+        let next = [pos[0] + dx, pos[1], pos[2] + dz];
+
+        // straight across, stepping on or off anything low on the way
+        if let Some(cost) = standable(world, next, rules) {
+            if floor(world, next) <= here || headroom(world, pos, rules) {
+                moves.push((next, WALK_COST + cost));
+            }
+            continue;
+        }
+
+        // up a block, which is only a step from the top of a slab and two up the low
+        // side of stairs, and a jump otherwise. it needs headroom over where it's going
+        // up from either way
+        let up = above(next, 1);
+        if let Some(cost) = standable(world, up, rules) {
+            let rise = floor(world, up) - here;
+            if rise <= 1.0 && headroom(world, pos, rules) {
+                let halfway = edge_top(world, next, [dx, dz]) - floor_height(world, pos);
+                let climb = if rise <= STEP_HEIGHT || halfway <= STEP_HEIGHT {
+                    WALK_COST
+                } else {
+                    JUMP_COST
+                };
+                moves.push((up, climb + cost));
+            }
+            continue;
+        }
+
+        // off a ledge, as long as the way down is clear and not too far
+        if body_cost(world, next, rules).is_none() {
+            continue;
+        }
+        for drop in 1..=rules.max_fall {
+            let down = above(next, -drop);
+            if passable(world, down).is_none() {
+                break;
+            }
+            if let Some(cost) = standable(world, down, rules) {
+                moves.push((down, WALK_COST + FALL_COST_PER_BLOCK * drop as u32 + cost));
+                break;
+            }
+        }
+    }
+    moves
+}
+
+// somewhere to stand in the column at `pos`, looking a little above and below it
+pub fn ground_near(world: &World, pos: [i32; 3], rules: &PathRules) -> Option<[i32; 3]> {
+    (-rules.max_fall..=2)
+        .rev()
+        .map(|dy| above(pos, dy))
+        .find(|&p| standable(world, p, rules).is_some())
+}
+
+fn estimate(a: [i32; 3], b: [i32; 3]) -> u32 {
+    let d = (a[0] - b[0]).abs() + (a[1] - b[1]).abs() + (a[2] - b[2]).abs();
+    d as u32 * WALK_COST
+}
+
+// a* over block positions, where a position is the block the walker's feet are in.
+// returns every position along the way after `start`, ending at `goal`
+pub fn find_path(
+    world: &World,
+    start: [i32; 3],
+    goal: [i32; 3],
+    rules: &PathRules,
+) -> Option<Vec<[i32; 3]>> {
+    if start == goal {
+        return Some(Vec::new());
+    }
+    standable(world, goal, rules)?;
+
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<[i32; 3], [i32; 3]> = HashMap::new();
+    let mut best: HashMap<[i32; 3], u32> = HashMap::new();
+    best.insert(start, 0);
+    open.push(Reverse((estimate(start, goal), 0, start)));
+
+    let mut visited = 0;
+    while let Some(Reverse((_, cost, pos))) = open.pop() {
+        if pos == goal {
+            let mut path = vec![pos];
+            let mut at = pos;
+            while let Some(&previous) = came_from.get(&at) {
+                if previous == start {
+                    break;
+                }
+                path.push(previous);
+                at = previous;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        // a cheaper way here was already found
+        if best.get(&pos).is_some_and(|&b| b < cost) {
+            continue;
+        }
+        visited += 1;
+        if visited > rules.max_nodes {
+            return None;
+        }
+
+        for (next, step) in moves(world, pos, rules) {
+            let next_cost = cost + step;
+            if best.get(&next).is_some_and(|&b| b <= next_cost) {
+                continue;
+            }
+            best.insert(next, next_cost);
+            came_from.insert(next, pos);
+            open.push(Reverse((next_cost + estimate(next, goal), next_cost, next)));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::block_model::BlockModels;
+    use crate::world::registry::{BlockDef, BlockRegistry};
+    use crate::world::voxel::{BlockType, Voxel};
+
+    fn voxel(block_type: BlockType) -> Voxel {
+        Voxel {
+            block_type,
+            ..Voxel::default()
+        }
+    }
+
+    // a stone floor at y = 0 from (0, 0) to (size - 1, size - 1), walked on at y = 1
+    fn floor(size: i32) -> World {
+        let mut world = World::new(BlockRegistry::default(), BlockModels::default());
+        for x in 0..size {
+            for z in 0..size {
+                world.set_voxel([x, 0, z], voxel(BlockType::Stone));
+            }
+        }
+        world
+    }
+
+    fn wall(world: &mut World, x: i32, height: i32, size: i32) {
+        for z in 0..size {
+            for y in 1..=height {
+                world.set_voxel([x, y, z], voxel(BlockType::Stone));
+            }
+        }
+    }
+
+    fn rules() -> PathRules {
+        PathRules::default()
+    }
+
+    #[test]
+    fn walks_straight_across_flat_ground() {
+        let world = floor(8);
+        let path = find_path(&world, [0, 1, 0], [5, 1, 0], &rules()).unwrap();
+        assert_eq!(path.len(), 5);
+        assert_eq!(path.last(), Some(&[5, 1, 0]));
+        assert!(path.iter().all(|p| p[1] == 1));
+    }
+
+    #[test]
+    fn each_step_is_next_to_the_last() {
+        let world = floor(8);
+        let path = find_path(&world, [0, 1, 0], [6, 1, 4], &rules()).unwrap();
+        let mut at = [0, 1, 0];
+        for p in path {
+            let d = (p[0] - at[0]).abs() + (p[2] - at[2]).abs();
+            assert_eq!(d, 1);
+            at = p;
+        }
+        assert_eq!(at, [6, 1, 4]);
+    }
+
+    #[test]
+    fn jumps_up_single_blocks() {
+        let mut world = floor(8);
+        wall(&mut world, 3, 1, 8);
+        let path = find_path(&world, [0, 1, 0], [3, 2, 0], &rules()).unwrap();
+        assert_eq!(path, vec![[1, 1, 0], [2, 1, 0], [3, 2, 0]]);
+    }
+
+    #[test]
+    fn cant_jump_two_blocks() {
+        let mut world = floor(8);
+        wall(&mut world, 3, 2, 8);
+        assert_eq!(find_path(&world, [0, 1, 0], [5, 1, 0], &rules()), None);
+    }
+
+    #[test]
+    fn goes_round_a_wall_with_a_gap() {
+        let mut world = floor(8);
+        wall(&mut world, 3, 2, 7);
+        let path = find_path(&world, [0, 1, 0], [5, 1, 0], &rules()).unwrap();
+        assert!(path.contains(&[3, 1, 7]));
+        assert_eq!(path.last(), Some(&[5, 1, 0]));
+    }
+
+    #[test]
+    fn drops_off_ledges_it_can_survive() {
+        let mut world = floor(8);
+        // a raised platform three blocks up along x = 0..2
+        for x in 0..3 {
+            for y in 1..=3 {
+                world.set_voxel([x, y, 0], voxel(BlockType::Stone));
+            }
+        }
+        let path = find_path(&world, [0, 4, 0], [5, 1, 0], &rules()).unwrap();
+        assert_eq!(path.last(), Some(&[5, 1, 0]));
+        assert!(path.windows(2).any(|step| step[0][1] - step[1][1] == 3));
+
+        let timid = PathRules {
+            max_fall: 2,
+            ..rules()
+        };
+        assert_eq!(find_path(&world, [0, 4, 0], [5, 1, 0], &timid), None);
+    }
+
+    #[test]
+    fn needs_headroom() {
+        let mut world = floor(3);
+        // a one block high tunnel is too low for a two block tall walker
+        for x in 0..3 {
+            world.set_voxel([x, 2, 1], voxel(BlockType::Stone));
+        }
+        for x in 0..3 {
+            world.set_voxel([x, 1, 0], voxel(BlockType::Stone));
+            world.set_voxel([x, 1, 2], voxel(BlockType::Stone));
+        }
+        assert_eq!(find_path(&world, [0, 1, 1], [2, 1, 1], &rules()), None);
+
+        let short = PathRules {
+            height: 1,
+            ..rules()
+        };
+        assert!(find_path(&world, [0, 1, 1], [2, 1, 1], &short).is_some());
+    }
+
+    #[test]
+    fn prefers_going_round_water() {
+        let mut world = floor(8);
+        for z in 0..2 {
+            world.set_voxel([3, 1, z], voxel(BlockType::Water));
+        }
+        let path = find_path(&world, [0, 1, 0], [6, 1, 0], &rules()).unwrap();
+        assert!(path
+            .iter()
+            .all(|p| world.get_voxel(*p).block_type != BlockType::Water));
+
+        // with no way round it swims
+        for z in 2..8 {
+            world.set_voxel([3, 1, z], voxel(BlockType::Water));
+        }
+        let path = find_path(&world, [0, 1, 0], [6, 1, 0], &rules()).unwrap();
+        assert!(path.contains(&[3, 1, 0]));
+    }
+
+    #[test]
+    fn never_walks_through_lava() {
+        let mut world = floor(8);
+        for z in 0..8 {
+            world.set_voxel([3, 1, z], voxel(BlockType::Lava));
+        }
+        assert_eq!(find_path(&world, [0, 1, 0], [6, 1, 0], &rules()), None);
+    }
+
+    #[test]
+    fn gives_up_on_unreachable_goals() {
+        let world = floor(4);
+        // floating in the air, nothing to stand on
+        assert_eq!(find_path(&world, [0, 1, 0], [2, 5, 2], &rules()), None);
+        assert_eq!(
+            find_path(&world, [1, 1, 1], [1, 1, 1], &rules()),
+            Some(Vec::new())
+        );
+    }
+
+    #[test]
+    fn steps_over_slabs_and_up_slab_stairs() {
+        let mut world = floor(8);
+        for z in 0..8 {
+            world.set_voxel([3, 1, z], voxel(BlockType::PlankSlab));
+        }
+        // stood in at half a block up, as cheap as walking
+        let path = find_path(&world, [0, 1, 0], [5, 1, 0], &rules()).unwrap();
+        assert_eq!(
+            path,
+            vec![[1, 1, 0], [2, 1, 0], [3, 1, 0], [4, 1, 0], [5, 1, 0]]
+        );
+
+        // a slab in front of a full block is a step up onto it, not a jump
+        for z in 0..8 {
+            world.set_voxel([4, 1, z], voxel(BlockType::Stone));
+        }
+        let step = ([4, 2, 0], WALK_COST);
+        assert!(moves(&world, [3, 1, 0], &rules()).contains(&step));
+        let path = find_path(&world, [0, 1, 0], [4, 2, 0], &rules()).unwrap();
+        assert_eq!(path, vec![[1, 1, 0], [2, 1, 0], [3, 1, 0], [4, 2, 0]]);
+
+        // but a slab on top of a block is a block and a half up
+        world.set_voxel([4, 2, 0], voxel(BlockType::PlankSlab));
+        assert!(!moves(&world, [2, 1, 0], &rules())
+            .iter()
+            .any(|(to, _)| to[1] > 1));
+    }
+
+    #[test]
+    fn walks_up_stairs_from_their_low_side_only() {
+        let mut world = floor(8);
+        // the tall half is on the north, -z, side
+        world.set_voxel([3, 1, 3], voxel(BlockType::PlankStairs));
+        let top = [3, 2, 3];
+        assert!(moves(&world, [3, 1, 4], &rules()).contains(&(top, WALK_COST)));
+        assert!(moves(&world, [3, 1, 2], &rules()).contains(&(top, JUMP_COST)));
+        assert!(moves(&world, [2, 1, 3], &rules()).contains(&(top, JUMP_COST)));
+    }
+
+    #[test]
+    fn cant_get_over_a_fence_line() {
+        let mut world = floor(8);
+        world.models = BlockModels::load("assets/data/block_models.ron").unwrap();
+        for z in 0..8 {
+            world.set_voxel([3, 1, z], voxel(BlockType::Fence));
+        }
+        assert_eq!(find_path(&world, [0, 1, 0], [5, 1, 0], &rules()), None);
+
+        // a plain block in the line can be jumped onto and over
+        world.set_voxel([3, 1, 4], voxel(BlockType::Stone));
+        let path = find_path(&world, [0, 1, 0], [5, 1, 0], &rules()).unwrap();
+        assert!(path.contains(&[3, 2, 4]));
+    }
+
+    #[test]
+    fn goes_through_open_doors_only() {
+        let mut world = floor(8);
+        // planks standing in for a door, since nothing in the data files opens yet
+        let mut properties = HashMap::new();
+        properties.insert(Property::Open, 0);
+        world.blocks.register(
+            BlockType::Planks,
+            BlockDef {
+                properties,
+                ..BlockDef::default()
+            },
+        );
+        let closed = voxel(BlockType::Planks);
+        let open = Voxel {
+            state: closed.state.with(Property::Open, 1),
+            ..closed
+        };
+        world.set_voxel([0, 5, 0], closed);
+        assert_eq!(passable(&world, [0, 5, 0]), None);
+        world.set_voxel([0, 5, 0], open);
+        assert_eq!(passable(&world, [0, 5, 0]), Some(DOOR_COST));
+
+        // a wall of closed doors two high, too tall to jump
+        for z in 0..8 {
+            world.set_voxel([3, 1, z], closed);
+            world.set_voxel([3, 2, z], closed);
+        }
+        assert_eq!(find_path(&world, [0, 1, 0], [5, 1, 0], &rules()), None);
+
+        world.set_voxel([3, 1, 6], open);
+        world.set_voxel([3, 2, 6], open);
+        let path = find_path(&world, [0, 1, 0], [5, 1, 0], &rules()).unwrap();
+        assert!(path.contains(&[3, 1, 6]));
+    }
+}
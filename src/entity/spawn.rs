@@ -0,0 +1,282 @@
+use crate::entity::path::{standable, PathRules};
+use crate::entity::{Entities, Entity, EntityId, EntityKind, MobKind};
+use crate::support::rng::Rng;
+use crate::world::fluid::FluidKind;
+use crate::world::light::light_level;
+use crate::world::{split_pos, World};
+use crate::worldgen::biome::Biome;
+
+// passive mobs want at least this much light, hostile ones at most `HOSTILE_LIGHT`
+const PASSIVE_LIGHT: u8 = 9;
+const HOSTILE_LIGHT: u8 = 7;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SpawnRules {
+    // random positions tried each tick
+    pub attempts: u32,
+    // horizontal distance from the player, in blocks
+    pub min_distance: i32,
+    pub max_distance: i32,
+    // how far above or below the player's feet
+    pub vertical: i32,
+    pub passive_cap: usize,
+    pub hostile_cap: usize,
+}
+
+impl Default for SpawnRules {
+    fn default() -> Self {
+        SpawnRules {
+            attempts: 4,
+            min_distance: 24,
+            max_distance: 64,
+            vertical: 16,
+            passive_cap: 10,
+            hostile_cap: 20,
+        }
+    }
+}
+
+// which mobs turn up where
+fn spawn_table(biome: Biome, hostile: bool) -> &'static [MobKind] {
+    if hostile {
+        return &[MobKind::Zombie];
+    }
+    match biome {
+        Biome::Plains => &[MobKind::Pig, MobKind::Cow, MobKind::Sheep, MobKind::Chicken],
+        Biome::Forest => &[MobKind::Pig, MobKind::Sheep, MobKind::Chicken],
+        Biome::Tundra | Biome::Mountains => &[MobKind::Sheep, MobKind::Cow],
+        Biome::Desert => &[],
+    }
+}
+
+// picks random spots around the player and fills them with mobs that suit the light
+// and biome there. everything random comes from its own seeded rng so the same seed
+// and world always spawn the same mobs
+pub struct Spawner {
+    pub rules: SpawnRules,
+    rng: Rng,
+}
+
+impl Spawner {
+    pub fn new(seed: u64) -> Spawner {
+        Spawner {
+            rules: SpawnRules::default(),
+            rng: Rng::new(seed),
+        }
+    }
+
+    fn count(entities: &Entities, hostile: bool) -> usize {
+        entities
+            .iter()
+            .filter(|(_, entity)| {
+                matches!(entity.kind, EntityKind::Mob { kind, .. } if kind.is_hostile() == hostile)
+            })
+            .count()
+    }
+
+    // a random block position the right distance from the player
+    fn pick(&mut self, player: [f32; 3]) -> [i32; 3] {
+        let rules = self.rules;
+        let angle = self.rng.next_f32() * std::f32::consts::TAU;
+        let distance = self.rng.range(rules.min_distance, rules.max_distance + 1) as f32;
+        [
+            (player[0] + angle.cos() * distance).floor() as i32,
+            player[1].floor() as i32 + self.rng.range(-rules.vertical, rules.vertical + 1),
+            (player[2] + angle.sin() * distance).floor() as i32,
+        ]
+    }
+
+    // tries to spawn mobs around the player for one tick, returning the new ones
+    pub fn tick(
+        &mut self,
+        world: &World,
+        entities: &mut Entities,
+        player: [f32; 3],
+        daylight: u8,
+        biome_at: impl Fn(i32, i32) -> Biome,
+    ) -> Vec<EntityId> {
+        let mut spawned = Vec::new();
+        for _ in 0..self.rules.attempts {
+            let pos = self.pick(player);
+            if !world.chunks.contains_key(&split_pos(pos).0) {
+                continue;
+            }
+
+            // every mob fits in two blocks
+            let in_fluid = FluidKind::of(world.get_voxel(pos).block_type).is_some();
+            if in_fluid || standable(world, pos, &PathRules::default()).is_none() {
+                continue;
+            }
+
+            let light = light_level(world, pos, daylight);
+            let hostile = if light <= HOSTILE_LIGHT {
+                true
+            } else if light >= PASSIVE_LIGHT {
+                false
+            } else {
+                continue;
+            };
+            let cap = if hostile {
+                self.rules.hostile_cap
+            } else {
+                self.rules.passive_cap
+            };
+            if Spawner::count(entities, hostile) >= cap {
+                continue;
+            }
+
+            let biome = biome_at(pos[0], pos[2]);
+            // animals only graze on the biome's own ground
+            let ground = world.get_voxel([pos[0], pos[1] - 1, pos[2]]).block_type;
+            if !hostile && ground != biome.settings().surface {
+                continue;
+            }
+            let table = spawn_table(biome, hostile);
+            if table.is_empty() {
+                continue;
+            }
+            let kind = table[self.rng.range(0, table.len() as i32) as usize];
+            let position = [pos[0] as f32 + 0.5, pos[1] as f32, pos[2] as f32 + 0.5];
+            spawned.push(entities.spawn(Entity::mob(kind, position)));
+        }
+        spawned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::block_model::BlockModels;
+    use crate::world::light::{daylight, DAY_LENGTH, MAX_LIGHT};
+    use crate::world::registry::BlockRegistry;
+    use crate::world::voxel::{BlockType, Voxel};
+
+    fn voxel(block_type: BlockType) -> Voxel {
+        Voxel {
+            block_type,
+            ..Voxel::default()
+        }
+    }
+
+    // grass covering everything within `size` of the origin at y = 0, with the chunks
+    // above it loaded so the sky is checked
+    fn meadow(size: i32) -> World {
+        let mut world = World::new(BlockRegistry::default(), BlockModels::default());
+        for x in -size..size {
+            for z in -size..size {
+                world.set_voxel([x, 0, z], voxel(BlockType::Grass));
+                world.set_voxel([x, 1, z], voxel(BlockType::Air));
+            }
+        }
+        world
+    }
+
+    fn roof(world: &mut World, size: i32) {
+        for x in -size..size {
+            for z in -size..size {
+                world.set_voxel([x, 4, z], voxel(BlockType::Stone));
+            }
+        }
+    }
+
+    fn spawner(seed: u64) -> Spawner {
+        let mut spawner = Spawner::new(seed);
+        // flat ground, so only ever pick the layer just above it
+        spawner.rules.vertical = 0;
+        spawner
+    }
+
+    fn run(world: &World, spawner: &mut Spawner, light: u8, biome: Biome) -> Entities {
+        let mut entities = Entities::default();
+        for _ in 0..200 {
+            spawner.tick(world, &mut entities, [0.5, 1.0, 0.5], light, |_, _| biome);
+        }
+        entities
+    }
+
+    fn kind_of(entity: &Entity) -> Option<MobKind> {
+        match entity.kind {
+            EntityKind::Mob { kind, .. } => Some(kind),
+            _ => None,
+        }
+    }
+
+    fn kinds(entities: &Entities) -> Vec<MobKind> {
+        entities
+            .iter()
+            .filter_map(|(_, entity)| kind_of(entity))
+            .collect()
+    }
+
+    #[test]
+    fn animals_in_daylight() {
+        let world = meadow(64);
+        let entities = run(&world, &mut spawner(1), MAX_LIGHT, Biome::Plains);
+        let spawned = kinds(&entities);
+        assert!(!spawned.is_empty());
+        assert!(spawned.iter().all(|kind| !kind.is_hostile()));
+    }
+
+    #[test]
+    fn monsters_at_night_and_under_cover() {
+        let world = meadow(64);
+        let night = daylight(DAY_LENGTH * 3 / 4);
+        let spawned = kinds(&run(&world, &mut spawner(1), night, Biome::Plains));
+        assert!(!spawned.is_empty());
+        assert!(spawned.iter().all(|kind| kind.is_hostile()));
+
+        let mut world = meadow(64);
+        roof(&mut world, 64);
+        let spawned = kinds(&run(&world, &mut spawner(1), MAX_LIGHT, Biome::Plains));
+        assert!(!spawned.is_empty());
+        assert!(spawned.iter().all(|kind| kind.is_hostile()));
+    }
+
+    #[test]
+    fn respects_caps() {
+        let world = meadow(64);
+        let mut spawner = spawner(2);
+        spawner.rules.passive_cap = 3;
+        let entities = run(&world, &mut spawner, MAX_LIGHT, Biome::Plains);
+        assert_eq!(entities.len(), 3);
+    }
+
+    #[test]
+    fn biome_decides_what_spawns() {
+        let world = meadow(64);
+        // nothing grazes in the desert
+        assert!(run(&world, &mut spawner(3), MAX_LIGHT, Biome::Desert).is_empty());
+
+        let spawned = kinds(&run(&world, &mut spawner(3), MAX_LIGHT, Biome::Forest));
+        assert!(!spawned.is_empty());
+        assert!(!spawned.contains(&MobKind::Cow));
+    }
+
+    #[test]
+    fn needs_ground_and_loaded_chunks() {
+        let world = World::new(BlockRegistry::default(), BlockModels::default());
+        assert!(run(&world, &mut spawner(4), 0, Biome::Plains).is_empty());
+
+        // loaded but nothing to stand on
+        let mut world = World::new(BlockRegistry::default(), BlockModels::default());
+        for x in -64..64 {
+            for z in -64..64 {
+                world.set_voxel([x, 0, z], voxel(BlockType::Air));
+            }
+        }
+        assert!(run(&world, &mut spawner(4), 0, Biome::Plains).is_empty());
+    }
+
+    #[test]
+    fn same_seed_same_spawns() {
+        let world = meadow(64);
+        let positions = |seed| {
+            run(&world, &mut spawner(seed), MAX_LIGHT, Biome::Plains)
+                .iter()
+                .map(|(_, entity)| (entity.body.position, kind_of(entity)))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(positions(7), positions(7));
+        assert_ne!(positions(7), positions(8));
+    }
+}
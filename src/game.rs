@@ -0,0 +1,599 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::entity::spawn::Spawner;
+use crate::entity::{Entities, Entity};
+use crate::item::crafting::{CraftingGrid, RecipeBook};
+use crate::item::inventory::Inventory;
+use crate::item::registry::ItemRegistry;
+use crate::item::tool::{break_ticks, can_harvest, Mining, Tool};
+use crate::item::ItemStack;
+use crate::player::stats::{BREAK_EXHAUSTION, MAX_HUNGER};
+use crate::player::{Player, PlayerInput};
+use crate::support::camera::CameraState;
+use crate::support::rng::Rng;
+use crate::world::block_model::{BlockModels, Facing};
+use crate::world::block_state::{Axis, Property};
+use crate::world::chunk::{Chunk, CHUNK_SIZE};
+use crate::world::collision::Aabb;
+use crate::world::fluid::FluidSimulator;
+use crate::world::light::daylight;
+use crate::world::raycast::{raycast, RayHit};
+use crate::world::registry::BlockRegistry;
+use crate::world::voxel::{BlockType, Voxel};
+use crate::world::{split_pos, ChunkPos, World};
+use crate::worldgen::WorldGenerator;
+
+// simulation steps per second, independent of the frame rate
+pub const TICKS_PER_SECOND: u32 = 20;
+pub const REACH: f32 = 5.0;
+// how close dropped items have to be to get picked up
+const PICKUP_RADIUS: f32 = 2.0;
+// the small grid the player can craft in without a table
+const PLAYER_GRID_SIZE: usize = 2;
+// the grid a crafting table gives, big enough for every recipe
+const TABLE_GRID_SIZE: usize = 3;
+// chunks beyond the ones around the player generated each frame
+const FAR_CHUNKS_PER_CALL: usize = 2;
+// how far past the generated radius a chunk has to be before it's unloaded, so walking
+// back and forth over a chunk edge doesn't keep saving and loading the same chunks
+const UNLOAD_MARGIN: i32 = 2;
+
+// everything that makes up a running game, without any of the window or rendering
+pub struct Game {
+    pub world: World,
+    pub generator: WorldGenerator,
+    pub fluids: FluidSimulator,
+    pub camera: CameraState,
+    pub player: Player,
+    pub inventory: Inventory,
+    pub items: ItemRegistry,
+    pub recipes: RecipeBook,
+    pub crafting: CraftingGrid,
+    pub entities: Entities,
+    pub spawner: Spawner,
+    // ticks since the game started, for the time of day
+    pub time: u64,
+    // the block being broken while the break button is held
+    pub mining: Option<Mining>,
+    breaking: bool,
+    rng: Rng,
+    // chunks whose mesh is out of date
+    pub dirty_chunks: HashSet<ChunkPos>,
+    // chunks in range that generate_around left for later
+    pub chunks_to_generate: usize,
+    // chunks that went out of range, with their entities, kept until the player comes back
+    unloaded: HashMap<ChunkPos, Vec<u8>>,
+}
+
+impl Game {
+    pub fn new(seed: u64) -> Game {
+        let blocks = BlockRegistry::load("assets/data/blocks.ron").expect("loading blocks");
+        let models =
+            BlockModels::load("assets/data/block_models.ron").expect("loading block models");
+        let items = ItemRegistry::load("assets/data/items.ron").expect("loading items");
+        let recipes = RecipeBook::load("assets/data/recipes.ron", &items).expect("loading recipes");
+        let generator = WorldGenerator::new(seed);
+
+        let spawn = [0.5, generator.terrain_top(0, 0) as f32 + 1.0, 0.5];
+        let player = Player::new(spawn);
+        let mut camera = CameraState::new();
+        let eye = player.eye();
+        camera.set_position((eye[0], eye[1], eye[2]));
+
+        Game {
+            world: World::new(blocks, models),
+            generator,
+            fluids: FluidSimulator::default(),
+            camera,
+            player,
+            inventory: Inventory::default(),
+            items,
+            recipes,
+            crafting: CraftingGrid::new(PLAYER_GRID_SIZE, PLAYER_GRID_SIZE),
+            entities: Entities::default(),
+            spawner: Spawner::new(seed ^ 0x6d6f6273),
+            time: 0,
+            mining: None,
+            breaking: false,
+            rng: Rng::new(seed ^ 0x6974656d),
+            dirty_chunks: HashSet::new(),
+            chunks_to_generate: 0,
+            unloaded: HashMap::new(),
+        }
+    }
+
+    // generates the chunks next to `centre` straight away and the rest of the radius a
+    // few at a time, nearest first, so a long view distance doesn't stall a frame. chunks
+    // that were unloaded are loaded back rather than generated again
+    pub fn generate_around(&mut self, centre: ChunkPos, radius: i32) {
+        self.unload_outside(centre, radius + UNLOAD_MARGIN);
+
+        let mut missing = Vec::new();
+        for x in -radius..=radius {
+            for y in -1..=1 {
+                for z in -radius..=radius {
+                    let pos = [centre[0] + x, centre[1] + y, centre[2] + z];
+                    if !self.generator.is_generated(pos) || self.unloaded.contains_key(&pos) {
+                        missing.push(([x, y, z], pos));
+                    }
+                }
+            }
+        }
+        missing.sort_by_key(|(offset, _)| offset.map(|o| o * o).iter().sum::<i32>());
+
+        let mut far = 0;
+        self.chunks_to_generate = missing.len();
+        for (offset, pos) in missing {
+            let near = offset[0].abs() <= 1 && offset[2].abs() <= 1;
+            if !near {
+                if far == FAR_CHUNKS_PER_CALL {
+                    break;
+                }
+                far += 1;
+            }
+            match self.unloaded.remove(&pos) {
+                Some(bytes) => self.load_chunk(pos, &bytes),
+                None => self.generator.generate_chunk(&mut self.world, pos),
+            }
+            self.dirty_chunks.insert(pos);
+            self.chunks_to_generate -= 1;
+        }
+    }
+
+    // saves and drops every chunk further than `radius` chunks across or a chunk above or
+    // below it from `centre`, along with the entities in it
+    fn unload_outside(&mut self, centre: ChunkPos, radius: i32) {
+        let far: Vec<ChunkPos> = self
+            .world
+            .chunks
+            .keys()
+            .filter(|pos| {
+                (pos[0] - centre[0]).abs() > radius
+                    || (pos[1] - centre[1]).abs() > 1 + UNLOAD_MARGIN
+                    || (pos[2] - centre[2]).abs() > radius
+            })
+            .copied()
+            .collect();
+        for pos in far {
+            self.entities.store_in_chunk(&mut self.world, pos);
+            if let Some(chunk) = self.world.chunks.remove(&pos) {
+                self.unloaded.insert(pos, chunk.to_bytes());
+            }
+            self.dirty_chunks.remove(&pos);
+        }
+    }
+
+    fn load_chunk(&mut self, pos: ChunkPos, bytes: &[u8]) {
+        // only ever bytes this game wrote, so failing to read them back is a bug
+        let chunk = Chunk::from_bytes(bytes).expect("reading back an unloaded chunk");
+        self.world.chunks.insert(pos, chunk);
+        self.entities.restore_from_chunk(&mut self.world, pos);
+        self.generator.chunk_loaded(&mut self.world, pos);
+    }
+
+    pub fn tick(&mut self) {
+        for pos in self.fluids.tick(&mut self.world) {
+            self.mark_dirty(pos);
+        }
+
+        self.tick_player();
+        self.update_mining();
+
+        self.time += 1;
+        let generator = &self.generator;
+        self.spawner.tick(
+            &self.world,
+            &mut self.entities,
+            self.player.position(),
+            daylight(self.time),
+            |x, z| generator.biome_at(x, z),
+        );
+        let damage = self
+            .entities
+            .think(&self.world, self.player.position(), &mut self.rng);
+        self.player.stats.damage(damage);
+        self.entities.tick(&self.world);
+        let inventory = &mut self.inventory;
+        self.entities
+            .pick_up_items(self.player.position(), PICKUP_RADIUS, |stack| {
+                inventory.insert(stack)
+            });
+    }
+
+    fn tick_player(&mut self) {
+        // hold still until the ground underneath has been generated
+        let feet = self.player.position().map(|v| v.floor() as i32);
+        if !self.generator.is_generated(split_pos(feet).0) {
+            self.player.body.previous_position = self.player.body.position;
+            return;
+        }
+
+        let (walk, jump) = self.camera.walk_input();
+        self.player.tick(&self.world, PlayerInput { walk, jump });
+        if self.player.stats.is_dead() {
+            self.die();
+        }
+        self.update_camera(1.0);
+    }
+
+    // everything the player was carrying is left where they died
+    fn die(&mut self) {
+        let at = self.player.position();
+        for slot in 0..self.inventory.len() {
+            if let Some(stack) = self.inventory.set_slot(slot, None) {
+                self.entities.spawn(Entity::item(stack, at, [0.0; 3]));
+            }
+        }
+        for stack in self.crafting.clear() {
+            self.entities.spawn(Entity::item(stack, at, [0.0; 3]));
+        }
+        self.stop_breaking();
+        self.player.respawn();
+    }
+
+    // puts the camera at the player's eyes, `alpha` of the way through the current tick
+    pub fn update_camera(&mut self, alpha: f32) {
+        let eye = self.player.interpolated_eye(alpha);
+        self.camera.set_position((eye[0], eye[1], eye[2]));
+    }
+
+    pub fn camera_block(&self) -> [i32; 3] {
+        let p = self.camera.position();
+        [p.0.floor() as i32, p.1.floor() as i32, p.2.floor() as i32]
+    }
+
+    // the block the camera is looking at, if it's in reach
+    pub fn target(&self) -> Option<RayHit> {
+        let p = self.camera.position();
+        let d = self.camera.direction();
+        raycast(&self.world, [p.0, p.1, p.2], [d.0, d.1, d.2], REACH)
+    }
+
+    // changes a block and makes sure the meshes and fluids around it catch up
+    pub fn set_block(&mut self, pos: [i32; 3], voxel: Voxel) {
+        self.world.set_voxel(pos, voxel);
+        self.fluids.block_changed(&self.world, pos);
+        self.mark_dirty(pos);
+    }
+
+    // remeshes the chunk a changed block is in. blocks on a chunk edge also change what
+    // the neighbouring chunk draws
+    fn mark_dirty(&mut self, pos: [i32; 3]) {
+        let (chunk, local) = split_pos(pos);
+        self.dirty_chunks.insert(chunk);
+        for axis in 0..3 {
+            let mut neighbour = chunk;
+            if local[axis] == 0 {
+                neighbour[axis] -= 1;
+            } else if local[axis] == CHUNK_SIZE - 1 {
+                neighbour[axis] += 1;
+            } else {
+                continue;
+            }
+            self.dirty_chunks.insert(neighbour);
+        }
+    }
+
+    pub fn held_tool(&self) -> Option<Tool> {
+        let stack = self.inventory.selected_stack()?;
+        self.items.tool_of(stack.item).copied()
+    }
+
+    // breaking happens over several ticks while the button is held
+    pub fn start_breaking(&mut self) {
+        self.breaking = true;
+    }
+
+    pub fn stop_breaking(&mut self) {
+        self.breaking = false;
+        self.mining = None;
+    }
+
+    fn update_mining(&mut self) {
+        if !self.breaking {
+            return;
+        }
+        let Some(hit) = self.target() else {
+            self.mining = None;
+            return;
+        };
+
+        // looking at a different block starts over
+        if self.mining.map(|m| m.block) != Some(hit.block) {
+            let block_type = self.world.get_voxel(hit.block).block_type;
+            let def = self.world.blocks.get(block_type);
+            self.mining = break_ticks(def, self.held_tool().as_ref())
+                .map(|needed| Mining::new(hit.block, needed));
+        }
+        let Some(mining) = &mut self.mining else {
+            return;
+        };
+        mining.ticks += 1;
+        if mining.is_done() {
+            self.mining = None;
+            self.break_block(hit.block);
+        }
+    }
+
+    // breaks a block and scatters its drops where it was, as long as the held tool is
+    // good enough to harvest it
+    pub fn break_block(&mut self, pos: [i32; 3]) {
+        let broken = self.world.get_voxel(pos);
+        self.set_block(pos, Voxel::default());
+
+        self.player.stats.exhaust(BREAK_EXHAUSTION);
+        let def = self.world.blocks.get(broken.block_type);
+        if !can_harvest(def, self.held_tool().as_ref()) {
+            return;
+        }
+        let drops = self
+            .items
+            .roll_drops(&self.world.blocks, broken.block_type, &mut self.rng);
+        let centre = pos.map(|v| v as f32 + 0.5);
+        for stack in drops {
+            let velocity = [
+                (self.rng.next_f32() - 0.5) * 0.1,
+                0.2,
+                (self.rng.next_f32() - 0.5) * 0.1,
+            ];
+            self.entities.spawn(Entity::item(stack, centre, velocity));
+        }
+    }
+
+    // eats the selected item if it's food, otherwise tries to place it
+    pub fn use_item(&mut self) {
+        let Some(stack) = self.inventory.selected_stack() else {
+            return;
+        };
+        match self.items.food_of(stack.item) {
+            Some((hunger, saturation)) => {
+                if self.player.stats.hunger < MAX_HUNGER {
+                    self.inventory.take_selected(1);
+                    self.player.stats.eat(hunger, saturation);
+                }
+            }
+            None => self.place_block(),
+        }
+    }
+
+    // places one of whatever block is in the selected hotbar slot against the targeted
+    // block
+    pub fn place_block(&mut self) {
+        let Some(hit) = self.target() else {
+            return;
+        };
+        let Some(block_type) = self
+            .inventory
+            .selected_stack()
+            .and_then(|s| self.items.block_of(s.item))
+        else {
+            return;
+        };
+        if self.world.get_voxel(hit.previous).block_type.is_solid() {
+            return;
+        }
+        let block = hit.previous.map(|v| v as f32);
+        let space = Aabb::new(block, block.map(|v| v + 1.0));
+        if block_type.is_solid() && space.intersects(&self.player.aabb()) {
+            return;
+        }
+
+        self.inventory.take_selected(1);
+        let voxel = self.placed_voxel(block_type, &hit);
+        self.set_block(hit.previous, voxel);
+    }
+
+    // blocks with a facing turn the way the player's looking, and blocks with an axis
+    // lie along the one the targeted face is across
+    fn placed_voxel(&self, block_type: BlockType, hit: &RayHit) -> Voxel {
+        let mut voxel = self.world.blocks.default_voxel(block_type);
+        let properties = &self.world.blocks.get(block_type).properties;
+        if properties.contains_key(&Property::Facing) {
+            let d = self.camera.direction();
+            voxel.state = voxel.state.with_facing(Facing::looking(d.0, d.2));
+        }
+        if properties.contains_key(&Property::Axis) {
+            let axis = match (0..3).find(|&a| hit.previous[a] != hit.block[a]) {
+                Some(0) => Axis::X,
+                Some(2) => Axis::Z,
+                _ => Axis::Y,
+            };
+            voxel.state = voxel.state.with_axis(axis);
+        }
+        voxel
+    }
+
+    // moves one item from an inventory slot onto a crafting grid slot
+    pub fn put_in_grid(&mut self, slot: usize, x: usize, y: usize) {
+        let Some(stack) = self.inventory.slot(slot).copied() else {
+            return;
+        };
+        let placed = match self.crafting.slot(x, y) {
+            None => stack.with_count(1),
+            Some(existing) if existing.can_stack_with(&stack) && !existing.is_full() => {
+                existing.with_count(existing.count + 1)
+            }
+            Some(_) => return,
+        };
+        self.inventory.take(slot, 1);
+        self.crafting.set_slot(x, y, Some(placed));
+    }
+
+    // what the crafting grid would make, for showing in the output slot
+    pub fn crafting_output(&self) -> Option<ItemStack> {
+        self.recipes.find(&self.crafting)
+    }
+
+    // crafts once into the inventory, dropping anything that doesn't fit
+    pub fn take_crafted(&mut self) {
+        if let Some(result) = self.recipes.craft(&mut self.crafting) {
+            self.give(result);
+        }
+    }
+
+    // whether the targeted block is a crafting table, which using opens rather than
+    // placing against
+    pub fn targets_crafting_table(&self) -> bool {
+        self.target().is_some_and(|hit| {
+            self.world.get_voxel(hit.block).block_type == BlockType::CraftingTable
+        })
+    }
+
+    // swaps the player's grid for a table's bigger one until crafting's closed
+    pub fn open_crafting_table(&mut self) {
+        self.close_crafting();
+        self.crafting = CraftingGrid::new(TABLE_GRID_SIZE, TABLE_GRID_SIZE);
+    }
+
+    // hands everything left in the crafting grid back when it's closed
+    pub fn close_crafting(&mut self) {
+        for stack in self.crafting.clear() {
+            self.give(stack);
+        }
+        self.crafting = CraftingGrid::new(PLAYER_GRID_SIZE, PLAYER_GRID_SIZE);
+    }
+
+    // puts a stack in the inventory, anything that doesn't fit lands at the player's feet
+    pub fn give(&mut self, stack: ItemStack) {
+        if let Some(left) = self.inventory.insert(stack) {
+            self.entities
+                .spawn(Entity::item(left, self.player.position(), [0.0; 3]));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::{EntityKind, MobKind};
+
+    #[test]
+    fn chunks_out_of_range_are_unloaded_and_come_back_as_they_were() {
+        let mut game = Game::new(1);
+        game.generate_around([0, 0, 0], 0);
+        let block = [3, 5, 3];
+        let planks = game.world.blocks.default_voxel(BlockType::Planks);
+        game.set_block(block, planks);
+        game.entities
+            .spawn(Entity::mob(MobKind::Pig, [3.5, 6.0, 3.5]));
+
+        let away = [1 + UNLOAD_MARGIN, 0, 0];
+        game.generate_around(away, 0);
+        assert!(!game.world.chunks.contains_key(&[0, 0, 0]));
+        assert!(!game.dirty_chunks.contains(&[0, 0, 0]));
+        assert!(game.entities.is_empty());
+
+        game.generate_around([0, 0, 0], 0);
+        assert_eq!(game.world.get_voxel(block), planks);
+        let mobs: Vec<_> = game.entities.iter().map(|(_, e)| e.kind).collect();
+        assert!(matches!(
+            mobs[..],
+            [EntityKind::Mob {
+                kind: MobKind::Pig,
+                ..
+            }]
+        ));
+    }
+
+    // puts one of `name` in the selected slot and places it against `target`, looking
+    // along `direction` from a block and a half back
+    fn place_looking(game: &mut Game, name: &str, target: [i32; 3], direction: [f32; 3]) {
+        let stone = game.world.blocks.default_voxel(BlockType::Stone);
+        game.set_block(target, stone);
+        let eye = target.map(|v| v as f32 + 0.5);
+        let eye = [
+            eye[0] - direction[0] * 1.5,
+            eye[1] - direction[1] * 1.5,
+            eye[2] - direction[2] * 1.5,
+        ];
+        game.camera.set_position((eye[0], eye[1], eye[2]));
+        game.camera
+            .set_direction((direction[0], direction[1], direction[2]));
+        let item = game.items.id(name).unwrap();
+        game.inventory.set_slot(0, Some(game.items.stack(item, 1)));
+        game.inventory.select(0);
+        game.place_block();
+    }
+
+    #[test]
+    fn placed_blocks_face_the_player_and_lie_along_the_face_they_go_on() {
+        // well above the ground, away from the player
+        let mut game = Game::new(1);
+        let y = 200;
+        place_looking(&mut game, "plank_stairs", [0, y, 0], [0.0, 0.0, 1.0]);
+        let stairs = game.world.get_voxel([0, y, -1]);
+        assert_eq!(stairs.block_type, BlockType::PlankStairs);
+        assert_eq!(stairs.state.facing(), Facing::South);
+        place_looking(&mut game, "plank_stairs", [10, y, 0], [1.0, -0.3, 0.2]);
+        assert_eq!(game.world.get_voxel([9, y, 0]).state.facing(), Facing::East);
+
+        place_looking(&mut game, "log", [20, y, 0], [1.0, 0.0, 0.0]);
+        assert_eq!(game.world.get_voxel([19, y, 0]).state.axis(), Axis::X);
+        place_looking(&mut game, "log", [30, y, 0], [0.0, -1.0, 0.0]);
+        assert_eq!(game.world.get_voxel([30, y + 1, 0]).state.axis(), Axis::Y);
+        place_looking(&mut game, "log", [40, y, 0], [0.0, 0.0, 1.0]);
+        assert_eq!(game.world.get_voxel([40, y, -1]).state.axis(), Axis::Z);
+    }
+
+    #[test]
+    fn fluid_flowing_over_a_chunk_edge_remeshes_both_chunks() {
+        // well above the ground, away from the player
+        let mut game = Game::new(1);
+        let y = 200;
+        let stone = game.world.blocks.default_voxel(BlockType::Stone);
+        for x in 28..36 {
+            game.set_block([x, y, 0], stone);
+        }
+        let water = game.world.blocks.default_voxel(BlockType::Water);
+        game.set_block([30, y + 1, 0], water);
+        game.dirty_chunks.clear();
+
+        // the source is two blocks from the edge, so only the flow dirties the chunk
+        // on the other side
+        for _ in 0..100 {
+            game.tick();
+        }
+        assert_eq!(
+            game.world.get_voxel([33, y + 1, 0]).block_type,
+            BlockType::Water
+        );
+        let (inside, _) = split_pos([31, y + 1, 0]);
+        let (outside, _) = split_pos([32, y + 1, 0]);
+        assert_ne!(inside, outside);
+        assert!(game.dirty_chunks.contains(&inside));
+        assert!(game.dirty_chunks.contains(&outside));
+    }
+
+    #[test]
+    fn features_waiting_on_a_chunk_survive_the_player_leaving() {
+        // generates the chunks around the spawn, then everything around those, with or
+        // without going far enough away in between for the spawn chunks to unload
+        let around_spawn = |leave: bool| {
+            let mut game = Game::new(2);
+            let (spawn, _) = split_pos(game.camera_block());
+            game.generate_around(spawn, 0);
+            assert!(game.generator.pending_writes() > 0);
+            if leave {
+                let away = [spawn[0] + 3 + UNLOAD_MARGIN, spawn[1], spawn[2]];
+                game.generate_around(away, 0);
+                assert!(!game.world.chunks.contains_key(&spawn));
+            }
+            game.generate_around(spawn, 1);
+            (spawn, game)
+        };
+        let (spawn, stayed) = around_spawn(false);
+        let (_, left) = around_spawn(true);
+
+        for x in -1..=1 {
+            for y in -1..=1 {
+                for z in -1..=1 {
+                    let pos = [spawn[0] + x, spawn[1] + y, spawn[2] + z];
+                    assert!(
+                        stayed.world.chunks[&pos].to_bytes() == left.world.chunks[&pos].to_bytes(),
+                        "chunk {pos:?} differs"
+                    );
+                }
+            }
+        }
+    }
+}
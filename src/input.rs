@@ -0,0 +1,486 @@
+use std::collections::BTreeMap;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use glium::winit::event::{ElementState, MouseButton, WindowEvent};
+use glium::winit::keyboard::{KeyCode, PhysicalKey};
+use serde::{Deserialize, Serialize};
+
+use crate::support::data::{load_ron, save_ron, DataError};
+
+// what keys, mouse buttons and gamepad buttons do, kept in a file next to where the
+// game's run from so it can be edited by hand as well as from the controls menu
+pub const BINDINGS_PATH: &str = "bindings.ron";
+
+// something the player can do with a key or button
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    MoveForward,
+    MoveBackward,
+    MoveLeft,
+    MoveRight,
+    Jump,
+    Break,
+    Place,
+    Inventory,
+    Crafting,
+    // pauses the game, or goes back a step from a menu
+    Pause,
+    Screenshot,
+    Debug,
+    // picks a hotbar slot, counting from 0
+    Hotbar(usize),
+}
+
+impl Action {
+    pub fn name(self) -> String {
+        let name = match self {
+            Action::MoveForward => "move forward",
+            Action::MoveBackward => "move backward",
+            Action::MoveLeft => "move left",
+            Action::MoveRight => "move right",
+            Action::Jump => "jump",
+            Action::Break => "break",
+            Action::Place => "place",
+            Action::Inventory => "inventory",
+            Action::Crafting => "crafting",
+            Action::Pause => "pause",
+            Action::Screenshot => "screenshot",
+            Action::Debug => "debug screen",
+            Action::Hotbar(slot) => return format!("hotbar {}", slot + 1),
+        };
+        name.to_string()
+    }
+}
+
+// the buttons on a standard controller, named by where they are rather than what's
+// printed on them
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GamepadButton {
+    South,
+    East,
+    West,
+    North,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+impl GamepadButton {
+    fn name(self) -> &'static str {
+        match self {
+            GamepadButton::South => "pad south",
+            GamepadButton::East => "pad east",
+            GamepadButton::West => "pad west",
+            GamepadButton::North => "pad north",
+            GamepadButton::LeftBumper => "pad left bumper",
+            GamepadButton::RightBumper => "pad right bumper",
+            GamepadButton::LeftTrigger => "pad left trigger",
+            GamepadButton::RightTrigger => "pad right trigger",
+            GamepadButton::Select => "pad select",
+            GamepadButton::Start => "pad start",
+            GamepadButton::LeftStick => "pad left stick",
+            GamepadButton::RightStick => "pad right stick",
+            GamepadButton::DPadUp => "pad up",
+            GamepadButton::DPadDown => "pad down",
+            GamepadButton::DPadLeft => "pad left",
+            GamepadButton::DPadRight => "pad right",
+        }
+    }
+}
+
+// a physical key or button, whatever the keyboard layout
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+}
+
+// winit's names split into lowercase words, "ArrowUp" to "arrow up"
+fn words(name: &str) -> String {
+    let mut words = String::new();
+    let mut last = ' ';
+    for c in name.chars() {
+        if c.is_uppercase() && last.is_lowercase() {
+            words.push(' ');
+        }
+        words.extend(c.to_lowercase());
+        last = c;
+    }
+    words
+}
+
+impl Binding {
+    // the key or button pressed or let go in `event`, and whether it was pressed. held
+    // keys repeating don't count
+    pub fn from_event(event: &WindowEvent) -> Option<(Binding, bool)> {
+        match event {
+            WindowEvent::KeyboardInput { event, .. } if !event.repeat => {
+                let PhysicalKey::Code(code) = event.physical_key else {
+                    return None;
+                };
+                let pressed = event.state == ElementState::Pressed;
+                Some((Binding::Key(code), pressed))
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                Some((Binding::Mouse(*button), *state == ElementState::Pressed))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> String {
+        match self {
+            Binding::Key(code) => {
+                let name = format!("{code:?}");
+                // letters and numbers on their own, "KeyW" is just "w"
+                let short = name
+                    .strip_prefix("Key")
+                    .or_else(|| name.strip_prefix("Digit"))
+                    .filter(|rest| rest.len() == 1);
+                words(short.unwrap_or(&name))
+            }
+            Binding::Mouse(MouseButton::Left) => "left mouse".to_string(),
+            Binding::Mouse(MouseButton::Right) => "right mouse".to_string(),
+            Binding::Mouse(MouseButton::Middle) => "middle mouse".to_string(),
+            Binding::Mouse(MouseButton::Back) => "mouse back".to_string(),
+            Binding::Mouse(MouseButton::Forward) => "mouse forward".to_string(),
+            Binding::Mouse(MouseButton::Other(button)) => format!("mouse {button}"),
+            Binding::Gamepad(button) => button.name().to_string(),
+        }
+    }
+}
+
+// reads controllers through gilrs. it needs libudev to build on linux, so it's behind
+// the `gamepad` feature
+#[cfg(feature = "gamepad")]
+pub struct Gamepads {
+    // None if controllers can't be read on this system
+    gilrs: Option<gilrs::Gilrs>,
+}
+
+#[cfg(feature = "gamepad")]
+impl Default for Gamepads {
+    fn default() -> Self {
+        Gamepads::new()
+    }
+}
+
+#[cfg(feature = "gamepad")]
+impl Gamepads {
+    pub fn new() -> Gamepads {
+        let gilrs = match gilrs::Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(err) => {
+                eprintln!("gamepads won't work: {err}");
+                None
+            }
+        };
+        Gamepads { gilrs }
+    }
+
+    // the buttons pressed or let go on any controller since the last call, and whether
+    // they were pressed
+    pub fn poll(&mut self) -> Vec<(Binding, bool)> {
+        let mut input = Vec::new();
+        let Some(gilrs) = &mut self.gilrs else {
+            return input;
+        };
+        while let Some(event) = gilrs.next_event() {
+            let (button, pressed) = match event.event {
+                gilrs::EventType::ButtonPressed(button, _) => (button, true),
+                gilrs::EventType::ButtonReleased(button, _) => (button, false),
+                _ => continue,
+            };
+            if let Some(button) = GamepadButton::from_gilrs(button) {
+                input.push((Binding::Gamepad(button), pressed));
+            }
+        }
+        input
+    }
+}
+
+#[cfg(feature = "gamepad")]
+impl GamepadButton {
+    // gilrs calls the bumpers the first triggers and the triggers the second ones
+    fn from_gilrs(button: gilrs::Button) -> Option<GamepadButton> {
+        use gilrs::Button;
+        let button = match button {
+            Button::South => GamepadButton::South,
+            Button::East => GamepadButton::East,
+            Button::West => GamepadButton::West,
+            Button::North => GamepadButton::North,
+            Button::LeftTrigger => GamepadButton::LeftBumper,
+            Button::RightTrigger => GamepadButton::RightBumper,
+            Button::LeftTrigger2 => GamepadButton::LeftTrigger,
+            Button::RightTrigger2 => GamepadButton::RightTrigger,
+            Button::Select => GamepadButton::Select,
+            Button::Start => GamepadButton::Start,
+            Button::LeftThumb => GamepadButton::LeftStick,
+            Button::RightThumb => GamepadButton::RightStick,
+            Button::DPadUp => GamepadButton::DPadUp,
+            Button::DPadDown => GamepadButton::DPadDown,
+            Button::DPadLeft => GamepadButton::DPadLeft,
+            Button::DPadRight => GamepadButton::DPadRight,
+            _ => return None,
+        };
+        Some(button)
+    }
+}
+
+// which keys and buttons set off each action. an action can have several, and a key
+// bound to more than one action sets them all off
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Bindings {
+    map: BTreeMap<Action, Vec<Binding>>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        use Binding::{Gamepad, Key, Mouse};
+        let mut map = BTreeMap::from([
+            (
+                Action::MoveForward,
+                vec![Key(KeyCode::KeyW), Gamepad(GamepadButton::DPadUp)],
+            ),
+            (
+                Action::MoveBackward,
+                vec![Key(KeyCode::KeyS), Gamepad(GamepadButton::DPadDown)],
+            ),
+            (
+                Action::MoveLeft,
+                vec![Key(KeyCode::KeyA), Gamepad(GamepadButton::DPadLeft)],
+            ),
+            (
+                Action::MoveRight,
+                vec![Key(KeyCode::KeyD), Gamepad(GamepadButton::DPadRight)],
+            ),
+            (
+                Action::Jump,
+                vec![
+                    Key(KeyCode::Space),
+                    Key(KeyCode::ArrowUp),
+                    Gamepad(GamepadButton::South),
+                ],
+            ),
+            (
+                Action::Break,
+                vec![
+                    Mouse(MouseButton::Left),
+                    Gamepad(GamepadButton::RightTrigger),
+                ],
+            ),
+            (
+                Action::Place,
+                vec![
+                    Mouse(MouseButton::Right),
+                    Gamepad(GamepadButton::LeftTrigger),
+                ],
+            ),
+            (
+                Action::Inventory,
+                vec![Key(KeyCode::KeyE), Gamepad(GamepadButton::North)],
+            ),
+            (
+                Action::Crafting,
+                vec![Key(KeyCode::KeyC), Gamepad(GamepadButton::West)],
+            ),
+            (
+                Action::Pause,
+                vec![Key(KeyCode::Escape), Gamepad(GamepadButton::Start)],
+            ),
+            (Action::Screenshot, vec![Key(KeyCode::F2)]),
+            (
+                Action::Debug,
+                vec![Key(KeyCode::F3), Gamepad(GamepadButton::Select)],
+            ),
+        ]);
+        let digits = [
+            KeyCode::Digit1,
+            KeyCode::Digit2,
+            KeyCode::Digit3,
+            KeyCode::Digit4,
+            KeyCode::Digit5,
+            KeyCode::Digit6,
+            KeyCode::Digit7,
+            KeyCode::Digit8,
+            KeyCode::Digit9,
+        ];
+        for (slot, digit) in digits.into_iter().enumerate() {
+            map.insert(Action::Hotbar(slot), vec![Key(digit)]);
+        }
+        Bindings { map }
+    }
+}
+
+impl Bindings {
+    // actions the file leaves out keep their default bindings
+    pub fn load(path: impl AsRef<Path>) -> Result<Bindings, DataError> {
+        let mut bindings = Bindings::default();
+        let map: BTreeMap<Action, Vec<Binding>> = load_ron(path)?;
+        bindings.map.extend(map);
+        Ok(bindings)
+    }
+
+    // the defaults if there's no file yet, or if it can't be read
+    pub fn load_or_default(path: impl AsRef<Path>) -> Bindings {
+        match Bindings::load(path) {
+            Ok(bindings) => bindings,
+            Err(DataError::Io(err)) if err.kind() == ErrorKind::NotFound => Bindings::default(),
+            Err(err) => {
+                eprintln!("using the default key bindings: {err}");
+                Bindings::default()
+            }
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), DataError> {
+        save_ron(path, self)
+    }
+
+    // every action with what it's bound to, in the order the controls menu lists them
+    pub fn iter(&self) -> impl Iterator<Item = (Action, &[Binding])> + '_ {
+        self.map
+            .iter()
+            .map(|(&action, bindings)| (action, bindings.as_slice()))
+    }
+
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.map.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    // the actions `binding` sets off
+    pub fn actions(&self, binding: Binding) -> Vec<Action> {
+        self.iter()
+            .filter(|(_, bindings)| bindings.contains(&binding))
+            .map(|(action, _)| action)
+            .collect()
+    }
+
+    // `binding` is all `action` has from now on. anything else bound to it keeps it too,
+    // see conflicts
+    pub fn bind(&mut self, action: Action, binding: Binding) {
+        self.map.insert(action, vec![binding]);
+    }
+
+    // every key or button bound to more than one action, with the actions
+    pub fn conflicts(&self) -> BTreeMap<Binding, Vec<Action>> {
+        let mut uses: BTreeMap<Binding, Vec<Action>> = BTreeMap::new();
+        for (action, bindings) in self.iter() {
+            for &binding in bindings {
+                uses.entry(binding).or_default().push(action);
+            }
+        }
+        uses.retain(|_, actions| actions.len() > 1);
+        uses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebinding_finds_conflicts() {
+        let mut bindings = Bindings::default();
+        assert!(bindings.conflicts().is_empty());
+        assert_eq!(
+            bindings.actions(Binding::Key(KeyCode::Digit3)),
+            [Action::Hotbar(2)]
+        );
+
+        bindings.bind(Action::Crafting, Binding::Key(KeyCode::KeyE));
+        assert_eq!(
+            bindings.get(Action::Crafting),
+            [Binding::Key(KeyCode::KeyE)]
+        );
+        assert!(bindings.actions(Binding::Key(KeyCode::KeyC)).is_empty());
+        let both = vec![Action::Inventory, Action::Crafting];
+        assert_eq!(bindings.actions(Binding::Key(KeyCode::KeyE)), both);
+        assert_eq!(
+            bindings.conflicts(),
+            BTreeMap::from([(Binding::Key(KeyCode::KeyE), both)])
+        );
+
+        // gamepad buttons clash the same way
+        let south = Binding::Gamepad(GamepadButton::South);
+        bindings.bind(Action::Screenshot, south);
+        assert_eq!(bindings.actions(south), [Action::Jump, Action::Screenshot]);
+        assert_eq!(bindings.conflicts().len(), 2);
+    }
+
+    #[test]
+    fn bindings_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("bindings-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(BINDINGS_PATH);
+
+        let mut bindings = Bindings::default();
+        bindings.bind(Action::Jump, Binding::Key(KeyCode::KeyQ));
+        bindings.bind(Action::Break, Binding::Mouse(MouseButton::Other(4)));
+        bindings.bind(Action::Place, Binding::Gamepad(GamepadButton::East));
+        bindings.save(&path).unwrap();
+        assert_eq!(Bindings::load(&path).unwrap(), bindings);
+
+        // a file that only has some actions, unbinding one
+        let file = "{ jump: [key(KeyJ), gamepad(north)], hotbar(0): [] }";
+        std::fs::write(&path, file).unwrap();
+        let loaded = Bindings::load(&path).unwrap();
+        let jump = [
+            Binding::Key(KeyCode::KeyJ),
+            Binding::Gamepad(GamepadButton::North),
+        ];
+        assert_eq!(loaded.get(Action::Jump), jump);
+        assert!(loaded.get(Action::Hotbar(0)).is_empty());
+        assert_eq!(
+            loaded.get(Action::Pause),
+            Bindings::default().get(Action::Pause)
+        );
+
+        std::fs::write(&path, "{ fly: [key(KeyF)] }").unwrap();
+        assert!(Bindings::load(&path).is_err());
+        assert_eq!(Bindings::load_or_default(&path), Bindings::default());
+        assert_eq!(
+            Bindings::load_or_default(dir.join("missing.ron")),
+            Bindings::default()
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn bindings_have_readable_names() {
+        assert_eq!(Binding::Key(KeyCode::KeyW).name(), "w");
+        assert_eq!(Binding::Key(KeyCode::Digit1).name(), "1");
+        assert_eq!(Binding::Key(KeyCode::ArrowUp).name(), "arrow up");
+        assert_eq!(Binding::Key(KeyCode::F12).name(), "f12");
+        assert_eq!(Binding::Key(KeyCode::NumpadEnter).name(), "numpad enter");
+        assert_eq!(Binding::Mouse(MouseButton::Left).name(), "left mouse");
+        assert_eq!(Binding::Gamepad(GamepadButton::DPadUp).name(), "pad up");
+        assert_eq!(Action::Hotbar(0).name(), "hotbar 1");
+    }
+
+    #[cfg(feature = "gamepad")]
+    #[test]
+    fn gilrs_buttons_are_named_by_where_they_are() {
+        use gilrs::Button;
+        let bumper = GamepadButton::from_gilrs(Button::LeftTrigger);
+        assert_eq!(bumper, Some(GamepadButton::LeftBumper));
+        let trigger = GamepadButton::from_gilrs(Button::RightTrigger2);
+        assert_eq!(trigger, Some(GamepadButton::RightTrigger));
+        assert_eq!(GamepadButton::from_gilrs(Button::Mode), None);
+    }
+}
@@ -0,0 +1,455 @@
+use std::{collections::HashMap, fmt, path::Path};
+
+use serde::Deserialize;
+
+use crate::item::registry::ItemRegistry;
+use crate::item::{ItemId, ItemStack};
+use crate::support::data::{load_ron, DataError};
+
+fn one() -> u32 {
+    1
+}
+
+#[derive(Deserialize)]
+struct ResultDef {
+    item: String,
+    #[serde(default = "one")]
+    count: u32,
+}
+
+#[derive(Deserialize)]
+struct ShapedDef {
+    pattern: Vec<String>,
+    key: HashMap<char, String>,
+    result: ResultDef,
+}
+
+#[derive(Deserialize)]
+struct ShapelessDef {
+    ingredients: Vec<String>,
+    result: ResultDef,
+}
+
+#[derive(Deserialize)]
+struct SmeltingDef {
+    input: String,
+    result: ResultDef,
+    time: u32,
+}
+
+#[derive(Deserialize)]
+struct RecipeFile {
+    #[serde(default)]
+    shaped: Vec<ShapedDef>,
+    #[serde(default)]
+    shapeless: Vec<ShapelessDef>,
+    #[serde(default)]
+    smelting: Vec<SmeltingDef>,
+}
+
+#[derive(Debug)]
+pub enum RecipeError {
+    Data(DataError),
+    UnknownItem(String),
+    UnknownKey(char),
+    EmptyPattern,
+}
+
+impl fmt::Display for RecipeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecipeError::Data(err) => write!(f, "{err}"),
+            RecipeError::UnknownItem(name) => write!(f, "recipe uses unknown item {name:?}"),
+            RecipeError::UnknownKey(c) => write!(f, "pattern uses {c:?} which isn't in its key"),
+            RecipeError::EmptyPattern => write!(f, "shaped recipe has an empty pattern"),
+        }
+    }
+}
+
+impl std::error::Error for RecipeError {}
+
+// a pattern trimmed down to the smallest box around its ingredients
+#[derive(Clone, Debug)]
+pub struct ShapedRecipe {
+    pub width: usize,
+    pub height: usize,
+    // row by row, None for slots that have to be empty
+    pub cells: Vec<Option<ItemId>>,
+    pub result: ItemStack,
+}
+
+impl ShapedRecipe {
+    fn cell(&self, x: usize, y: usize, mirrored: bool) -> Option<ItemId> {
+        let x = if mirrored { self.width - 1 - x } else { x };
+        self.cells[y * self.width + x]
+    }
+
+    fn matches(&self, grid: &CraftingGrid, bounds: Bounds, mirrored: bool) -> bool {
+        (0..self.height).all(|y| {
+            (0..self.width).all(|x| {
+                let item = grid.slot(bounds.x + x, bounds.y + y).map(|s| s.item);
+                item == self.cell(x, y, mirrored)
+            })
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ShapelessRecipe {
+    // sorted so matching can compare lists directly
+    pub ingredients: Vec<ItemId>,
+    pub result: ItemStack,
+}
+
+#[derive(Clone, Debug)]
+pub struct SmeltingRecipe {
+    pub input: ItemId,
+    pub result: ItemStack,
+    // ticks to smelt one item
+    pub time: u32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Bounds {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+// the slots of a crafting grid, from the 2x2 one in the inventory up to a 3x3 table
+#[derive(Clone, Debug)]
+pub struct CraftingGrid {
+    width: usize,
+    height: usize,
+    slots: Vec<Option<ItemStack>>,
+}
+
+impl CraftingGrid {
+    pub fn new(width: usize, height: usize) -> CraftingGrid {
+        CraftingGrid {
+            width,
+            height,
+            slots: vec![None; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn slot(&self, x: usize, y: usize) -> Option<&ItemStack> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.slots[y * self.width + x].as_ref()
+    }
+
+    // puts `stack` in the slot and hands back whatever was there
+    pub fn set_slot(&mut self, x: usize, y: usize, stack: Option<ItemStack>) -> Option<ItemStack> {
+        let stack = stack.filter(|s| s.count > 0);
+        std::mem::replace(&mut self.slots[y * self.width + x], stack)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.iter().all(Option::is_none)
+    }
+
+    // empties the grid, for handing everything back when it's closed
+    pub fn clear(&mut self) -> Vec<ItemStack> {
+        self.slots.iter_mut().filter_map(Option::take).collect()
+    }
+
+    // uses up one item from every filled slot
+    fn consume(&mut self) {
+        for slot in self.slots.iter_mut() {
+            if let Some(stack) = slot {
+                stack.count -= 1;
+                if stack.count == 0 {
+                    *slot = None;
+                }
+            }
+        }
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        let mut min = [usize::MAX; 2];
+        let mut max = [0; 2];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.slot(x, y).is_some() {
+                    min = [min[0].min(x), min[1].min(y)];
+                    max = [max[0].max(x), max[1].max(y)];
+                }
+            }
+        }
+        (min[0] != usize::MAX).then(|| Bounds {
+            x: min[0],
+            y: min[1],
+            width: max[0] - min[0] + 1,
+            height: max[1] - min[1] + 1,
+        })
+    }
+
+    fn items(&self) -> Vec<ItemId> {
+        let mut items: Vec<ItemId> = self.slots.iter().flatten().map(|s| s.item).collect();
+        items.sort_by_key(|item| item.0);
+        items
+    }
+}
+
+#[derive(Default)]
+pub struct RecipeBook {
+    shaped: Vec<ShapedRecipe>,
+    shapeless: Vec<ShapelessRecipe>,
+    smelting: HashMap<ItemId, SmeltingRecipe>,
+}
+
+impl RecipeBook {
+    pub fn load(path: impl AsRef<Path>, items: &ItemRegistry) -> Result<RecipeBook, RecipeError> {
+        let file: RecipeFile = load_ron(path).map_err(RecipeError::Data)?;
+        let id = |name: &str| {
+            items
+                .id(name)
+                .ok_or_else(|| RecipeError::UnknownItem(name.to_string()))
+        };
+        let result = |def: &ResultDef| Ok(items.stack(id(&def.item)?, def.count));
+
+        let mut book = RecipeBook::default();
+        for def in &file.shaped {
+            let rows = def
+                .pattern
+                .iter()
+                .map(|row| {
+                    row.chars()
+                        .map(|c| match c {
+                            ' ' => Ok(None),
+                            c => {
+                                let name = def.key.get(&c).ok_or(RecipeError::UnknownKey(c))?;
+                                id(name).map(Some)
+                            }
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            book.shaped.push(trim_pattern(&rows, result(&def.result)?)?);
+        }
+        for def in &file.shapeless {
+            let mut ingredients = def
+                .ingredients
+                .iter()
+                .map(|name| id(name))
+                .collect::<Result<Vec<_>, _>>()?;
+            ingredients.sort_by_key(|item| item.0);
+            book.shapeless.push(ShapelessRecipe {
+                ingredients,
+                result: result(&def.result)?,
+            });
+        }
+        for def in &file.smelting {
+            let input = id(&def.input)?;
+            let recipe = SmeltingRecipe {
+                input,
+                result: result(&def.result)?,
+                time: def.time,
+            };
+            book.smelting.insert(input, recipe);
+        }
+        Ok(book)
+    }
+
+    pub fn shaped(&self) -> &[ShapedRecipe] {
+        &self.shaped
+    }
+
+    pub fn shapeless(&self) -> &[ShapelessRecipe] {
+        &self.shapeless
+    }
+
+    // what the grid would make as it's laid out right now. shaped recipes win over
+    // shapeless ones
+    pub fn find(&self, grid: &CraftingGrid) -> Option<ItemStack> {
+        let bounds = grid.bounds()?;
+        let shaped = self.shaped.iter().find(|recipe| {
+            recipe.width == bounds.width
+                && recipe.height == bounds.height
+                && (recipe.matches(grid, bounds, false) || recipe.matches(grid, bounds, true))
+        });
+        if let Some(recipe) = shaped {
+            return Some(recipe.result);
+        }
+
+        let items = grid.items();
+        self.shapeless
+            .iter()
+            .find(|recipe| recipe.ingredients == items)
+            .map(|recipe| recipe.result)
+    }
+
+    // crafts once, using up the ingredients
+    pub fn craft(&self, grid: &mut CraftingGrid) -> Option<ItemStack> {
+        let result = self.find(grid)?;
+        grid.consume();
+        Some(result)
+    }
+
+    pub fn smelting(&self, input: ItemId) -> Option<&SmeltingRecipe> {
+        self.smelting.get(&input)
+    }
+}
+
+// cuts the empty rows and columns off round a pattern
+fn trim_pattern(
+    rows: &[Vec<Option<ItemId>>],
+    result: ItemStack,
+) -> Result<ShapedRecipe, RecipeError> {
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let filled = |x: usize, y: usize| rows[y].get(x).copied().flatten().is_some();
+
+    let xs: Vec<usize> = (0..width)
+        .filter(|&x| (0..rows.len()).any(|y| filled(x, y)))
+        .collect();
+    let ys: Vec<usize> = (0..rows.len())
+        .filter(|&y| (0..width).any(|x| filled(x, y)))
+        .collect();
+    let (Some(&x0), Some(&x1), Some(&y0), Some(&y1)) =
+        (xs.first(), xs.last(), ys.first(), ys.last())
+    else {
+        return Err(RecipeError::EmptyPattern);
+    };
+
+    let mut cells = Vec::new();
+    for row in &rows[y0..=y1] {
+        for x in x0..=x1 {
+            cells.push(row.get(x).copied().flatten());
+        }
+    }
+    Ok(ShapedRecipe {
+        width: x1 - x0 + 1,
+        height: y1 - y0 + 1,
+        cells,
+        result,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load() -> (ItemRegistry, RecipeBook) {
+        let items = ItemRegistry::load("assets/data/items.ron").unwrap();
+        let recipes = RecipeBook::load("assets/data/recipes.ron", &items).unwrap();
+        (items, recipes)
+    }
+
+    // builds a grid from rows of item names, "" for an empty slot
+    fn grid(items: &ItemRegistry, rows: &[&[&str]]) -> CraftingGrid {
+        let mut grid = CraftingGrid::new(rows[0].len(), rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, name) in row.iter().enumerate() {
+                if !name.is_empty() {
+                    let stack = items.stack(items.id(name).unwrap(), 1);
+                    grid.set_slot(x, y, Some(stack));
+                }
+            }
+        }
+        grid
+    }
+
+    fn result(items: &ItemRegistry, name: &str, count: u32) -> Option<ItemStack> {
+        Some(items.stack(items.id(name).unwrap(), count))
+    }
+
+    #[test]
+    fn shaped_recipes_match_anywhere_in_the_grid() {
+        let (items, recipes) = load();
+        let left = grid(
+            &items,
+            &[&["planks", "", ""], &["planks", "", ""], &["", "", ""]],
+        );
+        let right = grid(
+            &items,
+            &[&["", "", ""], &["", "", "planks"], &["", "", "planks"]],
+        );
+        assert_eq!(recipes.find(&left), result(&items, "stick", 4));
+        assert_eq!(recipes.find(&right), result(&items, "stick", 4));
+    }
+
+    #[test]
+    fn shaped_recipes_match_mirrored() {
+        let (items, recipes) = load();
+        let axe = grid(
+            &items,
+            &[&["planks", "planks"], &["planks", "stick"], &["", "stick"]],
+        );
+        let mirrored = grid(
+            &items,
+            &[&["planks", "planks"], &["stick", "planks"], &["stick", ""]],
+        );
+        assert_eq!(recipes.find(&axe), result(&items, "wooden_axe", 1));
+        assert_eq!(recipes.find(&mirrored), result(&items, "wooden_axe", 1));
+    }
+
+    #[test]
+    fn shaped_recipes_need_the_exact_shape() {
+        let (items, recipes) = load();
+        let extra = grid(&items, &[&["planks", "log"], &["planks", ""]]);
+        let diagonal = grid(&items, &[&["planks", ""], &["", "planks"]]);
+        assert_eq!(recipes.find(&extra), None);
+        assert_eq!(recipes.find(&diagonal), None);
+        assert_eq!(recipes.find(&CraftingGrid::new(3, 3)), None);
+    }
+
+    #[test]
+    fn shapeless_recipes_ignore_position() {
+        let (items, recipes) = load();
+        let corner = grid(&items, &[&["", ""], &["", "log"]]);
+        assert_eq!(recipes.find(&corner), result(&items, "planks", 4));
+
+        let snow = grid(
+            &items,
+            &[&["snowball", "snowball"], &["snowball", "snowball"]],
+        );
+        assert_eq!(recipes.find(&snow), result(&items, "snow", 1));
+        let short = grid(&items, &[&["snowball", "snowball"], &["snowball", ""]]);
+        assert_eq!(recipes.find(&short), None);
+    }
+
+    #[test]
+    fn crafting_uses_one_of_each_ingredient() {
+        let (items, recipes) = load();
+        let mut grid = CraftingGrid::new(2, 2);
+        grid.set_slot(0, 0, Some(items.stack(items.id("log").unwrap(), 2)));
+
+        assert_eq!(recipes.craft(&mut grid), result(&items, "planks", 4));
+        assert_eq!(grid.slot(0, 0).unwrap().count, 1);
+        assert_eq!(recipes.craft(&mut grid), result(&items, "planks", 4));
+        assert!(grid.is_empty());
+        assert_eq!(recipes.craft(&mut grid), None);
+    }
+
+    #[test]
+    fn smelting_looks_up_by_input() {
+        let (items, recipes) = load();
+        let raw_iron = items.id("raw_iron").unwrap();
+        let recipe = recipes.smelting(raw_iron).unwrap();
+        assert_eq!(Some(recipe.result), result(&items, "iron_ingot", 1));
+        assert!(recipes.smelting(items.id("stick").unwrap()).is_none());
+    }
+
+    #[test]
+    fn patterns_are_trimmed_when_loaded() {
+        let p = Some(ItemId(1));
+        let rows = vec![vec![None, None, None], vec![None, p, None], vec![None, p]];
+        let recipe = trim_pattern(&rows, ItemStack::new(ItemId(2), 1, 64)).unwrap();
+        assert_eq!((recipe.width, recipe.height), (1, 2));
+        assert_eq!(recipe.cells, vec![p, p]);
+
+        let empty = vec![vec![None, None]];
+        assert!(trim_pattern(&empty, ItemStack::new(ItemId(2), 1, 64)).is_err());
+    }
+}
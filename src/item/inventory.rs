@@ -0,0 +1,288 @@
+use crate::item::{ItemId, ItemStack};
+
+pub const HOTBAR_SIZE: usize = 9;
+pub const INVENTORY_SIZE: usize = 36;
+
+// a fixed number of slots, the first HOTBAR_SIZE of which are the hotbar
+pub struct Inventory {
+    slots: Vec<Option<ItemStack>>,
+    selected: usize,
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Inventory::new(INVENTORY_SIZE)
+    }
+}
+
+impl Inventory {
+    pub fn new(size: usize) -> Inventory {
+        Inventory {
+            slots: vec![None; size.max(HOTBAR_SIZE)],
+            selected: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.iter().all(Option::is_none)
+    }
+
+    pub fn slot(&self, index: usize) -> Option<&ItemStack> {
+        self.slots.get(index).and_then(Option::as_ref)
+    }
+
+    // puts `stack` in the slot and hands back whatever was there
+    pub fn set_slot(&mut self, index: usize, stack: Option<ItemStack>) -> Option<ItemStack> {
+        let stack = stack.filter(|s| s.count > 0);
+        std::mem::replace(&mut self.slots[index], stack)
+    }
+
+    pub fn count(&self, item: ItemId) -> u32 {
+        self.slots
+            .iter()
+            .flatten()
+            .filter(|s| s.item == item)
+            .map(|s| s.count)
+            .sum()
+    }
+
+    // tops up matching stacks first, then fills empty slots from the hotbar onwards.
+    // returns whatever didn't fit
+    pub fn insert(&mut self, stack: ItemStack) -> Option<ItemStack> {
+        let mut remaining = stack.count;
+
+        for existing in self.slots.iter_mut().flatten() {
+            if remaining == 0 {
+                break;
+            }
+            if existing.can_stack_with(&stack) {
+                let moved = remaining.min(existing.space());
+                existing.count += moved;
+                remaining -= moved;
+            }
+        }
+
+        for slot in self.slots.iter_mut() {
+            if remaining == 0 {
+                break;
+            }
+            if slot.is_none() {
+                let moved = remaining.min(stack.max_stack);
+                *slot = Some(stack.with_count(moved));
+                remaining -= moved;
+            }
+        }
+
+        (remaining > 0).then(|| stack.with_count(remaining))
+    }
+
+    // moves as much of `from` onto `to` as fits. an empty `to` takes the whole stack,
+    // different items are left alone
+    pub fn merge(&mut self, from: usize, to: usize) {
+        if from == to {
+            return;
+        }
+        let Some(source) = self.slots[from] else {
+            return;
+        };
+        match &mut self.slots[to] {
+            None => {
+                self.slots[to] = Some(source);
+                self.slots[from] = None;
+            }
+            Some(target) if target.can_stack_with(&source) => {
+                let moved = source.count.min(target.space());
+                target.count += moved;
+                let left = source.count - moved;
+                self.slots[from] = (left > 0).then(|| source.with_count(left));
+            }
+            Some(_) => (),
+        }
+    }
+
+    // takes the bigger half of a stack out of its slot, leaving the rest
+    pub fn split(&mut self, index: usize) -> Option<ItemStack> {
+        let stack = self.slots[index]?;
+        let taken = stack.count.div_ceil(2);
+        self.take(index, taken)
+    }
+
+    pub fn swap(&mut self, a: usize, b: usize) {
+        self.slots.swap(a, b);
+    }
+
+    // removes up to `count` items from a slot
+    pub fn take(&mut self, index: usize, count: u32) -> Option<ItemStack> {
+        let stack = self.slots[index]?;
+        let taken = count.min(stack.count);
+        if taken == 0 {
+            return None;
+        }
+        let left = stack.count - taken;
+        self.slots[index] = (left > 0).then(|| stack.with_count(left));
+        Some(stack.with_count(taken))
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn selected_stack(&self) -> Option<&ItemStack> {
+        self.slot(self.selected)
+    }
+
+    pub fn select(&mut self, hotbar_slot: usize) {
+        if hotbar_slot < HOTBAR_SIZE {
+            self.selected = hotbar_slot;
+        }
+    }
+
+    // moves the selection along the hotbar, wrapping round at either end
+    pub fn scroll(&mut self, steps: i32) {
+        let size = HOTBAR_SIZE as i32;
+        self.selected = (self.selected as i32 + steps).rem_euclid(size) as usize;
+    }
+
+    pub fn take_selected(&mut self, count: u32) -> Option<ItemStack> {
+        self.take(self.selected, count)
+    }
+
+    // the scroll wheel moves along the hotbar. the number keys are bindings, see input
+    pub fn process_input(&mut self, event: &glium::winit::event::WindowEvent) {
+        use glium::winit::event::{MouseScrollDelta, WindowEvent};
+
+        if let WindowEvent::MouseWheel { delta, .. } = event {
+            let lines = match delta {
+                MouseScrollDelta::LineDelta(_, y) => *y,
+                MouseScrollDelta::PixelDelta(p) => (p.y / 40.0) as f32,
+            };
+            // scrolling down moves right along the hotbar
+            if lines != 0.0 {
+                self.scroll(-lines.signum() as i32);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIRT: ItemId = ItemId(2);
+    const STONE: ItemId = ItemId(9);
+
+    fn stack(item: ItemId, count: u32) -> ItemStack {
+        ItemStack::new(item, count, 64)
+    }
+
+    #[test]
+    fn insert_fills_empty_slots_from_the_hotbar() {
+        let mut inventory = Inventory::default();
+        assert_eq!(inventory.insert(stack(DIRT, 10)), None);
+        assert_eq!(inventory.slot(0), Some(&stack(DIRT, 10)));
+        assert_eq!(inventory.insert(stack(STONE, 1)), None);
+        assert_eq!(inventory.slot(1), Some(&stack(STONE, 1)));
+    }
+
+    #[test]
+    fn insert_tops_up_existing_stacks_before_empty_slots() {
+        let mut inventory = Inventory::default();
+        inventory.set_slot(4, Some(stack(DIRT, 60)));
+        assert_eq!(inventory.insert(stack(DIRT, 10)), None);
+        assert_eq!(inventory.slot(4).unwrap().count, 64);
+        assert_eq!(inventory.slot(0), Some(&stack(DIRT, 6)));
+        assert_eq!(inventory.count(DIRT), 70);
+    }
+
+    #[test]
+    fn insert_splits_over_max_stack_and_returns_leftovers() {
+        let mut inventory = Inventory::new(HOTBAR_SIZE);
+        let leftover = inventory.insert(stack(DIRT, 64 * 9 + 5));
+        assert_eq!(leftover, Some(stack(DIRT, 5)));
+        assert!((0..HOTBAR_SIZE).all(|i| inventory.slot(i).unwrap().is_full()));
+        assert_eq!(inventory.insert(stack(STONE, 1)), Some(stack(STONE, 1)));
+    }
+
+    #[test]
+    fn insert_respects_smaller_max_stacks() {
+        let mut inventory = Inventory::default();
+        let pearls = ItemStack::new(ItemId(40), 20, 16);
+        assert_eq!(inventory.insert(pearls), None);
+        assert_eq!(inventory.slot(0).unwrap().count, 16);
+        assert_eq!(inventory.slot(1).unwrap().count, 4);
+    }
+
+    #[test]
+    fn merge_moves_what_fits_and_keeps_the_rest() {
+        let mut inventory = Inventory::default();
+        inventory.set_slot(0, Some(stack(DIRT, 40)));
+        inventory.set_slot(1, Some(stack(DIRT, 40)));
+        inventory.merge(0, 1);
+        assert_eq!(inventory.slot(1).unwrap().count, 64);
+        assert_eq!(inventory.slot(0).unwrap().count, 16);
+
+        inventory.merge(0, 2);
+        assert_eq!(inventory.slot(0), None);
+        assert_eq!(inventory.slot(2).unwrap().count, 16);
+    }
+
+    #[test]
+    fn merge_leaves_different_items_alone() {
+        let mut inventory = Inventory::default();
+        inventory.set_slot(0, Some(stack(DIRT, 5)));
+        inventory.set_slot(1, Some(stack(STONE, 5)));
+        inventory.merge(0, 1);
+        assert_eq!(inventory.slot(0), Some(&stack(DIRT, 5)));
+        assert_eq!(inventory.slot(1), Some(&stack(STONE, 5)));
+    }
+
+    #[test]
+    fn split_takes_the_bigger_half() {
+        let mut inventory = Inventory::default();
+        inventory.set_slot(0, Some(stack(DIRT, 7)));
+        assert_eq!(inventory.split(0), Some(stack(DIRT, 4)));
+        assert_eq!(inventory.slot(0), Some(&stack(DIRT, 3)));
+
+        inventory.set_slot(1, Some(stack(DIRT, 1)));
+        assert_eq!(inventory.split(1), Some(stack(DIRT, 1)));
+        assert_eq!(inventory.slot(1), None);
+        assert_eq!(inventory.split(1), None);
+    }
+
+    #[test]
+    fn swap_exchanges_slots() {
+        let mut inventory = Inventory::default();
+        inventory.set_slot(0, Some(stack(DIRT, 3)));
+        inventory.set_slot(20, Some(stack(STONE, 8)));
+        inventory.swap(0, 20);
+        assert_eq!(inventory.slot(0), Some(&stack(STONE, 8)));
+        assert_eq!(inventory.slot(20), Some(&stack(DIRT, 3)));
+    }
+
+    #[test]
+    fn take_empties_the_slot_when_it_runs_out() {
+        let mut inventory = Inventory::default();
+        inventory.set_slot(0, Some(stack(DIRT, 2)));
+        assert_eq!(inventory.take_selected(1), Some(stack(DIRT, 1)));
+        assert_eq!(inventory.take_selected(5), Some(stack(DIRT, 1)));
+        assert_eq!(inventory.selected_stack(), None);
+        assert_eq!(inventory.take_selected(1), None);
+    }
+
+    #[test]
+    fn selection_wraps_round_the_hotbar() {
+        let mut inventory = Inventory::default();
+        inventory.scroll(-1);
+        assert_eq!(inventory.selected(), HOTBAR_SIZE - 1);
+        inventory.scroll(2);
+        assert_eq!(inventory.selected(), 1);
+        inventory.select(HOTBAR_SIZE);
+        assert_eq!(inventory.selected(), 1);
+        inventory.select(5);
+        assert_eq!(inventory.selected(), 5);
+    }
+}
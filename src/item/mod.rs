@@ -0,0 +1,43 @@
+pub mod crafting;
+pub mod inventory;
+pub mod registry;
+pub mod tool;
+
+// an index into the item registry
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ItemId(pub u16);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ItemStack {
+    pub item: ItemId,
+    pub count: u32,
+    pub max_stack: u32,
+}
+
+impl ItemStack {
+    pub fn new(item: ItemId, count: u32, max_stack: u32) -> ItemStack {
+        ItemStack {
+            item,
+            count,
+            max_stack,
+        }
+    }
+
+    pub fn can_stack_with(&self, other: &ItemStack) -> bool {
+        self.item == other.item
+    }
+
+    // how many more fit on this stack
+    pub fn space(&self) -> u32 {
+        self.max_stack.saturating_sub(self.count)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.count >= self.max_stack
+    }
+
+    // a copy of this stack holding `count` items
+    pub fn with_count(&self, count: u32) -> ItemStack {
+        ItemStack { count, ..*self }
+    }
+}
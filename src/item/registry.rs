@@ -0,0 +1,196 @@
+use std::{collections::HashMap, path::Path};
+
+use serde::Deserialize;
+
+use crate::item::tool::Tool;
+use crate::item::{ItemId, ItemStack};
+use crate::support::data::{load_ron, DataError};
+use crate::support::rng::Rng;
+use crate::world::registry::BlockRegistry;
+use crate::world::voxel::BlockType;
+
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize)]
+pub enum ItemKind {
+    // places this block when used
+    Block(BlockType),
+    Tool(Tool),
+    // restores this much hunger and saturation when eaten
+    Food { hunger: u32, saturation: f32 },
+    // only good for crafting
+    Material,
+}
+
+fn default_max_stack() -> u32 {
+    64
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ItemDef {
+    pub name: String,
+    pub kind: ItemKind,
+    #[serde(default = "default_max_stack")]
+    pub max_stack: u32,
+}
+
+#[derive(Default)]
+pub struct ItemRegistry {
+    defs: Vec<ItemDef>,
+    by_name: HashMap<String, ItemId>,
+    by_block: HashMap<BlockType, ItemId>,
+}
+
+impl ItemRegistry {
+    pub fn load(path: impl AsRef<Path>) -> Result<ItemRegistry, DataError> {
+        let defs: Vec<ItemDef> = load_ron(path)?;
+        let mut registry = ItemRegistry::default();
+        for def in defs {
+            registry.register(def);
+        }
+        Ok(registry)
+    }
+
+    pub fn register(&mut self, def: ItemDef) -> ItemId {
+        let id = ItemId(self.defs.len() as u16);
+        self.by_name.insert(def.name.clone(), id);
+        if let ItemKind::Block(block_type) = def.kind {
+            self.by_block.entry(block_type).or_insert(id);
+        }
+        self.defs.push(def);
+        id
+    }
+
+    pub fn len(&self) -> usize {
+        self.defs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.defs.is_empty()
+    }
+
+    pub fn get(&self, item: ItemId) -> Option<&ItemDef> {
+        self.defs.get(item.0 as usize)
+    }
+
+    pub fn id(&self, name: &str) -> Option<ItemId> {
+        self.by_name.get(name).copied()
+    }
+
+    pub fn name(&self, item: ItemId) -> &str {
+        self.get(item).map_or("unknown", |def| def.name.as_str())
+    }
+
+    // the block an item places, if it's a block item
+    pub fn block_of(&self, item: ItemId) -> Option<BlockType> {
+        match self.get(item)?.kind {
+            ItemKind::Block(block_type) => Some(block_type),
+            _ => None,
+        }
+    }
+
+    pub fn tool_of(&self, item: ItemId) -> Option<&Tool> {
+        match &self.get(item)?.kind {
+            ItemKind::Tool(tool) => Some(tool),
+            _ => None,
+        }
+    }
+
+    pub fn food_of(&self, item: ItemId) -> Option<(u32, f32)> {
+        match self.get(item)?.kind {
+            ItemKind::Food { hunger, saturation } => Some((hunger, saturation)),
+            _ => None,
+        }
+    }
+
+    pub fn item_for_block(&self, block_type: BlockType) -> Option<ItemId> {
+        self.by_block.get(&block_type).copied()
+    }
+
+    // a stack of `count` with the item's max stack size filled in
+    pub fn stack(&self, item: ItemId, count: u32) -> ItemStack {
+        let max_stack = self.get(item).map_or(64, |def| def.max_stack);
+        ItemStack::new(item, count, max_stack)
+    }
+
+    // rolls a block's loot table. blocks without one drop their own item
+    pub fn roll_drops(
+        &self,
+        blocks: &BlockRegistry,
+        block_type: BlockType,
+        rng: &mut Rng,
+    ) -> Vec<ItemStack> {
+        let Some(table) = &blocks.get(block_type).drops else {
+            return self
+                .item_for_block(block_type)
+                .map(|item| self.stack(item, 1))
+                .into_iter()
+                .collect();
+        };
+
+        let mut drops = Vec::new();
+        for entry in table {
+            if !rng.chance(entry.chance) {
+                continue;
+            }
+            // names that don't match an item are skipped rather than crashing mid game
+            let Some(item) = self.id(&entry.item) else {
+                continue;
+            };
+            let (lo, hi) = entry.count;
+            let count = rng.range(lo as i32, hi as i32 + 1) as u32;
+            if count > 0 {
+                drops.push(self.stack(item, count));
+            }
+        }
+        drops
+    }
+
+    // item names used in loot tables that aren't in this registry, for spotting typos
+    // in the data files
+    pub fn unknown_drops(&self, blocks: &BlockRegistry) -> Vec<String> {
+        BlockType::ALL
+            .iter()
+            .filter_map(|&block_type| blocks.get(block_type).drops.as_ref())
+            .flatten()
+            .filter(|entry| self.id(&entry.item).is_none())
+            .map(|entry| entry.item.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load() -> (ItemRegistry, BlockRegistry) {
+        let items = ItemRegistry::load("assets/data/items.ron").unwrap();
+        let blocks = BlockRegistry::load("assets/data/blocks.ron").unwrap();
+        (items, blocks)
+    }
+
+    #[test]
+    fn loot_tables_only_name_real_items() {
+        let (items, blocks) = load();
+        assert_eq!(items.unknown_drops(&blocks), Vec::<String>::new());
+    }
+
+    #[test]
+    fn grass_drops_dirt_and_stone_drops_cobblestone() {
+        let (items, blocks) = load();
+        let mut rng = Rng::new(1);
+        let grass = items.roll_drops(&blocks, BlockType::Grass, &mut rng);
+        assert_eq!(grass, vec![items.stack(items.id("dirt").unwrap(), 1)]);
+        let stone = items.roll_drops(&blocks, BlockType::Stone, &mut rng);
+        assert_eq!(stone, vec![items.stack(items.id("cobblestone").unwrap(), 1)]);
+        assert!(items.roll_drops(&blocks, BlockType::Water, &mut rng).is_empty());
+    }
+
+    #[test]
+    fn blocks_without_a_table_drop_themselves() {
+        let (items, blocks) = load();
+        let planks = items.id("planks").unwrap();
+        let drops = items.roll_drops(&blocks, BlockType::Planks, &mut Rng::new(1));
+        assert_eq!(drops, vec![items.stack(planks, 1)]);
+        assert_eq!(items.block_of(planks), Some(BlockType::Planks));
+        assert_eq!(items.block_of(items.id("stone_pickaxe").unwrap()), None);
+    }
+}
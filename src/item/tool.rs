@@ -0,0 +1,169 @@
+use serde::Deserialize;
+
+use crate::game::TICKS_PER_SECOND;
+use crate::world::registry::BlockDef;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub enum ToolType {
+    Pickaxe,
+    Axe,
+    Shovel,
+    Sword,
+}
+
+// later tiers can harvest everything earlier ones can
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+pub enum ToolTier {
+    Wood,
+    Stone,
+    Iron,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize)]
+pub struct Tool {
+    pub kind: ToolType,
+    pub tier: ToolTier,
+    // how many times faster than a bare hand on blocks that want this kind of tool
+    pub speed: f32,
+}
+
+// the number of crack textures, along row 15 of the atlas
+pub const CRACK_STAGES: u32 = 10;
+pub const CRACK_ROW: i8 = 15;
+
+// blocks that need a tool take this much longer without the right one
+const WRONG_TOOL_PENALTY: f32 = 5.0 / 1.5;
+// seconds per point of hardness with the right tool
+const SECONDS_PER_HARDNESS: f32 = 1.5;
+
+// whether breaking a block with `tool` (None is a bare hand) gives its drops
+pub fn can_harvest(block: &BlockDef, tool: Option<&Tool>) -> bool {
+    match block.tier {
+        None => true,
+        Some(needed) => tool.is_some_and(|t| Some(t.kind) == block.tool && t.tier >= needed),
+    }
+}
+
+// how many ticks of holding the button it takes to break a block. None if it can't be
+// broken at all
+pub fn break_ticks(block: &BlockDef, tool: Option<&Tool>) -> Option<u32> {
+    if block.hardness < 0.0 {
+        return None;
+    }
+    let mut seconds = block.hardness * SECONDS_PER_HARDNESS;
+    if !can_harvest(block, tool) {
+        seconds *= WRONG_TOOL_PENALTY;
+    }
+    if let Some(tool) = tool.filter(|t| Some(t.kind) == block.tool) {
+        seconds /= tool.speed;
+    }
+    Some((seconds * TICKS_PER_SECOND as f32).ceil() as u32)
+}
+
+// progress on breaking one block. starts over if the player looks at something else
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Mining {
+    pub block: [i32; 3],
+    pub ticks: u32,
+    pub needed: u32,
+}
+
+impl Mining {
+    pub fn new(block: [i32; 3], needed: u32) -> Mining {
+        Mining {
+            block,
+            ticks: 0,
+            needed,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.ticks >= self.needed
+    }
+
+    // which crack texture to draw over the block
+    pub fn stage(&self) -> u32 {
+        if self.needed == 0 {
+            return CRACK_STAGES - 1;
+        }
+        (self.ticks * CRACK_STAGES / self.needed).min(CRACK_STAGES - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool(kind: ToolType, tier: ToolTier, speed: f32) -> Tool {
+        Tool { kind, tier, speed }
+    }
+
+    fn ore() -> BlockDef {
+        BlockDef {
+            hardness: 2.0,
+            tool: Some(ToolType::Pickaxe),
+            tier: Some(ToolTier::Stone),
+            ..BlockDef::default()
+        }
+    }
+
+    #[test]
+    fn harvesting_needs_the_right_kind_and_tier() {
+        let ore = ore();
+        let wood_pick = tool(ToolType::Pickaxe, ToolTier::Wood, 2.0);
+        let stone_pick = tool(ToolType::Pickaxe, ToolTier::Stone, 4.0);
+        let iron_pick = tool(ToolType::Pickaxe, ToolTier::Iron, 6.0);
+        let iron_axe = tool(ToolType::Axe, ToolTier::Iron, 6.0);
+        assert!(!can_harvest(&ore, None));
+        assert!(!can_harvest(&ore, Some(&wood_pick)));
+        assert!(can_harvest(&ore, Some(&stone_pick)));
+        assert!(can_harvest(&ore, Some(&iron_pick)));
+        assert!(!can_harvest(&ore, Some(&iron_axe)));
+        // a block without a tier drops for anything, a bare hand included
+        let dirt = BlockDef {
+            tool: Some(ToolType::Shovel),
+            ..BlockDef::default()
+        };
+        assert!(can_harvest(&dirt, None));
+        assert!(can_harvest(&dirt, Some(&wood_pick)));
+    }
+
+    #[test]
+    fn break_time_scales_with_hardness_tool_and_speed() {
+        let ore = ore();
+        let stone_pick = tool(ToolType::Pickaxe, ToolTier::Stone, 4.0);
+        let iron_pick = tool(ToolType::Pickaxe, ToolTier::Iron, 6.0);
+        let hand = break_ticks(&ore, None).unwrap();
+        let stone = break_ticks(&ore, Some(&stone_pick)).unwrap();
+        let iron = break_ticks(&ore, Some(&iron_pick)).unwrap();
+        // 2 hardness at 1.5 seconds each, a quarter of that with a speed 4 pickaxe
+        assert_eq!(stone, 15);
+        assert!(iron < stone);
+        assert!(hand > stone * 4);
+        // the wrong tool is as slow as a bare hand
+        let axe = tool(ToolType::Axe, ToolTier::Iron, 6.0);
+        assert_eq!(break_ticks(&ore, Some(&axe)), Some(hand));
+        // a tool too low a tier is still fast, it just gets nothing
+        let wood_pick = tool(ToolType::Pickaxe, ToolTier::Wood, 2.0);
+        let wood = break_ticks(&ore, Some(&wood_pick)).unwrap();
+        assert!(wood > stone && wood < hand);
+
+        let softer = BlockDef {
+            hardness: 1.0,
+            ..ore.clone()
+        };
+        assert!(break_ticks(&softer, Some(&stone_pick)).unwrap() < stone);
+        let instant = BlockDef {
+            hardness: 0.0,
+            ..BlockDef::default()
+        };
+        assert_eq!(break_ticks(&instant, None), Some(0));
+        let bedrock = BlockDef {
+            hardness: -1.0,
+            ..BlockDef::default()
+        };
+        assert_eq!(break_ticks(&bedrock, Some(&iron_pick)), None);
+        // blocks built in code default to the same hardness as the data file
+        assert_eq!(BlockDef::default().hardness, 1.0);
+    }
+}
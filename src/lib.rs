@@ -0,0 +1,15 @@
+#[macro_use]
+extern crate glium;
+
+pub mod debug;
+pub mod entity;
+pub mod game;
+pub mod input;
+pub mod item;
+pub mod player;
+pub mod render;
+pub mod settings;
+pub mod support;
+pub mod ui;
+pub mod world;
+pub mod worldgen;
@@ -1,9 +1,18 @@
-use glium::{backend::glutin::simple_window_builder::GliumEventLoop, Surface};
-#[macro_use]
-extern crate glium;
-
-mod support;
-use support::cube::*;
+use std::time::{Duration, Instant};
+
+use survival_project::debug::{debug_lines, debug_text, FrameTimes};
+use survival_project::game::{Game, TICKS_PER_SECOND};
+#[cfg(feature = "gamepad")]
+use survival_project::input::Gamepads;
+use survival_project::input::{Action, Binding, Bindings, BINDINGS_PATH};
+use survival_project::item::tool::CRACK_ROW;
+use survival_project::render::screenshot::save_in_background;
+use survival_project::render::Renderer;
+use survival_project::settings::Settings;
+use survival_project::ui::menus::{MenuAction, Menus, Screen};
+use survival_project::ui::Ui;
+use survival_project::world::mesher::overlay_mesh;
+use survival_project::world::split_pos;
 
 fn main() {
     let event_loop = glium::winit::event_loop::EventLoop::builder()
@@ -15,142 +24,163 @@ fn main() {
         .with_title("voxel game")
         .build(&event_loop);
 
-    let positions = glium::VertexBuffer::new(&display, &support::cube::VERTICES).unwrap();
-    let normals = glium::VertexBuffer::new(&display, &support::cube::NORMALS).unwrap();
-    let indices = glium::IndexBuffer::new(
-        &display,
-        glium::index::PrimitiveType::TrianglesList,
-        &support::cube::INDICES,
-    )
-    .unwrap();
-
-    // vertex shader
-    let vertex_shader_src = r#"
-        #version 150
-
-        in vec3 position;
-        in vec3 normal;
-
-        out vec3 v_normal;
-
-        uniform mat4 perspective;
-        uniform mat4 view;
-        uniform mat4 model;
-
-        void main(){
-            mat4 modelview = view * model;
-            v_normal = transpose(inverse(mat3(modelview))) * normal;
-            gl_Position = perspective * modelview * vec4(position, 1.0);
-        }
-    "#;
-
-    // fragment shader (colour)
-    let fragment_shader_src = r#"
-        #version 140
-       
-        
-        out vec4 color;
-        
-        in vec3 v_normal;
-        uniform vec3 u_light;
-
-        void main(){
-            float brightness = dot(normalize(v_normal), normalize(u_light));
-            vec3 dark_color = vec3(0.6, 0.0, 0.0);
-            vec3 regular_color = vec3(1.0, 0.0, 0.0);
-            color = vec4(mix(dark_color, regular_color, brightness), 1.0);
-        }
-    "#;
-
-    //loads shaders
-    let program =
-        glium::Program::from_source(&display, vertex_shader_src, fragment_shader_src, None)
-            .unwrap();
+    let mut game = Game::new(0);
+    let mut renderer = Renderer::new(display).expect("setting up rendering");
+    let mut settings = Settings {
+        bindings: Bindings::load_or_default(BINDINGS_PATH),
+        ..Settings::default()
+    };
+    let mut menus = Menus::new();
+    let mut ui = Ui::new();
+    let mut window_size = window.inner_size();
+
+    // the simulation runs at a fixed rate however fast frames are drawn
+    let tick_length = Duration::from_secs(1) / TICKS_PER_SECOND;
+    let mut last_frame = Instant::now();
+    let mut tick_time = Duration::ZERO;
+    let mut mouse_grabbed = false;
+    // whether the screen that's up wants the mouse, see set_mouse_grab
+    let mut wants_grab = false;
+    // F2 asks for one, it's taken after the next frame is drawn
+    let mut take_screenshot = false;
+    // F3 shows what the game's up to over the top left
+    let mut show_debug = false;
+    let mut frame_times = FrameTimes::default();
+    #[cfg(feature = "gamepad")]
+    let mut gamepads = Gamepads::new();
 
     // runs until closed
     #[allow(deprecated)]
     event_loop
         .run(move |event, window_target| {
             match event {
-                glium::winit::event::Event::WindowEvent { event, .. } => match event {
-                    // quits the game when asked
-                    glium::winit::event::WindowEvent::CloseRequested => window_target.exit(),
-
-                    //rendering
-                    glium::winit::event::WindowEvent::RedrawRequested => {
-                        // creates a new frame
-                        let mut target = display.draw();
-
-                        // adds a background and depth buffer
-                        target.clear_color_and_depth((0.0, 0.0, 1.0, 1.0), 1.0);
-
-                        let x = 0.0;
-
-                        let model = [
-                            [0.5, 0.0, 0.0, 0.0],
-                            [0.0, 0.5, 0.0, 0.0],
-                            [0.0, 0.0,  0.5, 0.0],
-                            [0.0, 0.0,  2.25, 1.0f32],
-                        ];
-                        let perspective = {
-                            let (width, height) = target.get_dimensions();
-                            let aspect_ratio = height as f32 / width as f32;
-
-                            let fov: f32 = std::f32::consts::PI / 3.0;
-                            let zfar = 1024.0;
-                            let znear = 0.1;
-
-                            let f = 1.0 / (fov / 2.0).tan();
-
-                            [
-                                [f * aspect_ratio, 0.0, 0.0, 0.0],
-                                [0.0, f, 0.0, 0.0],
-                                [0.0, 0.0, (zfar + znear) / (zfar - znear), 1.0],
-                                [0.0, 0.0, -(2.0 * zfar * znear) / (zfar - znear), 0.0],
-                            ]
-                        };
-                        let light = [-1.0, 0.5, 0.9f32];
-
-                        // camera angle i think
-                        let view = view_matrix(&[2.0, -1.0, 1.0], &[-2.0, 1.0, 1.0], &[0.0, 1.0, 0.0]);
-
-                        // rendering options
-                        let params = glium::DrawParameters {
-                            // depth buffer
-                            depth: glium::Depth {
-                                test: glium::draw_parameters::DepthTest::IfLess,
-                                write: true,
-                                ..Default::default()
-                            },
-                            // back face culling
-                            backface_culling: glium::draw_parameters::BackfaceCullingMode::CullClockwise,
-
-                            ..Default::default()
-                        };
-
-                        //draws the vertex vertex_buffer
-                        target
-                            .draw(
-                                (&positions, &normals),
-                                &indices,
-                                &program,
-                                &uniform! {model: model, view: view, perspective: perspective, u_light: light},
-                                &params,
-                            )
-                            .unwrap();
-                        // makes the frame visible
-                        target.finish().unwrap();
+                glium::winit::event::Event::WindowEvent { event, .. } => {
+                    let input = Binding::from_event(&event);
+                    if let Some((binding, pressed)) = input {
+                        if rebind(&mut menus, &mut settings.bindings, binding, pressed) {
+                            return;
+                        }
+                    }
+
+                    ui.process_input(&event);
+                    // menus have the keyboard and mouse to themselves
+                    let playing = menus.screen() == Screen::Playing;
+                    if playing {
+                        game.inventory.process_input(&event);
+                    }
+
+                    // clicking grabs the mouse if it got away while playing, rather than
+                    // breaking or placing
+                    if playing && !mouse_grabbed && matches!(input, Some((Binding::Mouse(_), true))) {
+                        mouse_grabbed = set_mouse_grab(&window, true);
+                        return;
                     }
-                    // when the window's size has changed.
-                    glium::winit::event::WindowEvent::Resized(window_size) => {
-                        display.resize(window_size.into());
+                    if let Some((binding, pressed)) = input {
+                        act(binding, pressed, &settings.bindings, &mut menus, &mut game, &mut take_screenshot, &mut show_debug);
                     }
 
-                    _ => (),
-                },
+                    match event {
+                        // quits the game when asked
+                        glium::winit::event::WindowEvent::CloseRequested => window_target.exit(),
+
+                        //rendering
+                        glium::winit::event::WindowEvent::RedrawRequested => {
+                            let now = Instant::now();
+                            tick_time += now - last_frame;
+                            frame_times.push(now - last_frame);
+                            last_frame = now;
+                            if menus.screen().pauses() {
+                                tick_time = Duration::ZERO;
+                            }
+                            while tick_time >= tick_length {
+                                game.tick();
+                                tick_time -= tick_length;
+                            }
+
+                            let alpha = tick_time.as_secs_f32() / tick_length.as_secs_f32();
+                            game.update_camera(alpha);
+                            let (centre, _) = split_pos(game.camera_block());
+                            game.generate_around(centre, settings.view_radius);
+
+                            renderer.update_chunks(
+                                &game.world,
+                                &game.camera,
+                                std::mem::take(&mut game.dirty_chunks),
+                            );
+                            renderer.set_entities(&game.entities.mesh(alpha));
+                            // cracks over the block being broken
+                            let cracks = game.mining.map(|mining| {
+                                overlay_mesh(&game.world, mining.block, [mining.stage() as i8, CRACK_ROW])
+                            });
+                            renderer.set_overlay(cracks.as_ref());
+
+                            ui.begin([window_size.width as f32, window_size.height as f32]);
+                            let action = menus.show(&mut ui, &mut game, &mut settings);
+                            settings.apply(&mut game.camera);
+                            let mut text = ui.finish();
+                            if show_debug {
+                                text.append(debug_text(&debug_lines(&game, &frame_times, renderer.stats())));
+                            }
+                            renderer.set_text(Some(&text));
+                            renderer.render(&game.world, &game.camera);
+                            if std::mem::take(&mut take_screenshot) {
+                                match renderer.read_frame() {
+                                    Ok(pixels) => {
+                                        save_in_background(pixels);
+                                    }
+                                    Err(err) => eprintln!("couldn't take screenshot: {err}"),
+                                }
+                            }
+
+                            match action {
+                                Some(MenuAction::Quit) => window_target.exit(),
+                                Some(MenuAction::SaveBindings) => save_bindings(&settings.bindings),
+                                None => (),
+                            }
+                            // the pointer's let go whenever a menu comes up, and taken back
+                            // when it's gone
+                            let grab = menus.screen().grabs_pointer();
+                            if grab != wants_grab {
+                                wants_grab = grab;
+                                mouse_grabbed = set_mouse_grab(&window, grab);
+                                if !grab {
+                                    game.stop_breaking();
+                                    game.camera.stop_moving();
+                                }
+                            }
+                        }
+                        // when the window's size has changed.
+                        glium::winit::event::WindowEvent::Resized(size) => {
+                            window_size = size;
+                            renderer.resize(window_size.into());
+                            game.camera
+                                .set_aspect_ratio(window_size.width as f32 / window_size.height.max(1) as f32);
+                        }
+
+                        // switching to another window pauses
+                        glium::winit::event::WindowEvent::Focused(false) => menus.pause(&mut game),
+
+                        _ => (),
+                    }
+                }
+
+                // mouse look
+                glium::winit::event::Event::DeviceEvent {
+                    event: glium::winit::event::DeviceEvent::MouseMotion { delta },
+                    ..
+                } if mouse_grabbed => {
+                    game.camera.process_mouse_motion(delta.0, delta.1);
+                }
 
                 // updates the window
                 glium::winit::event::Event::AboutToWait => {
+                    // gamepads are read here, winit doesn't report them
+                    #[cfg(feature = "gamepad")]
+                    for (binding, pressed) in gamepads.poll() {
+                        if !rebind(&mut menus, &mut settings.bindings, binding, pressed) {
+                            act(binding, pressed, &settings.bindings, &mut menus, &mut game, &mut take_screenshot, &mut show_debug);
+                        }
+                    }
                     window.request_redraw();
                 }
 
@@ -160,44 +190,68 @@ fn main() {
         .unwrap();
 }
 
-// lol idk
-
-fn view_matrix(position: &[f32; 3], direction: &[f32; 3], up: &[f32; 3]) -> [[f32; 4]; 4] {
-    let f = {
-        let f = direction;
-        let len = f[0] * f[0] + f[1] * f[1] + f[2] * f[2];
-        let len = len.sqrt();
-        [f[0] / len, f[1] / len, f[2] / len]
-    };
+// a binding being changed in the controls menu takes the next key or button pressed.
+// returns whether it took this one
+fn rebind(menus: &mut Menus, bindings: &mut Bindings, binding: Binding, pressed: bool) -> bool {
+    if menus.rebinding().is_none() || !pressed {
+        return false;
+    }
+    if menus.rebind(binding, bindings) {
+        save_bindings(bindings);
+    }
+    true
+}
 
-    let s = [
-        up[1] * f[2] - up[2] * f[1],
-        up[2] * f[0] - up[0] * f[2],
-        up[0] * f[1] - up[1] * f[0],
-    ];
+// sets off whatever a key or button is bound to. pause, the inventory and crafting,
+// screenshots and the debug screen work from menus too, everything else only while
+// playing
+fn act(
+    binding: Binding,
+    pressed: bool,
+    bindings: &Bindings,
+    menus: &mut Menus,
+    game: &mut Game,
+    take_screenshot: &mut bool,
+    show_debug: &mut bool,
+) {
+    let playing = menus.screen() == Screen::Playing;
+    for action in bindings.actions(binding) {
+        match action {
+            Action::Pause if pressed => menus.back(game),
+            Action::Inventory if pressed => menus.toggle(Screen::Inventory, game),
+            Action::Crafting if pressed => menus.toggle(Screen::Crafting, game),
+            Action::Screenshot if pressed => *take_screenshot = true,
+            Action::Debug if pressed => *show_debug = !*show_debug,
+            _ if !playing => (),
+            Action::Break if pressed => game.start_breaking(),
+            Action::Break => game.stop_breaking(),
+            Action::Place if pressed => menus.use_item(game),
+            Action::Hotbar(slot) if pressed => game.inventory.select(slot),
+            _ => game.camera.process_action(action, pressed),
+        }
+    }
+}
 
-    let s_norm = {
-        let len = s[0] * s[0] + s[1] * s[1] + s[2] * s[2];
-        let len = len.sqrt();
-        [s[0] / len, s[1] / len, s[2] / len]
-    };
+// returns whether the mouse ended up grabbed
+fn set_mouse_grab(window: &glium::winit::window::Window, grab: bool) -> bool {
+    use glium::winit::window::CursorGrabMode;
+    if grab {
+        // not every platform can lock the cursor in place, confining it is close enough
+        let grabbed = window
+            .set_cursor_grab(CursorGrabMode::Locked)
+            .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined))
+            .is_ok();
+        window.set_cursor_visible(!grabbed);
+        grabbed
+    } else {
+        let _ = window.set_cursor_grab(CursorGrabMode::None);
+        window.set_cursor_visible(true);
+        false
+    }
+}
 
-    let u = [
-        f[1] * s_norm[2] - f[2] * s_norm[1],
-        f[2] * s_norm[0] - f[0] * s_norm[2],
-        f[0] * s_norm[1] - f[1] * s_norm[0],
-    ];
-
-    let p = [
-        -position[0] * s_norm[0] - position[1] * s_norm[1] - position[2] * s_norm[2],
-        -position[0] * u[0] - position[1] * u[1] - position[2] * u[2],
-        -position[0] * f[0] - position[1] * f[1] - position[2] * f[2],
-    ];
-
-    [
-        [s_norm[0], u[0], f[0], 0.0],
-        [s_norm[1], u[1], f[1], 0.0],
-        [s_norm[2], u[2], f[2], 0.0],
-        [p[0], p[1], p[2], 1.0],
-    ]
+fn save_bindings(bindings: &Bindings) {
+    if let Err(err) = bindings.save(BINDINGS_PATH) {
+        eprintln!("couldn't save key bindings: {err}");
+    }
 }
@@ -0,0 +1,161 @@
+pub mod stats;
+
+use crate::player::stats::{PlayerStats, Surroundings, JUMP_EXHAUSTION, WALK_EXHAUSTION};
+use crate::world::collision::{Aabb, Body};
+use crate::world::fluid::FluidKind;
+use crate::world::World;
+
+// all per tick, at 20 ticks a second
+pub const GRAVITY: f32 = 0.08;
+const DRAG: f32 = 0.98;
+const WALK_SPEED: f32 = 0.215;
+const JUMP_SPEED: f32 = 0.42;
+const SWIM_GRAVITY: f32 = 0.02;
+const SWIM_SPEED: f32 = 0.04;
+const WATER_DRAG: f32 = 0.8;
+
+const HALF_WIDTH: f32 = 0.3;
+const HEIGHT: f32 = 1.8;
+pub const EYE_HEIGHT: f32 = 1.62;
+
+// what the player wants to do this tick, from the keyboard
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct PlayerInput {
+    // horizontal direction to walk in, world space, any length up to 1
+    pub walk: [f32; 2],
+    pub jump: bool,
+}
+
+pub struct Player {
+    pub body: Body,
+    pub stats: PlayerStats,
+    pub spawn: [f32; 3],
+}
+
+impl Player {
+    pub fn new(spawn: [f32; 3]) -> Player {
+        Player {
+            body: Body::new(spawn, HALF_WIDTH, HEIGHT),
+            stats: PlayerStats::new(),
+            spawn,
+        }
+    }
+
+    // the middle of the player's feet
+    pub fn position(&self) -> [f32; 3] {
+        self.body.position
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        self.body.aabb()
+    }
+
+    pub fn eye(&self) -> [f32; 3] {
+        let [x, y, z] = self.body.position;
+        [x, y + EYE_HEIGHT, z]
+    }
+
+    // the eye position part of the way from last tick to this one
+    pub fn interpolated_eye(&self, alpha: f32) -> [f32; 3] {
+        let [x, y, z] = self.body.interpolated(alpha);
+        [x, y + EYE_HEIGHT, z]
+    }
+
+    fn in_water(world: &World, pos: [f32; 3]) -> bool {
+        let block = pos.map(|v| v.floor() as i32);
+        FluidKind::of(world.get_voxel(block).block_type) == Some(FluidKind::Water)
+    }
+
+    // moves the player for one tick and runs their stats
+    pub fn tick(&mut self, world: &World, input: PlayerInput) {
+        let body = &mut self.body;
+        body.previous_position = body.position;
+        if self.stats.is_dead() {
+            return;
+        }
+
+        let swimming = Player::in_water(world, body.position);
+        body.velocity[0] = input.walk[0] * WALK_SPEED;
+        body.velocity[2] = input.walk[1] * WALK_SPEED;
+        if swimming {
+            body.velocity[1] = (body.velocity[1] - SWIM_GRAVITY) * WATER_DRAG;
+            if input.jump {
+                body.velocity[1] += SWIM_SPEED;
+            }
+        } else {
+            if input.jump && body.on_ground {
+                body.velocity[1] = JUMP_SPEED;
+                self.stats.exhaust(JUMP_EXHAUSTION);
+            }
+        }
+
+        // water breaks a fall
+        if let Some(speed) = body.step(world).landed {
+            if !swimming {
+                self.stats.land(speed);
+            }
+        }
+        // gravity after moving, so a jump carries its full speed for the first tick and
+        // clears a whole block
+        if !swimming {
+            body.velocity[1] = (body.velocity[1] - GRAVITY) * DRAG;
+        }
+
+        let walked = (body.position[0] - body.previous_position[0])
+            .hypot(body.position[2] - body.previous_position[2]);
+        if body.on_ground {
+            self.stats.exhaust(walked * WALK_EXHAUSTION);
+        }
+
+        self.stats.tick(Surroundings {
+            head_underwater: Player::in_water(world, self.eye()),
+        });
+    }
+
+    // back to the world spawn with everything topped up
+    pub fn respawn(&mut self) {
+        *self = Player::new(self.spawn);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::block_model::{BlockModels, BARRIER_HEIGHT};
+    use crate::world::registry::BlockRegistry;
+    use crate::world::voxel::{BlockType, Voxel};
+
+    #[test]
+    fn jumps_clear_a_block_but_not_a_fence() {
+        let mut world = World::new(BlockRegistry::default(), BlockModels::default());
+        for x in -2..=2 {
+            for z in -2..=2 {
+                let voxel = Voxel {
+                    block_type: BlockType::Stone,
+                    ..Voxel::default()
+                };
+                world.set_voxel([x, 0, z], voxel);
+            }
+        }
+        // a couple of ticks to settle onto the ground
+        let mut player = Player::new([0.5, 1.0, 0.5]);
+        for _ in 0..2 {
+            player.tick(&world, PlayerInput::default());
+        }
+        assert!(player.body.on_ground);
+
+        let jump = PlayerInput {
+            jump: true,
+            ..PlayerInput::default()
+        };
+        player.tick(&world, jump);
+        let mut apex: f32 = 0.0;
+        for _ in 0..40 {
+            apex = apex.max(player.position()[1] - 1.0);
+            player.tick(&world, PlayerInput::default());
+        }
+        assert!(apex > 1.0, "only jumped {apex}");
+        assert!(apex < BARRIER_HEIGHT, "jumped {apex}");
+        assert_eq!(player.position()[1], 1.0);
+    }
+}
@@ -0,0 +1,283 @@
+use crate::player::GRAVITY;
+
+pub const MAX_HEALTH: u32 = 20;
+pub const MAX_HUNGER: u32 = 20;
+// ticks of air, fifteen seconds
+pub const MAX_OXYGEN: u32 = 300;
+
+// falls shorter than this many blocks don't hurt
+const SAFE_FALL: f32 = 3.0;
+// how much oxygen comes back each tick out of water
+const OXYGEN_REFILL: u32 = 4;
+// drowning and starving hurt once every this many ticks
+const DROWN_INTERVAL: u32 = 20;
+const STARVE_INTERVAL: u32 = 80;
+// healing needs a mostly full stomach and happens every this many ticks
+const REGEN_HUNGER: u32 = 18;
+const REGEN_INTERVAL: u32 = 80;
+const REGEN_EXHAUSTION: f32 = 6.0;
+// every this much exhaustion costs a point of saturation, or hunger once that's gone
+const EXHAUSTION_PER_POINT: f32 = 4.0;
+const DAMAGE_EXHAUSTION: f32 = 0.1;
+
+pub const JUMP_EXHAUSTION: f32 = 0.05;
+pub const BREAK_EXHAUSTION: f32 = 0.005;
+pub const WALK_EXHAUSTION: f32 = 0.01;
+
+// what the player's surroundings are like this tick
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Surroundings {
+    pub head_underwater: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlayerStats {
+    pub health: u32,
+    pub hunger: u32,
+    // eaten food keeps hunger from dropping until this runs out. never above hunger
+    pub saturation: f32,
+    pub exhaustion: f32,
+    pub oxygen: u32,
+    drown_timer: u32,
+    starve_timer: u32,
+    regen_timer: u32,
+}
+
+impl Default for PlayerStats {
+    fn default() -> Self {
+        PlayerStats::new()
+    }
+}
+
+impl PlayerStats {
+    pub fn new() -> PlayerStats {
+        PlayerStats {
+            health: MAX_HEALTH,
+            hunger: MAX_HUNGER,
+            saturation: 5.0,
+            exhaustion: 0.0,
+            oxygen: MAX_OXYGEN,
+            drown_timer: 0,
+            starve_timer: 0,
+            regen_timer: 0,
+        }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.health == 0
+    }
+
+    pub fn damage(&mut self, amount: u32) {
+        if amount == 0 {
+            return;
+        }
+        self.health = self.health.saturating_sub(amount);
+        self.exhaust(DAMAGE_EXHAUSTION);
+    }
+
+    pub fn heal(&mut self, amount: u32) {
+        self.health = (self.health + amount).min(MAX_HEALTH);
+    }
+
+    pub fn exhaust(&mut self, amount: f32) {
+        self.exhaustion += amount;
+    }
+
+    pub fn eat(&mut self, hunger: u32, saturation: f32) {
+        self.hunger = (self.hunger + hunger).min(MAX_HUNGER);
+        self.saturation = (self.saturation + saturation).min(self.hunger as f32);
+    }
+
+    // hurts the player for hitting the ground at `speed` blocks per tick. returns the damage
+    pub fn land(&mut self, speed: f32) -> u32 {
+        let damage = fall_damage(speed);
+        self.damage(damage);
+        damage
+    }
+
+    // one fixed simulation tick of breathing, hunger and healing
+    pub fn tick(&mut self, surroundings: Surroundings) {
+        if self.is_dead() {
+            return;
+        }
+        self.tick_oxygen(surroundings);
+        self.tick_hunger();
+    }
+
+    fn tick_oxygen(&mut self, surroundings: Surroundings) {
+        if !surroundings.head_underwater {
+            self.oxygen = (self.oxygen + OXYGEN_REFILL).min(MAX_OXYGEN);
+            self.drown_timer = 0;
+            return;
+        }
+        if self.oxygen > 0 {
+            self.oxygen -= 1;
+            return;
+        }
+        self.drown_timer += 1;
+        if self.drown_timer >= DROWN_INTERVAL {
+            self.drown_timer = 0;
+            self.damage(2);
+        }
+    }
+
+    fn tick_hunger(&mut self) {
+        while self.exhaustion >= EXHAUSTION_PER_POINT {
+            self.exhaustion -= EXHAUSTION_PER_POINT;
+            if self.saturation > 0.0 {
+                self.saturation = (self.saturation - 1.0).max(0.0);
+            } else {
+                self.hunger = self.hunger.saturating_sub(1);
+            }
+        }
+
+        if self.hunger >= REGEN_HUNGER && self.health < MAX_HEALTH {
+            self.regen_timer += 1;
+            if self.regen_timer >= REGEN_INTERVAL {
+                self.regen_timer = 0;
+                self.heal(1);
+                self.exhaust(REGEN_EXHAUSTION);
+            }
+        } else {
+            self.regen_timer = 0;
+        }
+
+        if self.hunger == 0 {
+            self.starve_timer += 1;
+            if self.starve_timer >= STARVE_INTERVAL {
+                self.starve_timer = 0;
+                self.damage(1);
+            }
+        } else {
+            self.starve_timer = 0;
+        }
+    }
+}
+
+// damage for landing at `speed` blocks per tick: one point per block fallen past the safe
+// height, working the height back out from the speed
+pub fn fall_damage(speed: f32) -> u32 {
+    let height = speed * speed / (2.0 * GRAVITY);
+    (height - SAFE_FALL).ceil().max(0.0) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DRY: Surroundings = Surroundings {
+        head_underwater: false,
+    };
+    const UNDERWATER: Surroundings = Surroundings {
+        head_underwater: true,
+    };
+
+    // the speed reached after falling `height` blocks with no drag
+    fn speed_after(height: f32) -> f32 {
+        (2.0 * GRAVITY * height).sqrt()
+    }
+
+    #[test]
+    fn short_falls_are_safe() {
+        assert_eq!(fall_damage(0.0), 0);
+        assert_eq!(fall_damage(speed_after(2.5)), 0);
+    }
+
+    #[test]
+    fn fall_damage_grows_with_height() {
+        assert_eq!(fall_damage(speed_after(3.5)), 1);
+        assert_eq!(fall_damage(speed_after(10.5)), 8);
+
+        let mut stats = PlayerStats::new();
+        assert_eq!(stats.land(speed_after(30.5)), 28);
+        assert_eq!(stats.health, 0);
+        assert!(stats.is_dead());
+    }
+
+    #[test]
+    fn drowning_starts_once_oxygen_runs_out() {
+        let mut stats = PlayerStats::new();
+        for _ in 0..MAX_OXYGEN {
+            stats.tick(UNDERWATER);
+        }
+        assert_eq!(stats.oxygen, 0);
+        assert_eq!(stats.health, MAX_HEALTH);
+
+        for _ in 0..DROWN_INTERVAL * 3 {
+            stats.tick(UNDERWATER);
+        }
+        assert_eq!(stats.health, MAX_HEALTH - 6);
+    }
+
+    #[test]
+    fn oxygen_refills_out_of_water() {
+        let mut stats = PlayerStats::new();
+        for _ in 0..100 {
+            stats.tick(UNDERWATER);
+        }
+        assert_eq!(stats.oxygen, MAX_OXYGEN - 100);
+        for _ in 0..10 {
+            stats.tick(DRY);
+        }
+        assert_eq!(stats.oxygen, MAX_OXYGEN - 60);
+        for _ in 0..100 {
+            stats.tick(DRY);
+        }
+        assert_eq!(stats.oxygen, MAX_OXYGEN);
+    }
+
+    #[test]
+    fn exhaustion_uses_saturation_before_hunger() {
+        let mut stats = PlayerStats::new();
+        stats.saturation = 1.0;
+        stats.exhaust(EXHAUSTION_PER_POINT * 3.0);
+        stats.tick(DRY);
+        assert_eq!(stats.saturation, 0.0);
+        assert_eq!(stats.hunger, MAX_HUNGER - 2);
+        assert!(stats.exhaustion < EXHAUSTION_PER_POINT);
+    }
+
+    #[test]
+    fn a_full_stomach_heals_over_time() {
+        let mut stats = PlayerStats::new();
+        stats.health = 10;
+        for _ in 0..REGEN_INTERVAL * 2 {
+            stats.tick(DRY);
+        }
+        assert_eq!(stats.health, 12);
+
+        stats.hunger = REGEN_HUNGER - 1;
+        for _ in 0..REGEN_INTERVAL * 2 {
+            stats.tick(DRY);
+        }
+        assert_eq!(stats.health, 12);
+    }
+
+    #[test]
+    fn starving_hurts_until_death() {
+        let mut stats = PlayerStats::new();
+        stats.hunger = 0;
+        stats.saturation = 0.0;
+        for _ in 0..STARVE_INTERVAL * 5 {
+            stats.tick(DRY);
+        }
+        assert_eq!(stats.health, MAX_HEALTH - 5);
+
+        for _ in 0..STARVE_INTERVAL * MAX_HEALTH {
+            stats.tick(DRY);
+        }
+        assert!(stats.is_dead());
+    }
+
+    #[test]
+    fn eating_caps_saturation_at_hunger() {
+        let mut stats = PlayerStats::new();
+        stats.hunger = 4;
+        stats.saturation = 0.0;
+        stats.eat(4, 20.0);
+        assert_eq!(stats.hunger, 8);
+        assert_eq!(stats.saturation, 8.0);
+        stats.eat(30, 0.0);
+        assert_eq!(stats.hunger, MAX_HUNGER);
+    }
+}
@@ -0,0 +1,116 @@
+use std::ffi::{c_void, CString};
+use std::fmt;
+use std::rc::Rc;
+
+use glium::backend::{Backend, Context, Facade};
+use glium::debug::DebugCallbackBehavior;
+use glium::glutin::api::egl::context::PossiblyCurrentContext;
+use glium::glutin::api::egl::device::Device;
+use glium::glutin::api::egl::display::Display as EglDisplay;
+use glium::glutin::config::{ConfigSurfaceTypes, ConfigTemplateBuilder};
+use glium::glutin::context::ContextAttributesBuilder;
+use glium::glutin::prelude::*;
+use glium::SwapBuffersError;
+
+// mesa's software renderer. it's picked over any gpu so pictures come out the same on
+// every machine, including ones with no gpu at all
+const SOFTWARE_DEVICE: &str = "EGL_MESA_device_software";
+
+#[derive(Debug)]
+pub enum HeadlessError {
+    NoDevice,
+    Egl(glium::glutin::error::Error),
+    OpenGl(glium::IncompatibleOpenGl),
+}
+
+impl fmt::Display for HeadlessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeadlessError::NoDevice => write!(f, "no egl devices to render with"),
+            HeadlessError::Egl(err) => write!(f, "couldn't set up egl: {err}"),
+            HeadlessError::OpenGl(err) => write!(f, "opengl version isn't supported: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for HeadlessError {}
+
+impl From<glium::glutin::error::Error> for HeadlessError {
+    fn from(err: glium::glutin::error::Error) -> Self {
+        HeadlessError::Egl(err)
+    }
+}
+
+// an egl context with no window or surface. everything is drawn into textures
+struct EglBackend {
+    display: EglDisplay,
+    context: PossiblyCurrentContext,
+}
+
+unsafe impl Backend for EglBackend {
+    fn swap_buffers(&self) -> Result<(), SwapBuffersError> {
+        Ok(())
+    }
+
+    unsafe fn get_proc_address(&self, symbol: &str) -> *const c_void {
+        let symbol = CString::new(symbol).unwrap();
+        self.display.get_proc_address(&symbol)
+    }
+
+    // there's no default framebuffer to draw to
+    fn get_framebuffer_dimensions(&self) -> (u32, u32) {
+        (1, 1)
+    }
+
+    fn resize(&self, _: (u32, u32)) {}
+
+    fn is_current(&self) -> bool {
+        self.context.is_current()
+    }
+
+    unsafe fn make_current(&self) {
+        self.context
+            .make_current_surfaceless()
+            .expect("making the headless context current");
+    }
+}
+
+// somewhere to render without a window, for tests and tools
+pub struct Headless {
+    context: Rc<Context>,
+}
+
+impl Headless {
+    pub fn new() -> Result<Headless, HeadlessError> {
+        let devices: Vec<Device> = Device::query_devices()?.collect();
+        let device = devices
+            .iter()
+            .find(|device| device.extensions().contains(SOFTWARE_DEVICE))
+            .or(devices.first())
+            .ok_or(HeadlessError::NoDevice)?;
+
+        // safe as no display handle is passed in
+        let display = unsafe { EglDisplay::with_device(device, None)? };
+        let template = ConfigTemplateBuilder::new()
+            .with_surface_type(ConfigSurfaceTypes::empty())
+            .build();
+        let config = unsafe { display.find_configs(template)? }
+            .next()
+            .ok_or(HeadlessError::NoDevice)?;
+        let attributes = ContextAttributesBuilder::new().build(None);
+        let context =
+            unsafe { display.create_context(&config, &attributes)? }.make_current_surfaceless()?;
+
+        let backend = EglBackend { display, context };
+        // safe as the context was just made current on this thread
+        let context = unsafe { Context::new(backend, true, DebugCallbackBehavior::default()) }
+            .map_err(HeadlessError::OpenGl)?;
+        Ok(Headless { context })
+    }
+}
+
+impl Facade for Headless {
+    fn get_context(&self) -> &Rc<Context> {
+        &self.context
+    }
+}
@@ -0,0 +1,413 @@
+pub mod headless;
+pub mod screenshot;
+pub mod shaders;
+pub mod text;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use glium::backend::Facade;
+use glium::framebuffer::{DepthRenderBuffer, SimpleFrameBuffer, ValidationError};
+use glium::glutin::surface::WindowSurface;
+use glium::index::PrimitiveType;
+use glium::texture::{DepthFormat, RawImage2d, SrgbTexture2d, Texture2d};
+use glium::uniforms::MagnifySamplerFilter;
+use glium::{
+    BackfaceCullingMode, Blend, Depth, DepthTest, Display, DrawParameters, IndexBuffer, ReadError,
+    Surface, VertexBuffer,
+};
+use image::RgbaImage;
+
+use crate::render::headless::Headless;
+use crate::render::screenshot::flipped;
+use crate::render::shaders::{ShaderError, ShaderProgram};
+use crate::render::text::{TextMesh, TextVertex, FONT_PATH};
+use crate::support::camera::CameraState;
+use crate::support::packed::PackedVertex;
+use crate::support::rendering_info::ChunkVertex;
+use crate::world::lod::{choose_lod, chunk_distance, mesh_chunk_lod};
+use crate::world::mesher::ChunkMesh;
+use crate::world::visibility::{visible_chunks, Visibility};
+use crate::world::{split_pos, ChunkPos, World};
+
+const ATLAS_PATH: &str = "assets/textures/texture-atlas.png";
+const SKY_COLOUR: (f32, f32, f32, f32) = (0.0, 0.0, 1.0, 1.0);
+// world space, towards the light
+const LIGHT: [f32; 3] = [-1.0, 0.5, 0.9];
+// how often debug builds look for edited shaders
+const SHADER_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+// chunks remeshed a frame because the camera moved them to another level of detail.
+// edited chunks don't count, they're always remeshed straight away
+const LOD_CHANGES_PER_FRAME: usize = 8;
+
+#[derive(Debug)]
+pub enum RenderError {
+    Shader(ShaderError),
+    Image(image::ImageError),
+    Texture(glium::texture::TextureCreationError),
+    RenderBuffer(glium::framebuffer::RenderBufferCreationError),
+    Framebuffer(ValidationError),
+    Read(ReadError),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::Shader(err) => write!(f, "{err}"),
+            RenderError::Image(err) => write!(f, "couldn't load texture: {err}"),
+            RenderError::Texture(err) => write!(f, "couldn't upload texture: {err}"),
+            RenderError::RenderBuffer(err) => write!(f, "couldn't make depth buffer: {err}"),
+            RenderError::Framebuffer(err) => write!(f, "couldn't make framebuffer: {err}"),
+            RenderError::Read(err) => write!(f, "couldn't read pixels back: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+// a mesh uploaded to the gpu
+struct GpuMesh<V: Copy> {
+    vertices: VertexBuffer<V>,
+    indices: IndexBuffer<u32>,
+}
+
+impl<V: glium::Vertex> GpuMesh<V> {
+    // None for an empty mesh, there's nothing to draw
+    fn new(facade: &impl Facade, vertices: &[V], indices: &[u32]) -> Option<Self> {
+        if indices.is_empty() {
+            return None;
+        }
+        Some(GpuMesh {
+            vertices: VertexBuffer::new(facade, vertices).unwrap(),
+            indices: IndexBuffer::new(facade, PrimitiveType::TrianglesList, indices).unwrap(),
+        })
+    }
+}
+
+// counts for the debug screen
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RenderStats {
+    pub loaded_chunks: usize,
+    // chunks drawn last frame, after the visibility walk
+    pub drawn_chunks: usize,
+    // level of detail changes waiting for a later frame
+    pub lod_changes: usize,
+}
+
+// a chunk's packed mesh, None if there's nothing to draw at its level of detail
+struct GpuChunk {
+    mesh: Option<GpuMesh<PackedVertex>>,
+    // where its corner is in the world
+    origin: [f32; 3],
+    // the cell size it was meshed with
+    lod: u32,
+    // which of its faces can see each other, for skipping chunks hidden behind it
+    visibility: Visibility,
+    // the biome colour for its grass and leaves
+    tint: [f32; 3],
+}
+
+// owns everything on the gpu: shaders, the atlas, and the meshes for chunks, entities and
+// overlays. the game hands it meshes when they change and it draws whatever it has.
+// it draws to a window, or into images with a headless::Headless
+pub struct Renderer<F = Display<WindowSurface>> {
+    facade: F,
+    block_program: ShaderProgram,
+    entity_program: ShaderProgram,
+    overlay_program: ShaderProgram,
+    text_program: ShaderProgram,
+    // when shader files were last checked for changes
+    last_shader_check: Instant,
+    atlas: SrgbTexture2d,
+    font: Texture2d,
+    // solid geometry, depth tested and written
+    opaque: DrawParameters<'static>,
+    // drawn on top of solid geometry without hiding anything behind it
+    overlay: DrawParameters<'static>,
+    // text and anything else flat over the screen, on top of everything
+    ui: DrawParameters<'static>,
+    chunks: HashMap<ChunkPos, GpuChunk>,
+    entities: Option<GpuMesh<ChunkVertex>>,
+    overlay_mesh: Option<GpuMesh<ChunkVertex>>,
+    text: Option<GpuMesh<TextVertex>>,
+    stats: RenderStats,
+}
+
+fn load_image(path: &str) -> Result<RawImage2d<'static, u8>, RenderError> {
+    let image = image::open(path).map_err(RenderError::Image)?.to_rgba8();
+    let size = image.dimensions();
+    Ok(RawImage2d::from_raw_rgba_reversed(&image.into_raw(), size))
+}
+
+impl<F: Facade> Renderer<F> {
+    pub fn new(facade: F) -> Result<Renderer<F>, RenderError> {
+        let program = |name| ShaderProgram::load(&facade, name).map_err(RenderError::Shader);
+        let depth = |test, write| Depth {
+            test,
+            write,
+            ..Default::default()
+        };
+
+        Ok(Renderer {
+            block_program: program("block")?,
+            entity_program: program("entity")?,
+            overlay_program: program("overlay")?,
+            text_program: program("text")?,
+            last_shader_check: Instant::now(),
+            atlas: SrgbTexture2d::new(&facade, load_image(ATLAS_PATH)?)
+                .map_err(RenderError::Texture)?,
+            font: Texture2d::new(&facade, load_image(FONT_PATH)?).map_err(RenderError::Texture)?,
+            opaque: DrawParameters {
+                depth: depth(DepthTest::IfLess, true),
+                backface_culling: BackfaceCullingMode::CullClockwise,
+                ..Default::default()
+            },
+            overlay: DrawParameters {
+                depth: depth(DepthTest::IfLessOrEqual, false),
+                blend: Blend::alpha_blending(),
+                backface_culling: BackfaceCullingMode::CullClockwise,
+                ..Default::default()
+            },
+            ui: DrawParameters {
+                blend: Blend::alpha_blending(),
+                ..Default::default()
+            },
+            chunks: HashMap::new(),
+            entities: None,
+            overlay_mesh: None,
+            text: None,
+            stats: RenderStats::default(),
+            facade,
+        })
+    }
+
+    fn mesh_chunk(&mut self, world: &World, pos: ChunkPos, lod: u32) {
+        let mesh = mesh_chunk_lod(world, pos, lod);
+        let chunk = GpuChunk {
+            mesh: GpuMesh::new(&self.facade, &mesh.vertices, &mesh.indices),
+            origin: mesh.origin.map(|c| c as f32),
+            lod,
+            visibility: world
+                .chunks
+                .get(&pos)
+                .map_or(Visibility::OPEN, |chunk| Visibility::compute(world, chunk)),
+            tint: world.chunks.get(&pos).map_or([1.0; 3], |chunk| chunk.tint),
+        };
+        self.chunks.insert(pos, chunk);
+    }
+
+    // remeshes chunks that changed, then those the camera has moved far enough from or
+    // close enough to for another level of detail
+    pub fn update_chunks(
+        &mut self,
+        world: &World,
+        camera: &CameraState,
+        dirty: impl IntoIterator<Item = ChunkPos>,
+    ) {
+        for pos in dirty {
+            let current = self.chunks.get(&pos).map(|chunk| chunk.lod);
+            let lod = choose_lod(chunk_distance(camera, pos), current);
+            self.mesh_chunk(world, pos, lod);
+        }
+
+        let mut changes: Vec<(f32, ChunkPos, u32)> = self
+            .chunks
+            .iter()
+            .filter_map(|(pos, chunk)| {
+                let distance = chunk_distance(camera, *pos);
+                let lod = choose_lod(distance, Some(chunk.lod));
+                (lod != chunk.lod).then_some((distance, *pos, lod))
+            })
+            .collect();
+        // nearest first, they're the ones that show
+        changes.sort_by(|a, b| a.0.total_cmp(&b.0));
+        self.stats.lod_changes = changes.len().saturating_sub(LOD_CHANGES_PER_FRAME);
+        for (_, pos, lod) in changes.into_iter().take(LOD_CHANGES_PER_FRAME) {
+            if world.chunks.contains_key(&pos) {
+                self.mesh_chunk(world, pos, lod);
+            }
+        }
+    }
+
+    // entities move every frame, so their mesh is replaced every frame
+    pub fn set_entities(&mut self, mesh: &ChunkMesh) {
+        self.entities = GpuMesh::new(&self.facade, &mesh.vertices, &mesh.indices);
+    }
+
+    pub fn set_overlay(&mut self, mesh: Option<&ChunkMesh>) {
+        self.overlay_mesh =
+            mesh.and_then(|mesh| GpuMesh::new(&self.facade, &mesh.vertices, &mesh.indices));
+    }
+
+    pub fn set_text(&mut self, mesh: Option<&TextMesh>) {
+        self.text = mesh.and_then(|mesh| GpuMesh::new(&self.facade, &mesh.vertices, &mesh.indices));
+    }
+
+    pub fn stats(&self) -> RenderStats {
+        self.stats
+    }
+
+    // picks up edits to the shader files while the game runs. a shader that doesn't
+    // compile is reported and the old one kept
+    fn reload_shaders(&mut self) {
+        if self.last_shader_check.elapsed() < SHADER_CHECK_INTERVAL {
+            return;
+        }
+        self.last_shader_check = Instant::now();
+        for shader in [
+            &mut self.block_program,
+            &mut self.entity_program,
+            &mut self.overlay_program,
+            &mut self.text_program,
+        ] {
+            match shader.reload_if_changed(&self.facade) {
+                Some(Ok(())) => eprintln!("reloaded {} shaders", shader.name()),
+                Some(Err(err)) => eprintln!("{err}"),
+                None => (),
+            }
+        }
+    }
+
+    // draws the world from the camera onto `target`
+    pub fn draw(&mut self, target: &mut impl Surface, world: &World, camera: &CameraState) {
+        if cfg!(debug_assertions) {
+            self.reload_shaders();
+        }
+        // chunks that were unloaded since the last frame
+        self.chunks.retain(|pos, _| world.chunks.contains_key(pos));
+
+        target.clear_color_and_depth(SKY_COLOUR, 1.0);
+
+        let perspective = camera.get_perspective();
+        let view = camera.get_view();
+        let atlas = self
+            .atlas
+            .sampled()
+            .magnify_filter(MagnifySamplerFilter::Nearest);
+
+        // chunks walled off from the camera, like caves under a mountain, aren't drawn.
+        // there's nothing to walk out from when the camera is outside the meshed chunks
+        let (x, y, z) = camera.position();
+        let (dx, dy, dz) = camera.direction();
+        let camera_chunk = split_pos([x, y, z].map(|c| c.floor() as i32)).0;
+        let visible = self.chunks.contains_key(&camera_chunk).then(|| {
+            visible_chunks([x, y, z], [dx, dy, dz], |pos| {
+                self.chunks.get(&pos).map(|chunk| chunk.visibility)
+            })
+        });
+
+        self.stats.loaded_chunks = self.chunks.len();
+        self.stats.drawn_chunks = 0;
+        for (pos, chunk) in &self.chunks {
+            if visible
+                .as_ref()
+                .is_some_and(|visible| !visible.contains(pos))
+            {
+                continue;
+            }
+            let Some(mesh) = &chunk.mesh else {
+                continue;
+            };
+            self.stats.drawn_chunks += 1;
+            let uniforms = uniform! {
+                view: view,
+                perspective: perspective,
+                chunk_origin: chunk.origin,
+                tint: chunk.tint,
+                u_light: LIGHT,
+                atlas: atlas,
+            };
+            target
+                .draw(
+                    &mesh.vertices,
+                    &mesh.indices,
+                    self.block_program.program(),
+                    &uniforms,
+                    &self.opaque,
+                )
+                .unwrap();
+        }
+
+        if let Some(mesh) = &self.entities {
+            target
+                .draw(
+                    &mesh.vertices,
+                    &mesh.indices,
+                    self.entity_program.program(),
+                    &uniform! {view: view, perspective: perspective, u_light: LIGHT},
+                    &self.opaque,
+                )
+                .unwrap();
+        }
+
+        if let Some(mesh) = &self.overlay_mesh {
+            target
+                .draw(
+                    &mesh.vertices,
+                    &mesh.indices,
+                    self.overlay_program.program(),
+                    &uniform! {view: view, perspective: perspective, atlas: atlas},
+                    &self.overlay,
+                )
+                .unwrap();
+        }
+
+        if let Some(mesh) = &self.text {
+            let (width, height) = target.get_dimensions();
+            let font = self
+                .font
+                .sampled()
+                .magnify_filter(MagnifySamplerFilter::Nearest);
+            target
+                .draw(
+                    &mesh.vertices,
+                    &mesh.indices,
+                    self.text_program.program(),
+                    &uniform! {screen_size: [width as f32, height as f32], font: font},
+                    &self.ui,
+                )
+                .unwrap();
+        }
+    }
+}
+
+impl Renderer<Display<WindowSurface>> {
+    pub fn resize(&self, size: (u32, u32)) {
+        self.facade.resize(size);
+    }
+
+    // draws a frame to the window
+    pub fn render(&mut self, world: &World, camera: &CameraState) {
+        let mut target = self.facade.draw();
+        self.draw(&mut target, world, camera);
+        target.finish().unwrap();
+    }
+
+    // the last frame drawn, bottom row first. read straight after `render`, before
+    // anything else is drawn over it
+    pub fn read_frame(&self) -> Result<RawImage2d<'static, u8>, RenderError> {
+        self.facade.read_front_buffer().map_err(RenderError::Read)
+    }
+}
+
+impl Renderer<Headless> {
+    // draws the world into a `size` picture, top row first
+    pub fn render_image(
+        &mut self,
+        world: &World,
+        camera: &CameraState,
+        size: (u32, u32),
+    ) -> Result<RgbaImage, RenderError> {
+        let (width, height) = size;
+        let colour =
+            SrgbTexture2d::empty(&self.facade, width, height).map_err(RenderError::Texture)?;
+        let depth = DepthRenderBuffer::new(&self.facade, DepthFormat::I24, width, height)
+            .map_err(RenderError::RenderBuffer)?;
+        let mut target = SimpleFrameBuffer::with_depth_buffer(&self.facade, &colour, &depth)
+            .map_err(RenderError::Framebuffer)?;
+        self.draw(&mut target, world, camera);
+
+        Ok(flipped(colour.read()))
+    }
+}
@@ -0,0 +1,121 @@
+use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{fmt, fs};
+
+use glium::texture::RawImage2d;
+use image::{imageops, RgbaImage};
+
+pub const SCREENSHOT_DIR: &str = "screenshots";
+
+#[derive(Debug)]
+pub enum ScreenshotError {
+    Io(PathBuf, std::io::Error),
+    Image(PathBuf, image::ImageError),
+}
+
+impl fmt::Display for ScreenshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScreenshotError::Io(path, err) => write!(f, "couldn't make {}: {err}", path.display()),
+            ScreenshotError::Image(path, err) => {
+                write!(f, "couldn't save {}: {err}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScreenshotError {}
+
+// pixels read back from opengl, whose rows go bottom up
+pub fn flipped(pixels: RawImage2d<u8>) -> RgbaImage {
+    let image = RgbaImage::from_raw(pixels.width, pixels.height, pixels.data.into_owned())
+        .expect("pixels read back as rgba");
+    imageops::flip_vertical(&image)
+}
+
+// "2026-10-19_08-44-17-250", in utc as there's no time zone to go on without a
+// dependency for it
+fn timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    let (days, second) = ((seconds / 86400) as i64, seconds % 86400);
+
+    // days since 1970 to a date, counting in 400 year eras that start on the 1st of march
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+    format!(
+        "{year:04}-{month:02}-{day:02}_{:02}-{:02}-{:02}-{:03}",
+        second / 3600,
+        second / 60 % 60,
+        second % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+// writes `image` into `dir` named after `time`, making the directory if it isn't there
+pub fn save(image: &RgbaImage, dir: &Path, time: SystemTime) -> Result<PathBuf, ScreenshotError> {
+    fs::create_dir_all(dir).map_err(|err| ScreenshotError::Io(dir.to_path_buf(), err))?;
+    let path = dir.join(format!("{}.png", timestamp(time)));
+    image
+        .save(&path)
+        .map_err(|err| ScreenshotError::Image(path.clone(), err))?;
+    Ok(path)
+}
+
+// flipping and encoding a png takes long enough to drop frames, so it's done on another
+// thread. the time is taken now so it's when the picture was, not when it was written
+pub fn save_in_background(pixels: RawImage2d<'static, u8>) -> JoinHandle<()> {
+    let time = SystemTime::now();
+    thread::spawn(move || {
+        let image = flipped(pixels);
+        match save(&image, Path::new(SCREENSHOT_DIR), time) {
+            Ok(path) => eprintln!("saved screenshot to {}", path.display()),
+            Err(err) => eprintln!("{err}"),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn timestamps_are_utc_dates() {
+        assert_eq!(timestamp(UNIX_EPOCH), "1970-01-01_00-00-00-000");
+        // a leap day
+        let time = UNIX_EPOCH + Duration::from_millis(1_709_210_096_789);
+        assert_eq!(timestamp(time), "2024-02-29_12-34-56-789");
+        let time = UNIX_EPOCH + Duration::from_secs(951_868_800);
+        assert_eq!(timestamp(time), "2000-03-01_00-00-00-000");
+    }
+
+    #[test]
+    fn saves_the_right_way_up() {
+        // two rows as opengl gives them, bottom first
+        let pixels = RawImage2d::from_raw_rgba(vec![0, 0, 0, 255, 255, 255, 255, 255], (1, 2));
+        let image = flipped(pixels);
+        assert_eq!(image.get_pixel(0, 0).0, [255, 255, 255, 255]);
+
+        let dir = std::env::temp_dir().join(format!("screenshots-{}", std::process::id()));
+        let path = save(&image, &dir, UNIX_EPOCH).unwrap();
+        assert_eq!(path, dir.join("1970-01-01_00-00-00-000.png"));
+        let saved = image::open(&path).unwrap().to_rgba8();
+        assert_eq!(saved, image);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
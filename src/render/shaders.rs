@@ -0,0 +1,184 @@
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use std::{fmt, fs};
+
+use glium::program::ShaderType;
+use glium::{Program, ProgramCreationError};
+
+const SHADER_DIR: &str = "assets/shaders";
+
+#[derive(Debug)]
+pub enum ShaderError {
+    Io(PathBuf, std::io::Error),
+    // the driver's log with its line numbers pointed back at the file
+    Compile(PathBuf, String),
+    Link(String, String),
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaderError::Io(path, err) => write!(f, "couldn't read {}: {err}", path.display()),
+            ShaderError::Compile(path, log) => {
+                write!(f, "couldn't compile {}:\n{log}", path.display())
+            }
+            ShaderError::Link(name, log) => write!(f, "couldn't link {name} shaders:\n{log}"),
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+// the line a driver's error message is about. drivers disagree on the format: mesa
+// writes "0:12(5): error", nvidia "0(12) : error" and others "ERROR: 0:12: message"
+fn error_line(message: &str) -> Option<usize> {
+    let message = message.trim_start();
+    let message = message
+        .strip_prefix("ERROR:")
+        .unwrap_or(message)
+        .trim_start();
+    let rest = message.strip_prefix('0')?;
+    let digits = match rest.chars().next()? {
+        ':' | '(' => &rest[1..],
+        _ => return None,
+    };
+    let end = digits.find(|c: char| !c.is_ascii_digit())?;
+    digits[..end].parse().ok()
+}
+
+// rewrites a compile log so each message starts with the file and line it's about,
+// the way a compiler would
+pub fn locate_errors(path: &Path, log: &str) -> String {
+    log.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| match error_line(line) {
+            Some(number) => format!("{}:{number}: {}", path.display(), line.trim()),
+            None => format!("{}: {}", path.display(), line.trim()),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+// a vertex and fragment shader pair read from assets/shaders/<name>.vert and .frag,
+// which can be rebuilt when the files change
+pub struct ShaderProgram {
+    name: String,
+    vertex: PathBuf,
+    fragment: PathBuf,
+    program: Program,
+    // when the files were last read, to notice edits
+    loaded: [Option<SystemTime>; 2],
+}
+
+impl ShaderProgram {
+    pub fn load(facade: &impl glium::backend::Facade, name: &str) -> Result<Self, ShaderError> {
+        let dir = Path::new(SHADER_DIR);
+        let vertex = dir.join(format!("{name}.vert"));
+        let fragment = dir.join(format!("{name}.frag"));
+        let loaded = [modified(&vertex), modified(&fragment)];
+        let program = compile(facade, name, &vertex, &fragment)?;
+        Ok(ShaderProgram {
+            name: name.to_string(),
+            vertex,
+            fragment,
+            program,
+            loaded,
+        })
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // rebuilds the program if either file changed since it was last read. on failure
+    // the last program that worked is kept, so a typo doesn't take the game down
+    pub fn reload_if_changed(
+        &mut self,
+        facade: &impl glium::backend::Facade,
+    ) -> Option<Result<(), ShaderError>> {
+        let now = [modified(&self.vertex), modified(&self.fragment)];
+        if now == self.loaded {
+            return None;
+        }
+        // don't try again until the files change again
+        self.loaded = now;
+        Some(
+            compile(facade, &self.name, &self.vertex, &self.fragment)
+                .map(|program| self.program = program),
+        )
+    }
+}
+
+fn compile(
+    facade: &impl glium::backend::Facade,
+    name: &str,
+    vertex: &Path,
+    fragment: &Path,
+) -> Result<Program, ShaderError> {
+    let read = |path: &Path| {
+        fs::read_to_string(path).map_err(|err| ShaderError::Io(path.to_path_buf(), err))
+    };
+    let vertex_src = read(vertex)?;
+    let fragment_src = read(fragment)?;
+    Program::from_source(facade, &vertex_src, &fragment_src, None).map_err(|err| match err {
+        ProgramCreationError::CompilationError(log, ShaderType::Fragment) => {
+            ShaderError::Compile(fragment.to_path_buf(), locate_errors(fragment, &log))
+        }
+        ProgramCreationError::CompilationError(log, _) => {
+            ShaderError::Compile(vertex.to_path_buf(), locate_errors(vertex, &log))
+        }
+        ProgramCreationError::LinkingError(log) => ShaderError::Link(name.to_string(), log),
+        other => ShaderError::Link(name.to_string(), other.to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_lines_in_every_driver_format() {
+        assert_eq!(error_line("0:12(5): error: `foo' undeclared"), Some(12));
+        assert_eq!(
+            error_line("0(7) : error C1008: undefined variable"),
+            Some(7)
+        );
+        assert_eq!(
+            error_line("ERROR: 0:31: 'x' : undeclared identifier"),
+            Some(31)
+        );
+        assert_eq!(error_line("error: linking failed"), None);
+    }
+
+    #[test]
+    fn points_errors_at_the_file() {
+        let log = "0:3(1): error: syntax error\n\nwarning: something vague\n";
+        let located = locate_errors(Path::new("assets/shaders/block.frag"), log);
+        assert_eq!(
+            located,
+            "assets/shaders/block.frag:3: 0:3(1): error: syntax error\n\
+             assets/shaders/block.frag: warning: something vague"
+        );
+    }
+
+    #[test]
+    fn shader_files_agree_on_a_version() {
+        for entry in fs::read_dir(SHADER_DIR).unwrap() {
+            let path = entry.unwrap().path();
+            let source = fs::read_to_string(&path).unwrap();
+            assert!(
+                source.starts_with("#version 150\n"),
+                "{} should start with #version 150",
+                path.display()
+            );
+        }
+    }
+}
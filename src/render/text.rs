@@ -0,0 +1,140 @@
+// text drawn flat over the screen from a bitmap font, for the debug screen and menus.
+// positions are in pixels from the top left corner of the window
+
+pub const FONT_PATH: &str = "assets/textures/font.png";
+// every glyph takes up the same cell, so a line is as wide as its length
+pub const GLYPH_SIZE: [f32; 2] = [8.0, 16.0];
+// the font has printable ascii in rows of 16, starting with a space
+const COLUMNS: u32 = 16;
+const ROWS: u32 = 6;
+const FIRST: char = ' ';
+// the cell after '~' is filled in, for solid rectangles
+const SOLID: char = '\u{7f}';
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TextVertex {
+    pub position: [f32; 2],
+    pub tex_coords: [f32; 2],
+    pub colour: [f32; 4],
+}
+
+implement_vertex!(TextVertex, position, tex_coords, colour);
+
+#[derive(Default)]
+pub struct TextMesh {
+    pub vertices: Vec<TextVertex>,
+    pub indices: Vec<u32>,
+}
+
+// where a character's cell is in the font, min and max. characters the font doesn't
+// have come out as '?'
+fn glyph_uv(c: char) -> [[f32; 2]; 2] {
+    let c = if (FIRST..=SOLID).contains(&c) { c } else { '?' };
+    let index = c as u32 - FIRST as u32;
+    let (column, row) = (index % COLUMNS, index / COLUMNS);
+    let (width, height) = (1.0 / COLUMNS as f32, 1.0 / ROWS as f32);
+    // the font's rows are flipped when it's uploaded, so the first row is at the top
+    let top = 1.0 - row as f32 * height;
+    [
+        [column as f32 * width, top - height],
+        [(column + 1) as f32 * width, top],
+    ]
+}
+
+pub fn text_width(text: &str) -> f32 {
+    let longest = text
+        .lines()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0);
+    longest as f32 * GLYPH_SIZE[0]
+}
+
+impl TextMesh {
+    pub fn new() -> TextMesh {
+        TextMesh::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    // puts `other` on top of everything in this mesh
+    pub fn append(&mut self, other: TextMesh) {
+        let start = self.vertices.len() as u32;
+        self.vertices.extend(other.vertices);
+        self.indices
+            .extend(other.indices.into_iter().map(|i| start + i));
+    }
+
+    fn push_quad(&mut self, min: [f32; 2], max: [f32; 2], uv: [[f32; 2]; 2], colour: [f32; 4]) {
+        let start = self.vertices.len() as u32;
+        // screen y goes down and texture v goes up
+        let corners = [
+            ([min[0], min[1]], [uv[0][0], uv[1][1]]),
+            ([max[0], min[1]], [uv[1][0], uv[1][1]]),
+            ([max[0], max[1]], [uv[1][0], uv[0][1]]),
+            ([min[0], max[1]], [uv[0][0], uv[0][1]]),
+        ];
+        for (position, tex_coords) in corners {
+            self.vertices.push(TextVertex {
+                position,
+                tex_coords,
+                colour,
+            });
+        }
+        self.indices.extend([0, 1, 2, 0, 2, 3].map(|i| start + i));
+    }
+
+    pub fn push_rect(&mut self, min: [f32; 2], max: [f32; 2], colour: [f32; 4]) {
+        self.push_quad(min, max, glyph_uv(SOLID), colour);
+    }
+
+    // `text` with its top left corner at `at`. newlines start another line underneath
+    pub fn push_text(&mut self, text: &str, at: [f32; 2], colour: [f32; 4]) {
+        for (row, line) in text.lines().enumerate() {
+            let y = at[1] + row as f32 * GLYPH_SIZE[1];
+            for (column, c) in line.chars().enumerate() {
+                if c == ' ' {
+                    continue;
+                }
+                let x = at[0] + column as f32 * GLYPH_SIZE[0];
+                let max = [x + GLYPH_SIZE[0], y + GLYPH_SIZE[1]];
+                self.push_quad([x, y], max, glyph_uv(c), colour);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: [f32; 4] = [1.0; 4];
+
+    #[test]
+    fn lays_text_out_in_cells() {
+        let mut mesh = TextMesh::new();
+        mesh.push_text("ab c\nd", [10.0, 20.0], WHITE);
+        // spaces take up room but aren't drawn
+        assert_eq!(mesh.vertices.len(), 4 * 4);
+        assert_eq!(mesh.indices.len(), 4 * 6);
+        assert_eq!(
+            mesh.vertices[8].position,
+            [10.0 + 3.0 * GLYPH_SIZE[0], 20.0]
+        );
+        assert_eq!(mesh.vertices[12].position, [10.0, 20.0 + GLYPH_SIZE[1]]);
+        assert_eq!(text_width("ab c\nd"), 4.0 * GLYPH_SIZE[0]);
+    }
+
+    #[test]
+    fn finds_glyphs_in_the_font() {
+        // the space is the first cell, in the top left
+        let [min, max] = glyph_uv(' ');
+        assert_eq!([min[0], max[1]], [0.0, 1.0]);
+        let [min, max] = glyph_uv('A');
+        assert_eq!([min[0], max[0]], [1.0 / 16.0, 2.0 / 16.0]);
+        assert!((max[1] - 4.0 / 6.0).abs() < 1e-6);
+        assert_eq!(glyph_uv('é'), glyph_uv('?'));
+    }
+}
@@ -0,0 +1,38 @@
+use crate::input::Bindings;
+use crate::support::camera::CameraState;
+
+// radians the camera turns for each pixel the mouse moves, at a sensitivity of 1
+const BASE_SENSITIVITY: f32 = 0.003;
+
+// what the settings menu changes
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    // vertical field of view in degrees
+    pub fov: f32,
+    // multiplies how far the camera turns with the mouse
+    pub sensitivity: f32,
+    // how many chunks out from the player get generated. distant ones are drawn with
+    // less detail, see world::lod
+    pub view_radius: i32,
+    // what the keys and buttons do. these are the only settings kept between runs, see
+    // input::BINDINGS_PATH
+    pub bindings: Bindings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            fov: 90.0,
+            sensitivity: 1.0,
+            view_radius: 8,
+            bindings: Bindings::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn apply(&self, camera: &mut CameraState) {
+        camera.set_fov(self.fov.to_radians());
+        camera.set_sensitivity(self.sensitivity * BASE_SENSITIVITY);
+    }
+}
@@ -1,25 +1,37 @@
+use crate::input::Action;
+
 pub struct CameraState {
     aspect_ratio: f32,
     position: (f32, f32, f32),
     direction: (f32, f32, f32),
+    // vertical, in radians
+    fov: f32,
+    // radians turned per pixel the mouse moves
+    sensitivity: f32,
 
     moving_up: bool,
     moving_left: bool,
-    moving_down: bool,
     moving_right: bool,
     moving_forward: bool,
     moving_backward: bool,
 }
 
+impl Default for CameraState {
+    fn default() -> Self {
+        CameraState::new()
+    }
+}
+
 impl CameraState {
     pub fn new() -> CameraState {
         CameraState {
             aspect_ratio: 1024.0 / 768.0,
             position: (0.1, 0.1, 1.0),
             direction: (0.0, 0.0, -1.0),
+            fov: std::f32::consts::PI / 2.0,
+            sensitivity: 0.003,
             moving_up: false,
             moving_left: false,
-            moving_down: false,
             moving_right: false,
             moving_forward: false,
             moving_backward: false,
@@ -34,8 +46,84 @@ impl CameraState {
         self.direction = dir;
     }
 
+    pub fn position(&self) -> (f32, f32, f32) {
+        self.position
+    }
+
+    pub fn direction(&self) -> (f32, f32, f32) {
+        self.direction
+    }
+
+    pub fn set_fov(&mut self, fov: f32) {
+        self.fov = fov;
+    }
+
+    pub fn set_sensitivity(&mut self, sensitivity: f32) {
+        self.sensitivity = sensitivity;
+    }
+
+    // lets go of every movement key, for when something else takes the keyboard
+    pub fn stop_moving(&mut self) {
+        self.moving_up = false;
+        self.moving_left = false;
+        self.moving_right = false;
+        self.moving_forward = false;
+        self.moving_backward = false;
+    }
+
+    pub fn set_aspect_ratio(&mut self, aspect_ratio: f32) {
+        self.aspect_ratio = aspect_ratio;
+    }
+
+    // turns the camera by a mouse movement in pixels
+    pub fn process_mouse_motion(&mut self, dx: f64, dy: f64) {
+        let sensitivity = self.sensitivity;
+        let d = self.direction;
+        let len = (d.0 * d.0 + d.1 * d.1 + d.2 * d.2).sqrt();
+        let mut yaw = d.2.atan2(d.0);
+        let mut pitch = (d.1 / len).asin();
+
+        // the view is left handed so moving the mouse right turns towards -yaw
+        yaw -= dx as f32 * sensitivity;
+        pitch -= dy as f32 * sensitivity;
+        // stop just short of straight up or down so the view matrix stays valid
+        let limit = std::f32::consts::FRAC_PI_2 - 0.01;
+        pitch = pitch.clamp(-limit, limit);
+
+        self.direction = (yaw.cos() * pitch.cos(), pitch.sin(), yaw.sin() * pitch.cos());
+    }
+
+    // the direction the movement keys point along the ground, and whether jump is held.
+    // used to walk the player rather than fly the camera
+    pub fn walk_input(&self) -> ([f32; 2], bool) {
+        let d = self.direction;
+        let len = (d.0 * d.0 + d.2 * d.2).sqrt().max(f32::EPSILON);
+        let forward = [d.0 / len, d.2 / len];
+        // matches the sideways vector `get_view` uses
+        let right = [-forward[1], forward[0]];
+
+        let mut walk = [0.0f32; 2];
+        let axes = [
+            (self.moving_forward, forward, 1.0),
+            (self.moving_backward, forward, -1.0),
+            (self.moving_right, right, 1.0),
+            (self.moving_left, right, -1.0),
+        ];
+        for (held, dir, sign) in axes {
+            if held {
+                walk[0] += dir[0] * sign;
+                walk[1] += dir[1] * sign;
+            }
+        }
+        let len = (walk[0] * walk[0] + walk[1] * walk[1]).sqrt();
+        if len > 1.0 {
+            walk = [walk[0] / len, walk[1] / len];
+        }
+        (walk, self.moving_up)
+    }
+
     pub fn get_perspective(&self) -> [[f32; 4]; 4] {
-        let fov: f32 = 3.141592 / 2.0;
+        let fov = self.fov;
         let zfar = 1024.0;
         let znear = 0.1;
 
@@ -74,7 +162,7 @@ impl CameraState {
                  s_norm.2 * f.0 - s_norm.0 * f.2,
                  s_norm.0 * f.1 - s_norm.1 * f.0);
 
-        let p = (-self.position.0 * s.0 - self.position.1 * s.1 - self.position.2 * s.2,
+        let p = (-self.position.0 * s_norm.0 - self.position.1 * s_norm.1 - self.position.2 * s_norm.2,
                  -self.position.0 * u.0 - self.position.1 * u.1 - self.position.2 * u.2,
                  -self.position.0 * f.0 - self.position.1 * f.1 - self.position.2 * f.2);
 
@@ -87,80 +175,14 @@ impl CameraState {
         ]
     }
 
-    pub fn update(&mut self) {
-        let f = {
-            let f = self.direction;
-            let len = f.0 * f.0 + f.1 * f.1 + f.2 * f.2;
-            let len = len.sqrt();
-            (f.0 / len, f.1 / len, f.2 / len)
-        };
-
-        let up = (0.0, 1.0, 0.0);
-
-        let s = (f.1 * up.2 - f.2 * up.1,
-                 f.2 * up.0 - f.0 * up.2,
-                 f.0 * up.1 - f.1 * up.0);
-
-        let s = {
-            let len = s.0 * s.0 + s.1 * s.1 + s.2 * s.2;
-            let len = len.sqrt();
-            (s.0 / len, s.1 / len, s.2 / len)
-        };
-
-        let u = (s.1 * f.2 - s.2 * f.1,
-                 s.2 * f.0 - s.0 * f.2,
-                 s.0 * f.1 - s.1 * f.0);
-
-        if self.moving_up {
-            self.position.0 += u.0 * 0.01;
-            self.position.1 += u.1 * 0.01;
-            self.position.2 += u.2 * 0.01;
-        }
-
-        if self.moving_left {
-            self.position.0 -= s.0 * 0.01;
-            self.position.1 -= s.1 * 0.01;
-            self.position.2 -= s.2 * 0.01;
-        }
-
-        if self.moving_down {
-            self.position.0 -= u.0 * 0.01;
-            self.position.1 -= u.1 * 0.01;
-            self.position.2 -= u.2 * 0.01;
-        }
-
-        if self.moving_right {
-            self.position.0 += s.0 * 0.01;
-            self.position.1 += s.1 * 0.01;
-            self.position.2 += s.2 * 0.01;
-        }
-
-        if self.moving_forward {
-            self.position.0 += f.0 * 0.01;
-            self.position.1 += f.1 * 0.01;
-            self.position.2 += f.2 * 0.01;
-        }
-
-        if self.moving_backward {
-            self.position.0 -= f.0 * 0.01;
-            self.position.1 -= f.1 * 0.01;
-            self.position.2 -= f.2 * 0.01;
-        }
-    }
-
-    pub fn process_input(&mut self, event: &glium::winit::event::WindowEvent) {
-        use glium::winit::keyboard::{PhysicalKey, KeyCode};
-        let glium::winit::event::WindowEvent::KeyboardInput { event, .. } = event else {
-            return
-        };
-        let pressed = event.state == glium::winit::event::ElementState::Pressed;
-        match &event.physical_key {
-            PhysicalKey::Code(KeyCode::ArrowUp) => self.moving_up = pressed,
-            PhysicalKey::Code(KeyCode::ArrowDown) => self.moving_down = pressed,
-            PhysicalKey::Code(KeyCode::KeyA) => self.moving_left = pressed,
-            PhysicalKey::Code(KeyCode::KeyD) => self.moving_right = pressed,
-            PhysicalKey::Code(KeyCode::KeyW) => self.moving_forward = pressed,
-            PhysicalKey::Code(KeyCode::KeyS) => self.moving_backward = pressed,
+    // holds a movement key down or lets it go
+    pub fn process_action(&mut self, action: Action, pressed: bool) {
+        match action {
+            Action::Jump => self.moving_up = pressed,
+            Action::MoveLeft => self.moving_left = pressed,
+            Action::MoveRight => self.moving_right = pressed,
+            Action::MoveForward => self.moving_forward = pressed,
+            Action::MoveBackward => self.moving_backward = pressed,
             _ => (),
         };
     }
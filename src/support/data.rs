@@ -0,0 +1,38 @@
+use std::{fmt, fs, path::Path};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+#[derive(Debug)]
+pub enum DataError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Write(std::io::Error),
+    Serialize(ron::Error),
+}
+
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataError::Io(err) => write!(f, "couldn't read data file: {err}"),
+            DataError::Parse(err) => write!(f, "couldn't parse data file: {err}"),
+            DataError::Write(err) => write!(f, "couldn't write data file: {err}"),
+            DataError::Serialize(err) => write!(f, "couldn't serialize data: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for DataError {}
+
+// reads a ron file from the assets folder into `T`
+pub fn load_ron<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T, DataError> {
+    let source = fs::read_to_string(path).map_err(DataError::Io)?;
+    ron::from_str(&source).map_err(DataError::Parse)
+}
+
+// writes `value` out as a ron file, laid out to be edited by hand
+pub fn save_ron<T: Serialize>(path: impl AsRef<Path>, value: &T) -> Result<(), DataError> {
+    let source = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(DataError::Serialize)?;
+    fs::write(path, source).map_err(DataError::Write)
+}
@@ -0,0 +1,682 @@
+use std::collections::HashMap;
+use std::{fmt, fs, path::Path};
+
+use serde::Deserialize;
+
+use crate::support::rendering_info::{Normal, TexCoord, Vertex};
+
+// a triangle mesh loaded from a model file, in the same vertex formats the renderer
+// already uses. the three vertex buffers are always the same length
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mesh {
+    pub positions: Vec<Vertex>,
+    pub normals: Vec<Normal>,
+    pub tex_coords: Vec<TexCoord>,
+    pub indices: Vec<u32>,
+}
+
+#[derive(Debug)]
+pub enum MeshError {
+    Io(std::io::Error),
+    Obj { line: usize, message: String },
+    Json(serde_json::Error),
+    Gltf(String),
+    UnknownFormat(String),
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MeshError::Io(err) => write!(f, "couldn't read model file: {err}"),
+            MeshError::Obj { line, message } => write!(f, "bad obj file, line {line}: {message}"),
+            MeshError::Json(err) => write!(f, "couldn't parse gltf file: {err}"),
+            MeshError::Gltf(message) => write!(f, "bad gltf file: {message}"),
+            MeshError::UnknownFormat(ext) => write!(f, "unknown model format {ext:?}"),
+        }
+    }
+}
+
+impl std::error::Error for MeshError {}
+
+impl Mesh {
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    fn push(&mut self, position: [f32; 3], normal: [f32; 3], tex_coords: [f32; 2]) -> u32 {
+        self.positions.push(Vertex {
+            position: position.into(),
+        });
+        self.normals.push(Normal {
+            normal: normal.into(),
+        });
+        self.tex_coords.push(TexCoord {
+            tex_coords: tex_coords.into(),
+        });
+        (self.positions.len() - 1) as u32
+    }
+
+    // smooth normals for the vertices in `missing`, averaged from the faces around them
+    fn compute_normals(&mut self, missing: &[bool]) {
+        let mut sums = vec![[0.0f32; 3]; self.positions.len()];
+        for triangle in self.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| {
+                let p = self.positions[triangle[i] as usize].position;
+                [p.0, p.1, p.2]
+            });
+            let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+            let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+            let face = [
+                u[1] * v[2] - u[2] * v[1],
+                u[2] * v[0] - u[0] * v[2],
+                u[0] * v[1] - u[1] * v[0],
+            ];
+            for &i in triangle {
+                for axis in 0..3 {
+                    sums[i as usize][axis] += face[axis];
+                }
+            }
+        }
+        for (i, sum) in sums.into_iter().enumerate() {
+            if !missing[i] {
+                continue;
+            }
+            let len = (sum[0] * sum[0] + sum[1] * sum[1] + sum[2] * sum[2]).sqrt();
+            if len > 0.0 {
+                self.normals[i].normal = (sum[0] / len, sum[1] / len, sum[2] / len);
+            }
+        }
+    }
+}
+
+// loads an .obj, .gltf or .glb file
+pub fn load_mesh(path: impl AsRef<Path>) -> Result<Mesh, MeshError> {
+    let path = path.as_ref();
+    let ext = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match ext.as_str() {
+        "obj" => parse_obj(&fs::read_to_string(path).map_err(MeshError::Io)?),
+        "gltf" | "glb" => {
+            let bytes = fs::read(path).map_err(MeshError::Io)?;
+            parse_gltf(&bytes, path.parent())
+        }
+        _ => Err(MeshError::UnknownFormat(ext)),
+    }
+}
+
+fn floats<const N: usize>(parts: &[&str], line: usize) -> Result<[f32; N], MeshError> {
+    let mut out = [0.0; N];
+    for (i, value) in out.iter_mut().enumerate() {
+        let part = parts.get(i).ok_or_else(|| MeshError::Obj {
+            line,
+            message: format!("expected {N} numbers"),
+        })?;
+        *value = part.parse().map_err(|_| MeshError::Obj {
+            line,
+            message: format!("{part:?} isn't a number"),
+        })?;
+    }
+    Ok(out)
+}
+
+// obj indices start at 1, and negative ones count back from the latest
+fn obj_index(part: &str, len: usize, line: usize) -> Result<usize, MeshError> {
+    let bad = || MeshError::Obj {
+        line,
+        message: format!("bad index {part:?}"),
+    };
+    let index: i64 = part.parse().map_err(|_| bad())?;
+    let resolved = if index < 0 {
+        len as i64 + index
+    } else {
+        index - 1
+    };
+    if resolved < 0 || resolved >= len as i64 {
+        return Err(bad());
+    }
+    Ok(resolved as usize)
+}
+
+// wavefront obj, positions, texture coordinates and normals only. faces with more than
+// three corners are split into a fan, and anything else in the file is ignored
+pub fn parse_obj(source: &str) -> Result<Mesh, MeshError> {
+    let mut positions = Vec::new();
+    let mut tex_coords = Vec::new();
+    let mut normals = Vec::new();
+
+    let mut mesh = Mesh::default();
+    let mut missing_normals = Vec::new();
+    // each distinct position/uv/normal combination becomes one vertex
+    let mut corners: HashMap<(usize, Option<usize>, Option<usize>), u32> = HashMap::new();
+
+    for (number, text) in source.lines().enumerate() {
+        let line = number + 1;
+        let text = text.split('#').next().unwrap_or_default();
+        let mut parts = text.split_whitespace();
+        let Some(keyword) = parts.next() else {
+            continue;
+        };
+        let parts: Vec<&str> = parts.collect();
+        match keyword {
+            "v" => positions.push(floats::<3>(&parts, line)?),
+            "vt" => tex_coords.push(floats::<2>(&parts, line)?),
+            "vn" => normals.push(floats::<3>(&parts, line)?),
+            "f" => {
+                if parts.len() < 3 {
+                    return Err(MeshError::Obj {
+                        line,
+                        message: "faces need at least three corners".to_string(),
+                    });
+                }
+                let mut face = Vec::with_capacity(parts.len());
+                for part in parts {
+                    let mut refs = part.split('/');
+                    let v = obj_index(refs.next().unwrap_or_default(), positions.len(), line)?;
+                    let vt = match refs.next() {
+                        Some("") | None => None,
+                        Some(vt) => Some(obj_index(vt, tex_coords.len(), line)?),
+                    };
+                    let vn = match refs.next() {
+                        Some("") | None => None,
+                        Some(vn) => Some(obj_index(vn, normals.len(), line)?),
+                    };
+                    let index = *corners.entry((v, vt, vn)).or_insert_with(|| {
+                        missing_normals.push(vn.is_none());
+                        mesh.push(
+                            positions[v],
+                            vn.map_or([0.0; 3], |vn| normals[vn]),
+                            vt.map_or([0.0; 2], |vt| tex_coords[vt]),
+                        )
+                    });
+                    face.push(index);
+                }
+                for i in 1..face.len() - 1 {
+                    mesh.indices.extend([face[0], face[i], face[i + 1]]);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    if missing_normals.contains(&true) {
+        mesh.compute_normals(&missing_normals);
+    }
+    Ok(mesh)
+}
+
+// the parts of the gltf json this loader understands
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Gltf {
+    #[serde(default)]
+    buffers: Vec<Buffer>,
+    #[serde(default)]
+    buffer_views: Vec<BufferView>,
+    #[serde(default)]
+    accessors: Vec<Accessor>,
+    #[serde(default)]
+    meshes: Vec<GltfMesh>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Buffer {
+    uri: Option<String>,
+    byte_length: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BufferView {
+    buffer: usize,
+    #[serde(default)]
+    byte_offset: usize,
+    byte_length: usize,
+    byte_stride: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Accessor {
+    buffer_view: Option<usize>,
+    #[serde(default)]
+    byte_offset: usize,
+    component_type: u32,
+    count: usize,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    normalized: bool,
+}
+
+#[derive(Deserialize)]
+struct GltfMesh {
+    primitives: Vec<Primitive>,
+}
+
+#[derive(Deserialize)]
+struct Primitive {
+    attributes: HashMap<String, usize>,
+    indices: Option<usize>,
+    mode: Option<u32>,
+}
+
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const GLB_JSON: u32 = 0x4e4f534a;
+const GLB_BIN: u32 = 0x004e4942;
+const TRIANGLES: u32 = 4;
+
+const UNSIGNED_BYTE: u32 = 5121;
+const UNSIGNED_SHORT: u32 = 5123;
+const UNSIGNED_INT: u32 = 5125;
+const FLOAT: u32 = 5126;
+
+fn gltf_error(message: impl Into<String>) -> MeshError {
+    MeshError::Gltf(message.into())
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+// splits a binary .glb into its json and the binary chunk, if there is one
+fn split_glb(bytes: &[u8]) -> Result<(&[u8], Option<&[u8]>), MeshError> {
+    let truncated = || gltf_error("glb file is cut short");
+    let mut at = 12;
+    let mut json = None;
+    let mut bin = None;
+    while at < bytes.len() {
+        let len = read_u32(bytes, at).ok_or_else(truncated)? as usize;
+        let kind = read_u32(bytes, at + 4).ok_or_else(truncated)?;
+        let data = bytes.get(at + 8..at + 8 + len).ok_or_else(truncated)?;
+        match kind {
+            GLB_JSON => json = Some(data),
+            GLB_BIN => bin = Some(data),
+            _ => (),
+        }
+        at += 8 + len;
+    }
+    Ok((json.ok_or_else(|| gltf_error("glb file has no json"))?, bin))
+}
+
+fn decode_base64(text: &str) -> Result<Vec<u8>, MeshError> {
+    let value = |c: u8| match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' | b'-' => Some(62),
+        b'/' | b'_' => Some(63),
+        _ => None,
+    };
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    let mut bits = 0u32;
+    let mut count = 0;
+    for c in text.bytes().filter(|&c| c != b'=') {
+        let v = value(c).ok_or_else(|| gltf_error("bad base64 data"))?;
+        bits = bits << 6 | v as u32;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            out.push((bits >> count) as u8);
+        }
+    }
+    Ok(out)
+}
+
+fn load_buffer(
+    buffer: &Buffer,
+    bin: Option<&[u8]>,
+    folder: Option<&Path>,
+) -> Result<Vec<u8>, MeshError> {
+    let data = match &buffer.uri {
+        Some(uri) if uri.starts_with("data:") => {
+            let (_, encoded) = uri
+                .split_once(";base64,")
+                .ok_or_else(|| gltf_error("only base64 data uris are supported"))?;
+            decode_base64(encoded)?
+        }
+        Some(uri) => {
+            let folder = folder.ok_or_else(|| gltf_error(format!("can't find buffer {uri}")))?;
+            fs::read(folder.join(uri)).map_err(MeshError::Io)?
+        }
+        None => bin
+            .ok_or_else(|| gltf_error("buffer has no data"))?
+            .to_vec(),
+    };
+    if data.len() < buffer.byte_length {
+        return Err(gltf_error("buffer is shorter than it says"));
+    }
+    Ok(data)
+}
+
+fn components(kind: &str) -> Option<usize> {
+    match kind {
+        "SCALAR" => Some(1),
+        "VEC2" => Some(2),
+        "VEC3" => Some(3),
+        "VEC4" => Some(4),
+        _ => None,
+    }
+}
+
+// every element of an accessor as floats, indices included. those would only lose
+// precision past 2^24, far bigger than any model here
+fn read_accessor(gltf: &Gltf, buffers: &[Vec<u8>], index: usize) -> Result<Vec<f32>, MeshError> {
+    let accessor = gltf
+        .accessors
+        .get(index)
+        .ok_or_else(|| gltf_error(format!("no accessor {index}")))?;
+    let width = components(&accessor.kind)
+        .ok_or_else(|| gltf_error(format!("unknown accessor type {}", accessor.kind)))?;
+    let size = match accessor.component_type {
+        UNSIGNED_BYTE => 1,
+        UNSIGNED_SHORT => 2,
+        UNSIGNED_INT | FLOAT => 4,
+        other => return Err(gltf_error(format!("unsupported component type {other}"))),
+    };
+    // sparse accessors without a view are all zeroes
+    let Some(view) = accessor.buffer_view else {
+        return Ok(vec![0.0; accessor.count * width]);
+    };
+    let view = gltf
+        .buffer_views
+        .get(view)
+        .ok_or_else(|| gltf_error(format!("no buffer view {view}")))?;
+    let buffer = buffers
+        .get(view.buffer)
+        .ok_or_else(|| gltf_error(format!("no buffer {}", view.buffer)))?;
+    let data = buffer
+        .get(view.byte_offset..view.byte_offset + view.byte_length)
+        .ok_or_else(|| gltf_error("buffer view runs off the end of its buffer"))?;
+    let stride = view.byte_stride.unwrap_or(size * width);
+
+    let mut out = Vec::with_capacity(accessor.count * width);
+    for element in 0..accessor.count {
+        for component in 0..width {
+            let at = accessor.byte_offset + element * stride + component * size;
+            let bytes = data
+                .get(at..at + size)
+                .ok_or_else(|| gltf_error("accessor runs off the end of its buffer view"))?;
+            let value = match accessor.component_type {
+                UNSIGNED_BYTE => bytes[0] as f32,
+                UNSIGNED_SHORT => u16::from_le_bytes([bytes[0], bytes[1]]) as f32,
+                UNSIGNED_INT => u32::from_le_bytes(bytes.try_into().unwrap()) as f32,
+                _ => f32::from_le_bytes(bytes.try_into().unwrap()),
+            };
+            let value = match (accessor.normalized, accessor.component_type) {
+                (true, UNSIGNED_BYTE) => value / u8::MAX as f32,
+                (true, UNSIGNED_SHORT) => value / u16::MAX as f32,
+                _ => value,
+            };
+            out.push(value);
+        }
+    }
+    Ok(out)
+}
+
+// gltf, from a .gltf json file or a binary .glb. every triangle primitive of every mesh
+// goes into the one mesh, as authored, since node transforms aren't applied. `folder`
+// is where external buffer files are looked for
+pub fn parse_gltf(bytes: &[u8], folder: Option<&Path>) -> Result<Mesh, MeshError> {
+    let (json, bin) = if bytes.starts_with(GLB_MAGIC) {
+        split_glb(bytes)?
+    } else {
+        (bytes, None)
+    };
+    let gltf: Gltf = serde_json::from_slice(json).map_err(MeshError::Json)?;
+    let buffers = gltf
+        .buffers
+        .iter()
+        .map(|buffer| load_buffer(buffer, bin, folder))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut mesh = Mesh::default();
+    let mut missing_normals = Vec::new();
+    for primitive in gltf.meshes.iter().flat_map(|mesh| &mesh.primitives) {
+        if primitive.mode.unwrap_or(TRIANGLES) != TRIANGLES {
+            return Err(gltf_error("only triangle primitives are supported"));
+        }
+        let attribute = |name: &str| {
+            primitive
+                .attributes
+                .get(name)
+                .map(|&index| read_accessor(&gltf, &buffers, index))
+                .transpose()
+        };
+        let positions =
+            attribute("POSITION")?.ok_or_else(|| gltf_error("primitive has no positions"))?;
+        let normals = attribute("NORMAL")?;
+        let tex_coords = attribute("TEXCOORD_0")?;
+
+        let first = mesh.vertex_count() as u32;
+        let count = positions.len() / 3;
+        for i in 0..count {
+            let normal = normals
+                .as_ref()
+                .map_or([0.0; 3], |n| [n[i * 3], n[i * 3 + 1], n[i * 3 + 2]]);
+            let uv = tex_coords
+                .as_ref()
+                .map_or([0.0; 2], |t| [t[i * 2], t[i * 2 + 1]]);
+            mesh.push(
+                [positions[i * 3], positions[i * 3 + 1], positions[i * 3 + 2]],
+                normal,
+                uv,
+            );
+            missing_normals.push(normals.is_none());
+        }
+
+        let indices = match primitive.indices {
+            Some(index) => read_accessor(&gltf, &buffers, index)?
+                .into_iter()
+                .map(|i| i as u32)
+                .collect(),
+            None => (0..count as u32).collect::<Vec<_>>(),
+        };
+        if indices.iter().any(|&i| i as usize >= count) {
+            return Err(gltf_error("index out of range"));
+        }
+        mesh.indices.extend(indices.into_iter().map(|i| first + i));
+    }
+
+    if missing_normals.contains(&true) {
+        mesh.compute_normals(&missing_normals);
+    }
+    Ok(mesh)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(mesh: &Mesh, index: u32) -> [f32; 3] {
+        let p = mesh.positions[index as usize].position;
+        [p.0, p.1, p.2]
+    }
+
+    #[test]
+    fn loads_the_teapot() {
+        let mesh = load_mesh("assets/models/teapot.obj").unwrap();
+        assert_eq!(mesh.vertex_count(), 530);
+        assert_eq!(mesh.triangle_count(), 1024);
+        assert_eq!(mesh.normals.len(), mesh.vertex_count());
+        assert_eq!(mesh.tex_coords.len(), mesh.vertex_count());
+        // the first face ends on the first vertex in the file
+        assert_eq!(
+            position(&mesh, mesh.indices[2]),
+            [40.6266, 28.3457, -1.10804]
+        );
+    }
+
+    #[test]
+    fn splits_quads_and_shares_corners() {
+        let source = "
+            # a unit square facing up
+            v 0 0 0
+            v 1 0 0
+            v 1 0 1
+            v 0 0 1
+            vt 0 0
+            vt 1 0
+            vt 1 1
+            vt 0 1
+            vn 0 1 0
+            f 1/1/1 4/4/1 3/3/1 2/2/1
+        ";
+        let mesh = parse_obj(source).unwrap();
+        assert_eq!(mesh.vertex_count(), 4);
+        assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3]);
+        assert_eq!(mesh.tex_coords[2].tex_coords, (1.0, 1.0));
+        assert!(mesh.normals.iter().all(|n| n.normal == (0.0, 1.0, 0.0)));
+    }
+
+    #[test]
+    fn same_position_with_different_uvs_is_two_vertices() {
+        let source = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvt 1 1\nf 1/1 2/1 3/1\nf 1/2 3/1 2/1\n";
+        let mesh = parse_obj(source).unwrap();
+        assert_eq!(mesh.vertex_count(), 4);
+        assert_eq!(mesh.triangle_count(), 2);
+    }
+
+    #[test]
+    fn negative_indices_count_back() {
+        let mesh = parse_obj("v 0 0 0\nv 1 0 0\nv 0 0 1\nf -3 -1 -2\n").unwrap();
+        assert_eq!(position(&mesh, mesh.indices[1]), [0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn fills_in_missing_normals() {
+        // wound so it faces up
+        let mesh = parse_obj("v 0 0 0\nv 0 0 1\nv 1 0 0\nf 1 2 3\n").unwrap();
+        for normal in &mesh.normals {
+            assert_eq!(normal.normal, (0.0, 1.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn reports_bad_lines() {
+        let err = parse_obj("v 0 0 0\nv 1 0 0\nf 1 2 3\n").unwrap_err();
+        assert!(matches!(err, MeshError::Obj { line: 3, .. }), "{err}");
+        let err = parse_obj("v 0 zero 0\n").unwrap_err();
+        assert!(matches!(err, MeshError::Obj { line: 1, .. }), "{err}");
+        assert!(matches!(
+            load_mesh("teapot.fbx"),
+            Err(MeshError::UnknownFormat(_))
+        ));
+    }
+
+    fn encode_base64(bytes: &[u8]) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut out = String::new();
+        for chunk in bytes.chunks(3) {
+            let n = chunk.iter().fold(0u32, |n, &b| n << 8 | b as u32) << (8 * (3 - chunk.len()));
+            for i in 0..=chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            }
+        }
+        while !out.len().is_multiple_of(4) {
+            out.push('=');
+        }
+        out
+    }
+
+    // one triangle: three float positions then three u16 indices
+    fn triangle_buffer() -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for v in [0.0f32, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0] {
+            bytes.extend(v.to_le_bytes());
+        }
+        for i in [0u16, 1, 2] {
+            bytes.extend(i.to_le_bytes());
+        }
+        bytes
+    }
+
+    fn triangle_json(uri: Option<String>, mode: u32) -> String {
+        let uri = uri
+            .map(|uri| format!(r#""uri": "{uri}","#))
+            .unwrap_or_default();
+        format!(
+            r#"{{
+                "asset": {{ "version": "2.0" }},
+                "buffers": [{{ {uri} "byteLength": 42 }}],
+                "bufferViews": [
+                    {{ "buffer": 0, "byteOffset": 0, "byteLength": 36 }},
+                    {{ "buffer": 0, "byteOffset": 36, "byteLength": 6 }}
+                ],
+                "accessors": [
+                    {{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3" }},
+                    {{ "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }}
+                ],
+                "meshes": [{{ "primitives": [{{
+                    "attributes": {{ "POSITION": 0 }}, "indices": 1, "mode": {mode}
+                }}] }}]
+            }}"#
+        )
+    }
+
+    fn check_triangle(mesh: &Mesh) {
+        assert_eq!(mesh.vertex_count(), 3);
+        assert_eq!(mesh.indices, vec![0, 1, 2]);
+        assert_eq!(position(mesh, 2), [1.0, 0.0, 0.0]);
+        assert_eq!(mesh.normals[0].normal, (0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn loads_gltf_with_embedded_buffers() {
+        let uri = format!(
+            "data:application/octet-stream;base64,{}",
+            encode_base64(&triangle_buffer())
+        );
+        let json = triangle_json(Some(uri), TRIANGLES);
+        check_triangle(&parse_gltf(json.as_bytes(), None).unwrap());
+    }
+
+    #[test]
+    fn loads_glb() {
+        let mut json = triangle_json(None, TRIANGLES).into_bytes();
+        while !json.len().is_multiple_of(4) {
+            json.push(b' ');
+        }
+        let mut bin = triangle_buffer();
+        while !bin.len().is_multiple_of(4) {
+            bin.push(0);
+        }
+
+        let mut glb = Vec::new();
+        glb.extend(GLB_MAGIC);
+        glb.extend(2u32.to_le_bytes());
+        glb.extend((12 + 8 + json.len() as u32 + 8 + bin.len() as u32).to_le_bytes());
+        glb.extend((json.len() as u32).to_le_bytes());
+        glb.extend(GLB_JSON.to_le_bytes());
+        glb.extend(&json);
+        glb.extend((bin.len() as u32).to_le_bytes());
+        glb.extend(GLB_BIN.to_le_bytes());
+        glb.extend(&bin);
+        check_triangle(&parse_gltf(&glb, None).unwrap());
+    }
+
+    #[test]
+    fn rejects_gltf_it_cant_draw() {
+        let uri = format!(
+            "data:application/octet-stream;base64,{}",
+            encode_base64(&triangle_buffer())
+        );
+        // lines rather than triangles
+        let json = triangle_json(Some(uri), 1);
+        assert!(matches!(
+            parse_gltf(json.as_bytes(), None),
+            Err(MeshError::Gltf(_))
+        ));
+        assert!(matches!(
+            parse_gltf(b"{ not json", None),
+            Err(MeshError::Json(_))
+        ));
+    }
+}
@@ -1,3 +1,7 @@
 pub mod camera;
 pub mod cube;
+pub mod data;
+pub mod mesh;
+pub mod packed;
 pub mod rendering_info;
+pub mod rng;
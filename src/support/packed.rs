@@ -0,0 +1,202 @@
+// chunk vertices squeezed into two u32s. assets/shaders/block.vert unpacks them, so the
+// layout and tables here have to match it
+//
+// data[0]: x (10 bits) | y (10) | z (10) | ambient occlusion (2)
+// data[1]: face (4) | atlas tile (8) | u (5) | v (5) | light (4) | tinted (1)
+
+// positions are measured from the chunk's corner: six bits of whole blocks and four of
+// sixteenths, since slabs, fluid surfaces and plants don't sit on the block grid
+pub const POSITION_STEPS: f32 = 16.0;
+pub const MAX_POSITION: u16 = (1 << 10) - 1;
+// texture coordinates are in sixteenths of a tile
+pub const UV_STEPS: f32 = 16.0;
+pub const MAX_UV: u8 = 16;
+pub const ATLAS_TILES: u8 = 16;
+
+// brightness of a corner with none, one, two or three of the blocks around it solid
+pub const AO_LEVELS: [f32; 4] = [1.0, 0.82, 0.65, 0.45];
+
+const DIAGONAL: f32 = std::f32::consts::FRAC_1_SQRT_2;
+// every direction a chunk face can point: the six sides, then both sides of the two
+// diagonal planes plants are drawn with
+pub const FACE_NORMALS: [[f32; 3]; 10] = [
+    [-1.0, 0.0, 0.0],
+    [1.0, 0.0, 0.0],
+    [0.0, -1.0, 0.0],
+    [0.0, 1.0, 0.0],
+    [0.0, 0.0, -1.0],
+    [0.0, 0.0, 1.0],
+    [DIAGONAL, 0.0, -DIAGONAL],
+    [-DIAGONAL, 0.0, DIAGONAL],
+    [-DIAGONAL, 0.0, -DIAGONAL],
+    [DIAGONAL, 0.0, DIAGONAL],
+];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PackedVertex {
+    pub data: [u32; 2],
+}
+
+implement_vertex!(PackedVertex, data);
+
+// what a packed vertex holds, each field in its packed units
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct VertexFields {
+    // 0 to MAX_POSITION sixteenths of a block
+    pub position: [u16; 3],
+    // index into FACE_NORMALS
+    pub face: u8,
+    // (column, row) in the atlas
+    pub tile: [u8; 2],
+    // 0 to MAX_UV sixteenths of the tile
+    pub uv: [u8; 2],
+    // index into AO_LEVELS
+    pub ao: u8,
+    // 0 to light::MAX_LIGHT
+    pub light: u8,
+    // coloured by the chunk's biome tint, for grass and leaves
+    pub tinted: bool,
+}
+
+impl VertexFields {
+    pub fn pack(&self) -> PackedVertex {
+        let [x, y, z] = self.position.map(|p| p as u32 & 0x3ff);
+        let tile = (self.tile[1] as u32 & 0xf) << 4 | (self.tile[0] as u32 & 0xf);
+        PackedVertex {
+            data: [
+                x | y << 10 | z << 20 | (self.ao as u32 & 0x3) << 30,
+                (self.face as u32 & 0xf)
+                    | tile << 4
+                    | (self.uv[0] as u32 & 0x1f) << 12
+                    | (self.uv[1] as u32 & 0x1f) << 17
+                    | (self.light as u32 & 0xf) << 22
+                    | (self.tinted as u32) << 26,
+            ],
+        }
+    }
+}
+
+impl PackedVertex {
+    pub fn unpack(&self) -> VertexFields {
+        let [a, b] = self.data;
+        let tile = (b >> 4) & 0xff;
+        VertexFields {
+            position: [a & 0x3ff, (a >> 10) & 0x3ff, (a >> 20) & 0x3ff].map(|p| p as u16),
+            face: (b & 0xf) as u8,
+            tile: [(tile & 0xf) as u8, (tile >> 4) as u8],
+            uv: [((b >> 12) & 0x1f) as u8, ((b >> 17) & 0x1f) as u8],
+            ao: (a >> 30) as u8,
+            light: ((b >> 22) & 0xf) as u8,
+            tinted: (b >> 26) & 1 != 0,
+        }
+    }
+}
+
+// the FACE_NORMALS entry closest to `normal`
+pub fn face_index(normal: [f32; 3]) -> u8 {
+    let dot = |n: &[f32; 3]| n[0] * normal[0] + n[1] * normal[1] + n[2] * normal[2];
+    (0..FACE_NORMALS.len())
+        .max_by(|&a, &b| dot(&FACE_NORMALS[a]).total_cmp(&dot(&FACE_NORMALS[b])))
+        .unwrap_or(0) as u8
+}
+
+// a chunk local position in blocks, rounded to the nearest step
+pub fn quantise_position(local: [f32; 3]) -> [u16; 3] {
+    local.map(|p| (p * POSITION_STEPS).round().clamp(0.0, MAX_POSITION as f32) as u16)
+}
+
+pub fn quantise_uv(uv: [f32; 2]) -> [u8; 2] {
+    uv.map(|t| (t * UV_STEPS).round().clamp(0.0, MAX_UV as f32) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::light::MAX_LIGHT;
+
+    fn round_trips(fields: VertexFields) {
+        assert_eq!(fields.pack().unpack(), fields);
+    }
+
+    #[test]
+    fn every_field_round_trips_on_its_own() {
+        let base = VertexFields::default();
+        for axis in 0..3 {
+            for p in 0..=MAX_POSITION {
+                let mut position = [0; 3];
+                position[axis] = p;
+                round_trips(VertexFields { position, ..base });
+            }
+        }
+        for face in 0..FACE_NORMALS.len() as u8 {
+            round_trips(VertexFields { face, ..base });
+        }
+        for col in 0..ATLAS_TILES {
+            for row in 0..ATLAS_TILES {
+                round_trips(VertexFields {
+                    tile: [col, row],
+                    ..base
+                });
+            }
+        }
+        for u in 0..=MAX_UV {
+            for v in 0..=MAX_UV {
+                round_trips(VertexFields { uv: [u, v], ..base });
+            }
+        }
+        for ao in 0..AO_LEVELS.len() as u8 {
+            round_trips(VertexFields { ao, ..base });
+        }
+        for light in 0..=MAX_LIGHT {
+            round_trips(VertexFields { light, ..base });
+        }
+        round_trips(VertexFields {
+            tinted: true,
+            ..base
+        });
+    }
+
+    // every small field at once against the extremes of the position, so no field
+    // bleeds into its neighbours
+    #[test]
+    fn fields_dont_overlap() {
+        for position in [
+            [0, 0, 0],
+            [MAX_POSITION; 3],
+            [MAX_POSITION, 0, MAX_POSITION],
+        ] {
+            for face in 0..FACE_NORMALS.len() as u8 {
+                for tile in [[0, 0], [ATLAS_TILES - 1; 2], [3, 12]] {
+                    for uv in [[0, 0], [MAX_UV; 2], [MAX_UV, 0]] {
+                        for ao in 0..AO_LEVELS.len() as u8 {
+                            for light in [0, 7, MAX_LIGHT] {
+                                for tinted in [false, true] {
+                                    round_trips(VertexFields {
+                                        position,
+                                        face,
+                                        tile,
+                                        uv,
+                                        ao,
+                                        light,
+                                        tinted,
+                                    });
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn finds_faces_and_rounds_positions() {
+        for (i, normal) in FACE_NORMALS.iter().enumerate() {
+            assert_eq!(face_index(*normal), i as u8);
+        }
+        assert_eq!(quantise_position([0.0, 0.5, 32.0]), [0, 8, 512]);
+        // plants are inset by 0.15, which lands on the nearest sixteenth
+        assert_eq!(quantise_position([0.15, 0.0, 0.85]), [2, 0, 14]);
+        assert_eq!(quantise_uv([0.0, 1.0]), [0, MAX_UV]);
+    }
+}
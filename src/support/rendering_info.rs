@@ -1,13 +1,70 @@
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Vertex {
     pub position: (f32, f32, f32),
 }
 
 implement_vertex!(Vertex, position);
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Normal {
     pub normal: (f32, f32, f32),
 }
 
 implement_vertex!(Normal, normal);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TexCoord {
+    pub tex_coords: (f32, f32),
+}
+
+implement_vertex!(TexCoord, tex_coords);
+
+// everything a block face vertex needs in one interleaved buffer. the normal, texture
+// coordinates and shading are stored as normalised integers so each vertex is 24 bytes
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ChunkVertex {
+    pub position: [f32; 3],
+    // -127 to 127 for -1.0 to 1.0, the last one is padding
+    pub normal: [i8; 4],
+    // atlas coordinates, 0 to 65535 across the whole atlas
+    pub tex_coords: [u16; 2],
+    // ambient occlusion then sky light, 0 for fully dark to 255 for fully lit
+    pub shade: [u8; 2],
+}
+
+implement_vertex!(
+    ChunkVertex,
+    position normalize(false),
+    normal normalize(true),
+    tex_coords normalize(true),
+    shade normalize(true)
+);
+
+impl ChunkVertex {
+    pub fn new(
+        position: [f32; 3],
+        normal: [f32; 3],
+        tex_coords: [f32; 2],
+        shade: [f32; 2],
+    ) -> Self {
+        ChunkVertex {
+            position,
+            normal: [
+                (normal[0] * 127.0).round() as i8,
+                (normal[1] * 127.0).round() as i8,
+                (normal[2] * 127.0).round() as i8,
+                0,
+            ],
+            tex_coords: tex_coords.map(|t| (t.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16),
+            shade: shade.map(|s| (s.clamp(0.0, 1.0) * u8::MAX as f32).round() as u8),
+        }
+    }
+
+    pub fn normal(&self) -> [f32; 3] {
+        [0, 1, 2].map(|i| self.normal[i] as f32 / 127.0)
+    }
+
+    pub fn tex_coords(&self) -> [f32; 2] {
+        self.tex_coords.map(|t| t as f32 / u16::MAX as f32)
+    }
+}
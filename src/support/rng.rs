@@ -0,0 +1,47 @@
+// small deterministic random numbers for world generation and spawning. the same seed
+// always gives the same sequence on every machine
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+fn splitmix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+// mixes a seed with some coordinates, used to give every chunk or feature its own stream
+pub fn hash(seed: u64, values: &[i64]) -> u64 {
+    values.iter().fold(splitmix(seed), |h, &v| {
+        splitmix(h ^ (v as u64).wrapping_mul(0x9e3779b97f4a7c15))
+    })
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        splitmix(self.state)
+    }
+
+    // 0.0 up to but not including 1.0
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    // lo up to but not including hi
+    pub fn range(&mut self, lo: i32, hi: i32) -> i32 {
+        if hi <= lo {
+            return lo;
+        }
+        lo + (self.next_u64() % (hi - lo) as u64) as i32
+    }
+
+    pub fn chance(&mut self, probability: f32) -> bool {
+        self.next_f32() < probability
+    }
+}
@@ -0,0 +1,679 @@
+use glium::winit::keyboard::KeyCode;
+
+use crate::game::Game;
+use crate::input::{Action, Binding, Bindings};
+use crate::item::inventory::{HOTBAR_SIZE, INVENTORY_SIZE};
+use crate::item::ItemStack;
+use crate::player::stats::{MAX_HEALTH, MAX_HUNGER, MAX_OXYGEN};
+use crate::render::text::GLYPH_SIZE;
+use crate::settings::Settings;
+use crate::ui::{column, Rect, Ui};
+
+const TITLE: &str = "voxel game";
+const BUTTON_SIZE: [f32; 2] = [240.0, 32.0];
+const SLIDER_SIZE: [f32; 2] = [320.0, 24.0];
+const GAP: f32 = 8.0;
+const SLOT_SIZE: [f32; 2] = [84.0, 52.0];
+const SLOT_GAP: f32 = 4.0;
+// a row in the controls menu, an action and what it's bound to
+const CONTROL_SIZE: [f32; 2] = [320.0, 22.0];
+// the most of each word of an item's name that fits across a slot
+const SLOT_CHARS: usize = 10;
+// what the settings sliders go between
+const FOV_RANGE: (f32, f32) = (50.0, 110.0);
+const SENSITIVITY_RANGE: (f32, f32) = (0.2, 3.0);
+const VIEW_RADIUS_RANGE: (i32, i32) = (2, 16);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Screen {
+    Title,
+    Playing,
+    Paused,
+    Settings,
+    Controls,
+    Inventory,
+    Crafting,
+}
+
+impl Screen {
+    // the camera only has the mouse while playing, everything else needs a pointer
+    pub fn grabs_pointer(self) -> bool {
+        self == Screen::Playing
+    }
+
+    // the world stands still behind these. the inventory doesn't stop anything
+    pub fn pauses(self) -> bool {
+        matches!(
+            self,
+            Screen::Title | Screen::Paused | Screen::Settings | Screen::Controls
+        )
+    }
+}
+
+// things a menu asks of the main loop
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MenuAction {
+    Quit,
+    // the bindings were changed in the controls menu
+    SaveBindings,
+}
+
+// which screen is up, and drawing it each frame
+pub struct Menus {
+    screen: Screen,
+    // the screen drawn last frame, to notice when it changes
+    shown: Option<Screen>,
+    // where the settings go back to, the title or the pause menu
+    settings_from: Screen,
+    // an inventory slot picked up to move, or to take crafting ingredients from
+    held: Option<usize>,
+    // the action in the controls menu waiting for a key or button to be pressed
+    rebinding: Option<Action>,
+}
+
+impl Default for Menus {
+    fn default() -> Self {
+        Menus::new()
+    }
+}
+
+// an item's name a word a line, and how many there are if there's more than one
+fn stack_label(game: &Game, stack: Option<&ItemStack>) -> String {
+    let Some(stack) = stack else {
+        return String::new();
+    };
+    let mut lines: Vec<String> = game
+        .items
+        .name(stack.item)
+        .split('_')
+        .map(|word| word.chars().take(SLOT_CHARS).collect())
+        .collect();
+    if stack.count > 1 {
+        lines.push(stack.count.to_string());
+    }
+    lines.join("\n")
+}
+
+// the left edge of a row of hotbar slots centred on the screen
+fn row_left(ui: &Ui) -> f32 {
+    let width = HOTBAR_SIZE as f32 * (SLOT_SIZE[0] + SLOT_GAP) - SLOT_GAP;
+    (ui.centre()[0] - width / 2.0).round()
+}
+
+fn slot_rect(left: f32, top: f32, column: usize, row: f32) -> Rect {
+    let min = [
+        left + column as f32 * (SLOT_SIZE[0] + SLOT_GAP),
+        top + row * (SLOT_SIZE[1] + SLOT_GAP),
+    ];
+    Rect::new(min, SLOT_SIZE)
+}
+
+// every inventory slot with where it goes: the main rows, then the hotbar a little
+// apart underneath them
+fn inventory_rects(ui: &Ui, top: f32) -> Vec<(usize, Rect)> {
+    let left = row_left(ui);
+    let rows = INVENTORY_SIZE / HOTBAR_SIZE;
+    (0..INVENTORY_SIZE)
+        .map(|slot| {
+            let (column, row) = (slot % HOTBAR_SIZE, slot / HOTBAR_SIZE);
+            let row = if row == 0 {
+                rows as f32 - 0.75
+            } else {
+                row as f32 - 1.0
+            };
+            (slot, slot_rect(left, top, column, row))
+        })
+        .collect()
+}
+
+fn inventory_height() -> f32 {
+    let rows = (INVENTORY_SIZE / HOTBAR_SIZE) as f32 + 0.25;
+    rows * (SLOT_SIZE[1] + SLOT_GAP)
+}
+
+// a crosshair, the hotbar, and how the player's doing
+fn hud(ui: &mut Ui, game: &Game) {
+    let [x, y] = ui.centre();
+    ui.centred_label("+", [x, y]);
+
+    let left = row_left(ui);
+    let top = ui.size()[1] - SLOT_SIZE[1] - GAP;
+    for slot in 0..HOTBAR_SIZE {
+        let label = stack_label(game, game.inventory.slot(slot));
+        let rect = slot_rect(left, top, slot, 0.0);
+        ui.slot_display(&label, rect, slot == game.inventory.selected());
+    }
+
+    let stats = &game.player.stats;
+    let mut status = format!(
+        "health {}/{MAX_HEALTH}  hunger {}/{MAX_HUNGER}",
+        stats.health, stats.hunger
+    );
+    if stats.oxygen < MAX_OXYGEN {
+        status += &format!("  air {}%", stats.oxygen * 100 / MAX_OXYGEN);
+    }
+    ui.label(&status, [left, top - GLYPH_SIZE[1] - GAP / 2.0]);
+}
+
+impl Menus {
+    pub fn new() -> Menus {
+        Menus {
+            screen: Screen::Title,
+            shown: None,
+            settings_from: Screen::Title,
+            held: None,
+            rebinding: None,
+        }
+    }
+
+    pub fn screen(&self) -> Screen {
+        self.screen
+    }
+
+    pub fn open(&mut self, screen: Screen, game: &mut Game) {
+        // what's left in the crafting grid goes back when it's closed
+        if self.screen == Screen::Crafting && screen != Screen::Crafting {
+            game.close_crafting();
+        }
+        if screen == Screen::Settings && !matches!(self.screen, Screen::Settings | Screen::Controls)
+        {
+            self.settings_from = self.screen;
+        }
+        self.held = None;
+        self.rebinding = None;
+        self.screen = screen;
+    }
+
+    // escape pauses the game, or goes back a step from a menu
+    pub fn back(&mut self, game: &mut Game) {
+        let to = match self.screen {
+            Screen::Title => Screen::Title,
+            Screen::Playing => Screen::Paused,
+            Screen::Paused | Screen::Inventory | Screen::Crafting => Screen::Playing,
+            Screen::Settings => self.settings_from,
+            Screen::Controls => Screen::Settings,
+        };
+        self.open(to, game);
+    }
+
+    // the inventory and crafting keys open their screen while playing and close it again
+    pub fn toggle(&mut self, screen: Screen, game: &mut Game) {
+        if self.screen == screen {
+            self.open(Screen::Playing, game);
+        } else if self.screen == Screen::Playing {
+            self.open(screen, game);
+        }
+    }
+
+    // for when the window loses focus while playing
+    pub fn pause(&mut self, game: &mut Game) {
+        if self.screen == Screen::Playing {
+            self.open(Screen::Paused, game);
+        }
+    }
+
+    // using a crafting table opens crafting with the table's grid, anything else uses
+    // the held item
+    pub fn use_item(&mut self, game: &mut Game) {
+        if game.targets_crafting_table() {
+            self.open(Screen::Crafting, game);
+            game.open_crafting_table();
+        } else {
+            game.use_item();
+        }
+    }
+
+    pub fn rebinding(&self) -> Option<Action> {
+        self.rebinding
+    }
+
+    // gives the action waiting in the controls menu the key or button just pressed.
+    // escape leaves it as it was. returns whether the bindings changed
+    pub fn rebind(&mut self, binding: Binding, bindings: &mut Bindings) -> bool {
+        let Some(action) = self.rebinding.take() else {
+            return false;
+        };
+        if binding == Binding::Key(KeyCode::Escape) {
+            return false;
+        }
+        bindings.bind(action, binding);
+        true
+    }
+
+    // draws the current screen between `ui.begin` and `ui.finish`, acting on whatever
+    // was clicked
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        game: &mut Game,
+        settings: &mut Settings,
+    ) -> Option<MenuAction> {
+        // the keyboard focus doesn't carry over from another screen's widgets
+        if self.shown != Some(self.screen) {
+            ui.reset();
+            self.shown = Some(self.screen);
+        }
+        match self.screen {
+            Screen::Playing => hud(ui, game),
+            Screen::Title => return self.title(ui, game),
+            Screen::Paused => return self.paused(ui, game),
+            Screen::Settings => self.settings(ui, game, settings),
+            Screen::Controls => return self.controls(ui, game, &mut settings.bindings),
+            Screen::Inventory => self.inventory(ui, game),
+            Screen::Crafting => self.crafting(ui, game),
+        }
+        None
+    }
+
+    fn title(&mut self, ui: &mut Ui, game: &mut Game) -> Option<MenuAction> {
+        ui.shade();
+        let [x, y] = ui.centre();
+        ui.centred_label(TITLE, [x, y - 80.0]);
+        let buttons = column(x, y - 40.0, BUTTON_SIZE, GAP, 3);
+        if ui.button("play", buttons[0]) {
+            self.open(Screen::Playing, game);
+        }
+        if ui.button("settings", buttons[1]) {
+            self.open(Screen::Settings, game);
+        }
+        ui.button("quit", buttons[2]).then_some(MenuAction::Quit)
+    }
+
+    fn paused(&mut self, ui: &mut Ui, game: &mut Game) -> Option<MenuAction> {
+        ui.shade();
+        let [x, y] = ui.centre();
+        ui.centred_label("paused", [x, y - 80.0]);
+        let buttons = column(x, y - 40.0, BUTTON_SIZE, GAP, 3);
+        if ui.button("resume", buttons[0]) {
+            self.open(Screen::Playing, game);
+        }
+        if ui.button("settings", buttons[1]) {
+            self.open(Screen::Settings, game);
+        }
+        ui.button("quit", buttons[2]).then_some(MenuAction::Quit)
+    }
+
+    fn settings(&mut self, ui: &mut Ui, game: &mut Game, settings: &mut Settings) {
+        ui.shade();
+        let [x, y] = ui.centre();
+        ui.centred_label("settings", [x, y - 100.0]);
+        let rows = column(x, y - 60.0, SLIDER_SIZE, GAP, 3);
+
+        let (min, max) = FOV_RANGE;
+        let label = format!("field of view {:.0}", settings.fov);
+        if ui.slider(&label, &mut settings.fov, min, max, rows[0]) {
+            settings.fov = settings.fov.round();
+        }
+        let (min, max) = SENSITIVITY_RANGE;
+        let label = format!("mouse sensitivity {:.1}", settings.sensitivity);
+        ui.slider(&label, &mut settings.sensitivity, min, max, rows[1]);
+        let (min, max) = VIEW_RADIUS_RANGE;
+        let mut radius = settings.view_radius as f32;
+        let label = format!("view distance {} chunks", settings.view_radius);
+        if ui.slider(&label, &mut radius, min as f32, max as f32, rows[2]) {
+            settings.view_radius = radius.round() as i32;
+        }
+
+        let buttons = column(x, rows[2].max[1] + GAP * 3.0, BUTTON_SIZE, GAP, 2);
+        if ui.button("controls", buttons[0]) {
+            self.open(Screen::Controls, game);
+        }
+        if ui.button("back", buttons[1]) {
+            self.back(game);
+        }
+    }
+
+    // every action in two columns. clicking one waits for a key or button to bind it to,
+    // see rebind, and anything bound to more than one action is marked
+    fn controls(
+        &mut self,
+        ui: &mut Ui,
+        game: &mut Game,
+        bindings: &mut Bindings,
+    ) -> Option<MenuAction> {
+        ui.shade();
+        let [x, y] = ui.centre();
+        let count = bindings.iter().count();
+        let rows = count.div_ceil(2);
+        let grid_height = rows as f32 * (CONTROL_SIZE[1] + SLOT_GAP);
+        let height = grid_height + GLYPH_SIZE[1] + GAP * 2.0 + BUTTON_SIZE[1];
+        let top = (y - height / 2.0).round();
+        ui.centred_label("controls", [x, top - GLYPH_SIZE[1]]);
+
+        let conflicts = bindings.conflicts();
+        let mut clicked = None;
+        for (i, (action, bound)) in bindings.iter().enumerate() {
+            let (column, row) = (i / rows, i % rows);
+            let min = [
+                x - CONTROL_SIZE[0] - SLOT_GAP / 2.0 + column as f32 * (CONTROL_SIZE[0] + SLOT_GAP),
+                top + row as f32 * (CONTROL_SIZE[1] + SLOT_GAP),
+            ];
+            let rect = Rect::new(min, CONTROL_SIZE);
+            let pressed = if self.rebinding == Some(action) {
+                let label = format!("{}: press a key, escape to cancel", action.name());
+                ui.slot(&label, rect, true)
+            } else {
+                let names: Vec<String> = bound.iter().map(|binding| binding.name()).collect();
+                let names = if names.is_empty() {
+                    "nothing".to_string()
+                } else {
+                    names.join(", ")
+                };
+                let label = format!("{}: {names}", action.name());
+                let conflicted = bound.iter().any(|binding| conflicts.contains_key(binding));
+                ui.warning_button(&label, rect, conflicted)
+            };
+            if pressed {
+                clicked = Some(action);
+            }
+        }
+        if clicked.is_some() {
+            self.rebinding = clicked;
+        }
+
+        // says what the first clash is, there's usually only the one just made
+        let bottom = top + grid_height;
+        if let Some((binding, actions)) = conflicts.iter().next() {
+            let names: Vec<String> = actions.iter().map(|action| action.name()).collect();
+            let clash = format!("{} does {}", binding.name(), names.join(" and "));
+            ui.centred_label(&clash, [x, bottom + GLYPH_SIZE[1] / 2.0]);
+        }
+
+        let buttons_top = bottom + GLYPH_SIZE[1] + GAP * 2.0;
+        let reset = Rect::new([x - BUTTON_SIZE[0] - GAP / 2.0, buttons_top], BUTTON_SIZE);
+        let back = Rect::new([x + GAP / 2.0, buttons_top], BUTTON_SIZE);
+        let mut action = None;
+        if ui.button("reset to defaults", reset) {
+            *bindings = Bindings::default();
+            self.rebinding = None;
+            action = Some(MenuAction::SaveBindings);
+        }
+        if ui.button("back", back) {
+            self.back(game);
+        }
+        action
+    }
+
+    // the first click picks a stack up, the second puts it down on another slot, adding
+    // it to a stack of the same item or swapping it with anything else
+    fn move_stack(&mut self, game: &mut Game, slot: usize) {
+        let inventory = &mut game.inventory;
+        match self.held.take() {
+            None => {
+                if inventory.slot(slot).is_some() {
+                    self.held = Some(slot);
+                }
+            }
+            Some(from) => {
+                let stacks = match (inventory.slot(from), inventory.slot(slot)) {
+                    (Some(held), Some(onto)) => held.can_stack_with(onto),
+                    _ => true,
+                };
+                if stacks {
+                    inventory.merge(from, slot);
+                } else {
+                    inventory.swap(from, slot);
+                }
+            }
+        }
+    }
+
+    // draws every inventory slot and returns the one clicked
+    fn inventory_grid(&self, ui: &mut Ui, game: &Game, top: f32) -> Option<usize> {
+        let mut clicked = None;
+        for (slot, rect) in inventory_rects(ui, top) {
+            let label = stack_label(game, game.inventory.slot(slot));
+            if ui.slot(&label, rect, self.held == Some(slot)) {
+                clicked = Some(slot);
+            }
+        }
+        clicked
+    }
+
+    fn inventory(&mut self, ui: &mut Ui, game: &mut Game) {
+        ui.shade();
+        let [x, y] = ui.centre();
+        let top = (y - inventory_height() / 2.0).round();
+        ui.centred_label("inventory", [x, top - GLYPH_SIZE[1]]);
+        if let Some(slot) = self.inventory_grid(ui, game, top) {
+            self.move_stack(game, slot);
+        }
+    }
+
+    // pick a stack in the inventory, then click grid slots to put one of it in each.
+    // clicking a grid slot with nothing picked hands its items back
+    fn crafting(&mut self, ui: &mut Ui, game: &mut Game) {
+        ui.shade();
+        let [x, y] = ui.centre();
+        let (width, height) = (game.crafting.width(), game.crafting.height());
+        let grid_height = height as f32 * (SLOT_SIZE[1] + SLOT_GAP);
+        let top = (y - (grid_height + GAP * 2.0 + inventory_height()) / 2.0).round();
+        ui.centred_label("crafting", [x, top - GLYPH_SIZE[1]]);
+
+        // the grid, an arrow, then what it makes, all centred
+        let left = x - (width + 2) as f32 * (SLOT_SIZE[0] + SLOT_GAP) / 2.0;
+        for gy in 0..height {
+            for gx in 0..width {
+                let rect = slot_rect(left, top, gx, gy as f32);
+                let label = stack_label(game, game.crafting.slot(gx, gy));
+                if !ui.slot(&label, rect, false) {
+                    continue;
+                }
+                match self.held {
+                    Some(from) => {
+                        game.put_in_grid(from, gx, gy);
+                        if game.inventory.slot(from).is_none() {
+                            self.held = None;
+                        }
+                    }
+                    None => {
+                        if let Some(stack) = game.crafting.set_slot(gx, gy, None) {
+                            game.give(stack);
+                        }
+                    }
+                }
+            }
+        }
+        let middle = (grid_height - SLOT_GAP) / 2.0 - SLOT_SIZE[1] / 2.0;
+        let arrow = slot_rect(left, top + middle, width, 0.0);
+        ui.centred_label("=>", arrow.centre());
+        let output = game.crafting_output();
+        let rect = slot_rect(left, top + middle, width + 1, 0.0);
+        if ui.slot(&stack_label(game, output.as_ref()), rect, false) && output.is_some() {
+            game.take_crafted();
+        }
+
+        let inventory_top = top + grid_height + GAP * 2.0;
+        if let Some(slot) = self.inventory_grid(ui, game, inventory_top) {
+            let empty = game.inventory.slot(slot).is_none();
+            self.held = (self.held != Some(slot) && !empty).then_some(slot);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::item::ItemId;
+    use crate::world::split_pos;
+    use crate::world::voxel::BlockType;
+
+    fn playing() -> (Menus, Game) {
+        let mut menus = Menus::new();
+        let mut game = Game::new(0);
+        menus.open(Screen::Playing, &mut game);
+        (menus, game)
+    }
+
+    #[test]
+    fn escape_pauses_and_goes_back() {
+        let (mut menus, mut game) = playing();
+        assert!(menus.screen().grabs_pointer());
+        menus.back(&mut game);
+        assert_eq!(menus.screen(), Screen::Paused);
+        assert!(!menus.screen().grabs_pointer());
+        // settings go back to wherever they were opened from
+        menus.open(Screen::Settings, &mut game);
+        menus.back(&mut game);
+        assert_eq!(menus.screen(), Screen::Paused);
+        menus.back(&mut game);
+        assert_eq!(menus.screen(), Screen::Playing);
+
+        menus.toggle(Screen::Inventory, &mut game);
+        assert_eq!(menus.screen(), Screen::Inventory);
+        assert!(!menus.screen().grabs_pointer() && !menus.screen().pauses());
+        // another screen's key does nothing until this one's closed
+        menus.toggle(Screen::Crafting, &mut game);
+        assert_eq!(menus.screen(), Screen::Inventory);
+        menus.toggle(Screen::Inventory, &mut game);
+        assert_eq!(menus.screen(), Screen::Playing);
+
+        menus.pause(&mut game);
+        assert_eq!(menus.screen(), Screen::Paused);
+    }
+
+    #[test]
+    fn closing_crafting_hands_the_grid_back() {
+        let (mut menus, mut game) = playing();
+        let planks = game.items.id("planks").unwrap();
+        game.inventory
+            .set_slot(0, Some(game.items.stack(planks, 4)));
+        menus.toggle(Screen::Crafting, &mut game);
+        game.put_in_grid(0, 0, 0);
+        game.put_in_grid(0, 1, 1);
+        assert_eq!(game.inventory.count(planks), 2);
+        menus.back(&mut game);
+        assert!(game.crafting.is_empty());
+        assert_eq!(game.inventory.count(planks), 4);
+    }
+
+    // the first inventory slot holding `item`
+    fn slot_of(game: &Game, item: ItemId) -> usize {
+        (0..game.inventory.len())
+            .find(|&slot| game.inventory.slot(slot).is_some_and(|s| s.item == item))
+            .unwrap()
+    }
+
+    #[test]
+    fn a_wooden_pickaxe_can_be_made_from_nothing() {
+        let (mut menus, mut game) = playing();
+        let [log, planks, stick, table, pickaxe] =
+            ["log", "planks", "stick", "crafting_table", "wooden_pickaxe"]
+                .map(|name| game.items.id(name).unwrap());
+        assert!(game.inventory.is_empty());
+
+        // chops down a few logs next to the player and picks them up
+        let feet = game.player.position().map(|v| v.floor() as i32);
+        game.generate_around(split_pos(feet).0, 0);
+        let log_block = game.world.blocks.default_voxel(BlockType::Log);
+        for dy in 0..3 {
+            let pos = [feet[0] + 1, feet[1] + dy, feet[2]];
+            game.set_block(pos, log_block);
+            game.break_block(pos);
+        }
+        for _ in 0..40 {
+            game.tick();
+        }
+        assert_eq!(game.inventory.count(log), 3);
+
+        // planks, a table and sticks all fit in the player's own grid
+        menus.toggle(Screen::Crafting, &mut game);
+        for _ in 0..3 {
+            game.put_in_grid(slot_of(&game, log), 0, 0);
+            game.take_crafted();
+        }
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            game.put_in_grid(slot_of(&game, planks), x, y);
+        }
+        game.take_crafted();
+        game.put_in_grid(slot_of(&game, planks), 0, 0);
+        game.put_in_grid(slot_of(&game, planks), 0, 1);
+        game.take_crafted();
+        menus.back(&mut game);
+        assert_eq!(game.inventory.count(table), 1);
+        assert_eq!(game.inventory.count(stick), 4);
+
+        // puts the table down on a block well above the player, looking down at it
+        let ground = [feet[0], feet[1] + 20, feet[2]];
+        game.set_block(ground, game.world.blocks.default_voxel(BlockType::Stone));
+        let eye = ground.map(|v| v as f32 + 0.5);
+        game.camera.set_position((eye[0], eye[1] + 2.5, eye[2]));
+        game.camera.set_direction((0.0, -1.0, 0.0));
+        game.inventory.select(slot_of(&game, table));
+        menus.use_item(&mut game);
+        let above = [ground[0], ground[1] + 1, ground[2]];
+        assert_eq!(
+            game.world.get_voxel(above).block_type,
+            BlockType::CraftingTable
+        );
+
+        // using it opens crafting with a grid big enough for the pickaxe
+        menus.use_item(&mut game);
+        assert_eq!(menus.screen(), Screen::Crafting);
+        assert_eq!((game.crafting.width(), game.crafting.height()), (3, 3));
+        for x in 0..3 {
+            game.put_in_grid(slot_of(&game, planks), x, 0);
+        }
+        game.put_in_grid(slot_of(&game, stick), 1, 1);
+        game.put_in_grid(slot_of(&game, stick), 1, 2);
+        game.take_crafted();
+        assert_eq!(game.inventory.count(pickaxe), 1);
+
+        // and the player's small grid is back once it's closed
+        menus.back(&mut game);
+        assert_eq!((game.crafting.width(), game.crafting.height()), (2, 2));
+    }
+
+    #[test]
+    fn stacks_move_between_slots() {
+        let (mut menus, mut game) = playing();
+        let dirt = game.items.id("dirt").unwrap();
+        let stone = game.items.id("stone").unwrap();
+        game.inventory.set_slot(0, Some(game.items.stack(dirt, 10)));
+        game.inventory.set_slot(1, Some(game.items.stack(dirt, 5)));
+        game.inventory.set_slot(2, Some(game.items.stack(stone, 1)));
+
+        // clicking an empty slot first doesn't pick anything up
+        menus.move_stack(&mut game, 20);
+        assert_eq!(menus.held, None);
+        menus.move_stack(&mut game, 0);
+        menus.move_stack(&mut game, 1);
+        assert_eq!(game.inventory.slot(0), None);
+        assert_eq!(game.inventory.slot(1).unwrap().count, 15);
+
+        menus.move_stack(&mut game, 1);
+        menus.move_stack(&mut game, 2);
+        assert_eq!(game.inventory.slot(1).unwrap().item, stone);
+        assert_eq!(game.inventory.slot(2).unwrap().item, dirt);
+    }
+
+    #[test]
+    fn controls_bind_the_next_key() {
+        use glium::winit::event::MouseButton;
+
+        let (mut menus, mut game) = playing();
+        menus.back(&mut game);
+        menus.open(Screen::Settings, &mut game);
+        menus.open(Screen::Controls, &mut game);
+        let mut bindings = Bindings::default();
+
+        // escape leaves the binding alone
+        menus.rebinding = Some(Action::Jump);
+        assert!(!menus.rebind(Binding::Key(KeyCode::Escape), &mut bindings));
+        assert_eq!(menus.rebinding(), None);
+        assert_eq!(bindings, Bindings::default());
+
+        let middle = Binding::Mouse(MouseButton::Middle);
+        menus.rebinding = Some(Action::Jump);
+        assert!(menus.rebind(middle, &mut bindings));
+        assert_eq!(bindings.actions(middle), [Action::Jump]);
+        // only the one press is taken
+        assert!(!menus.rebind(Binding::Key(KeyCode::KeyQ), &mut bindings));
+
+        // the controls go back to the settings, which still go back to the pause menu
+        menus.back(&mut game);
+        assert_eq!(menus.screen(), Screen::Settings);
+        menus.back(&mut game);
+        assert_eq!(menus.screen(), Screen::Paused);
+    }
+}
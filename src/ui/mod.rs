@@ -0,0 +1,417 @@
+pub mod menus;
+
+use glium::winit::event::{ElementState, MouseButton, WindowEvent};
+use glium::winit::keyboard::{KeyCode, PhysicalKey};
+
+use crate::render::text::{text_width, TextMesh, GLYPH_SIZE};
+
+// an immediate mode ui: menus call widget functions every frame, each one draws itself
+// and says whether it was used. nothing is kept between frames but which widget has the
+// mouse and which has the keyboard, so a widget's id is just its place in the frame
+
+const TEXT: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const BUTTON: [f32; 4] = [0.3, 0.3, 0.3, 0.9];
+const HOVERED: [f32; 4] = [0.45, 0.45, 0.45, 0.9];
+const PRESSED: [f32; 4] = [0.2, 0.2, 0.2, 0.9];
+const SELECTED: [f32; 4] = [0.35, 0.45, 0.65, 0.9];
+const WARNING: [f32; 4] = [0.6, 0.25, 0.2, 0.9];
+const SLIDER_FILL: [f32; 4] = [0.35, 0.45, 0.65, 0.9];
+const FOCUS_OUTLINE: [f32; 4] = [1.0, 0.9, 0.4, 1.0];
+const FOCUS_WIDTH: f32 = 2.0;
+// darkens the world behind a menu
+const SHADE: [f32; 4] = [0.0, 0.0, 0.0, 0.5];
+// the arrow keys move a focused slider this fraction of the way along
+const SLIDER_STEP: f32 = 0.05;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Rect {
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+}
+
+impl Rect {
+    pub fn new(min: [f32; 2], size: [f32; 2]) -> Rect {
+        Rect {
+            min,
+            max: [min[0] + size[0], min[1] + size[1]],
+        }
+    }
+
+    pub fn centred(centre: [f32; 2], size: [f32; 2]) -> Rect {
+        Rect::new([centre[0] - size[0] / 2.0, centre[1] - size[1] / 2.0], size)
+    }
+
+    pub fn width(&self) -> f32 {
+        self.max[0] - self.min[0]
+    }
+
+    pub fn centre(&self) -> [f32; 2] {
+        [
+            (self.min[0] + self.max[0]) / 2.0,
+            (self.min[1] + self.max[1]) / 2.0,
+        ]
+    }
+
+    pub fn contains(&self, point: [f32; 2]) -> bool {
+        (0..2).all(|a| point[a] >= self.min[a] && point[a] < self.max[a])
+    }
+}
+
+// `count` rects of `size` going down from `top`, centred on `x`
+pub fn column(x: f32, top: f32, size: [f32; 2], gap: f32, count: usize) -> Vec<Rect> {
+    (0..count)
+        .map(|i| {
+            let y = top + i as f32 * (size[1] + gap);
+            Rect::new([x - size[0] / 2.0, y], size)
+        })
+        .collect()
+}
+
+// keys for getting round a menu without the mouse
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NavKey {
+    Next,
+    Previous,
+    Left,
+    Right,
+    Activate,
+}
+
+#[derive(Default)]
+pub struct Ui {
+    size: [f32; 2],
+    mouse: [f32; 2],
+    mouse_down: bool,
+    // the left button went down or up since the last frame
+    pressed: bool,
+    released: bool,
+    keys: Vec<NavKey>,
+    // what this frame's keys do to the focused widget
+    activate: bool,
+    step: f32,
+    // the widget the mouse went down on. only it can be clicked or dragged until the
+    // button is let go
+    active: Option<usize>,
+    // the widget the keyboard works
+    focus: Option<usize>,
+    // widgets so far this frame, and last frame for moving the focus round
+    widgets: usize,
+    last_widgets: usize,
+    mesh: TextMesh,
+}
+
+impl Ui {
+    pub fn new() -> Ui {
+        Ui::default()
+    }
+
+    pub fn process_input(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.mouse_moved([position.x as f32, position.y as f32])
+            }
+            WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Left,
+                ..
+            } => self.mouse_button(*state == ElementState::Pressed),
+            WindowEvent::KeyboardInput { event, .. } if event.state == ElementState::Pressed => {
+                let key = match event.physical_key {
+                    PhysicalKey::Code(KeyCode::Tab | KeyCode::ArrowDown) => NavKey::Next,
+                    PhysicalKey::Code(KeyCode::ArrowUp) => NavKey::Previous,
+                    PhysicalKey::Code(KeyCode::ArrowLeft) => NavKey::Left,
+                    PhysicalKey::Code(KeyCode::ArrowRight) => NavKey::Right,
+                    PhysicalKey::Code(KeyCode::Enter | KeyCode::NumpadEnter) => NavKey::Activate,
+                    _ => return,
+                };
+                self.key(key);
+            }
+            _ => (),
+        }
+    }
+
+    pub fn mouse_moved(&mut self, position: [f32; 2]) {
+        self.mouse = position;
+    }
+
+    pub fn mouse_button(&mut self, down: bool) {
+        self.mouse_down = down;
+        if down {
+            self.pressed = true;
+        } else {
+            self.released = true;
+        }
+    }
+
+    pub fn key(&mut self, key: NavKey) {
+        self.keys.push(key);
+    }
+
+    // forgets the focus, for when another screen opens
+    pub fn reset(&mut self) {
+        self.focus = None;
+        self.active = None;
+        self.last_widgets = 0;
+    }
+
+    // starts a frame on a screen `size` pixels across
+    pub fn begin(&mut self, size: [f32; 2]) {
+        self.size = size;
+        // using the mouse takes over from the keyboard
+        if self.pressed {
+            self.focus = None;
+        }
+        let count = self.last_widgets;
+        for key in std::mem::take(&mut self.keys) {
+            if count == 0 {
+                break;
+            }
+            match key {
+                NavKey::Next => {
+                    self.focus = Some(self.focus.map_or(0, |focus| (focus + 1) % count))
+                }
+                NavKey::Previous => {
+                    self.focus = Some(
+                        self.focus
+                            .map_or(count - 1, |focus| (focus + count - 1) % count),
+                    )
+                }
+                NavKey::Left => self.step -= SLIDER_STEP,
+                NavKey::Right => self.step += SLIDER_STEP,
+                NavKey::Activate => self.activate = true,
+            }
+        }
+    }
+
+    // ends the frame, handing over everything drawn in it
+    pub fn finish(&mut self) -> TextMesh {
+        self.last_widgets = self.widgets;
+        self.widgets = 0;
+        if self.focus.is_some_and(|focus| focus >= self.last_widgets) {
+            self.focus = None;
+        }
+        if !self.mouse_down {
+            self.active = None;
+        }
+        self.pressed = false;
+        self.released = false;
+        self.activate = false;
+        self.step = 0.0;
+        std::mem::take(&mut self.mesh)
+    }
+
+    pub fn size(&self) -> [f32; 2] {
+        self.size
+    }
+
+    pub fn centre(&self) -> [f32; 2] {
+        [self.size[0] / 2.0, self.size[1] / 2.0]
+    }
+
+    // the next widget's id, whether the mouse is over it and whether it was clicked:
+    // pressed and let go over it, or activated from the keyboard
+    fn interact(&mut self, rect: Rect) -> (usize, bool, bool) {
+        let id = self.widgets;
+        self.widgets += 1;
+        let hovered = rect.contains(self.mouse);
+        if hovered && self.pressed {
+            self.active = Some(id);
+        }
+        let clicked = (self.released && hovered && self.active == Some(id))
+            || (self.activate && self.focus == Some(id));
+        (id, hovered, clicked)
+    }
+
+    fn outline_focus(&mut self, id: usize, rect: Rect) {
+        if self.focus != Some(id) {
+            return;
+        }
+        let (min, max, w) = (rect.min, rect.max, FOCUS_WIDTH);
+        self.mesh.push_rect(
+            [min[0] - w, min[1] - w],
+            [max[0] + w, min[1]],
+            FOCUS_OUTLINE,
+        );
+        self.mesh.push_rect(
+            [min[0] - w, max[1]],
+            [max[0] + w, max[1] + w],
+            FOCUS_OUTLINE,
+        );
+        self.mesh
+            .push_rect([min[0] - w, min[1]], [min[0], max[1]], FOCUS_OUTLINE);
+        self.mesh
+            .push_rect([max[0], min[1]], [max[0] + w, max[1]], FOCUS_OUTLINE);
+    }
+
+    // `colour` when the mouse isn't on it
+    fn button_colour(&self, id: usize, hovered: bool, colour: [f32; 4]) -> [f32; 4] {
+        if hovered && self.mouse_down && self.active == Some(id) {
+            PRESSED
+        } else if hovered {
+            HOVERED
+        } else {
+            colour
+        }
+    }
+
+    pub fn rect(&mut self, rect: Rect, colour: [f32; 4]) {
+        self.mesh.push_rect(rect.min, rect.max, colour);
+    }
+
+    // darkens everything drawn so far, to put a menu over the world
+    pub fn shade(&mut self) {
+        self.mesh.push_rect([0.0, 0.0], self.size, SHADE);
+    }
+
+    pub fn label(&mut self, text: &str, at: [f32; 2]) {
+        self.mesh.push_text(text, at, TEXT);
+    }
+
+    // lines of text centred on `centre`
+    pub fn centred_label(&mut self, text: &str, centre: [f32; 2]) {
+        let height = text.lines().count() as f32 * GLYPH_SIZE[1];
+        let at = [
+            (centre[0] - text_width(text) / 2.0).round(),
+            (centre[1] - height / 2.0).round(),
+        ];
+        self.mesh.push_text(text, at, TEXT);
+    }
+
+    pub fn button(&mut self, text: &str, rect: Rect) -> bool {
+        self.slot(text, rect, false)
+    }
+
+    // a button that can be shown picked, for inventory slots
+    pub fn slot(&mut self, text: &str, rect: Rect, selected: bool) -> bool {
+        self.coloured_button(text, rect, if selected { SELECTED } else { BUTTON })
+    }
+
+    // a button that can be marked as wrong, like a key bound to two things
+    pub fn warning_button(&mut self, text: &str, rect: Rect, warn: bool) -> bool {
+        self.coloured_button(text, rect, if warn { WARNING } else { BUTTON })
+    }
+
+    fn coloured_button(&mut self, text: &str, rect: Rect, colour: [f32; 4]) -> bool {
+        let (id, hovered, clicked) = self.interact(rect);
+        let colour = self.button_colour(id, hovered, colour);
+        self.rect(rect, colour);
+        self.outline_focus(id, rect);
+        self.centred_label(text, rect.centre());
+        clicked
+    }
+
+    // a slot that can't be clicked, for the hud
+    pub fn slot_display(&mut self, text: &str, rect: Rect, selected: bool) {
+        self.rect(rect, if selected { SELECTED } else { BUTTON });
+        self.centred_label(text, rect.centre());
+    }
+
+    // dragged with the mouse between `min` and `max`, or stepped along with the arrow
+    // keys while focused. returns whether `value` changed
+    pub fn slider(&mut self, text: &str, value: &mut f32, min: f32, max: f32, rect: Rect) -> bool {
+        let (id, hovered, _) = self.interact(rect);
+        let before = *value;
+        let dragging = self.mouse_down || self.released;
+        if self.active == Some(id) && dragging {
+            let along = (self.mouse[0] - rect.min[0]) / rect.width();
+            *value = min + along.clamp(0.0, 1.0) * (max - min);
+        }
+        if self.focus == Some(id) {
+            *value = (*value + self.step * (max - min)).clamp(min, max);
+        }
+
+        let colour = self.button_colour(id, hovered, BUTTON);
+        self.rect(rect, colour);
+        let filled = (*value - min) / (max - min) * rect.width();
+        self.mesh
+            .push_rect(rect.min, [rect.min[0] + filled, rect.max[1]], SLIDER_FILL);
+        self.outline_focus(id, rect);
+        self.centred_label(text, rect.centre());
+        *value != before
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: [f32; 2] = [640.0, 480.0];
+
+    fn buttons(ui: &mut Ui) -> [bool; 3] {
+        ui.begin(SIZE);
+        let rects = column(320.0, 100.0, [200.0, 30.0], 10.0, 3);
+        let clicked: Vec<bool> = rects
+            .iter()
+            .map(|&rect| ui.button("button", rect))
+            .collect();
+        ui.finish();
+        clicked.try_into().unwrap()
+    }
+
+    #[test]
+    fn buttons_click_when_let_go_over_them() {
+        let mut ui = Ui::new();
+        ui.mouse_moved([320.0, 145.0]);
+        ui.mouse_button(true);
+        ui.mouse_button(false);
+        assert_eq!(buttons(&mut ui), [false, true, false]);
+        assert_eq!(buttons(&mut ui), [false; 3]);
+
+        // pressing on one and letting go off it doesn't count
+        ui.mouse_button(true);
+        assert_eq!(buttons(&mut ui), [false; 3]);
+        ui.mouse_moved([320.0, 105.0]);
+        ui.mouse_button(false);
+        assert_eq!(buttons(&mut ui), [false; 3]);
+    }
+
+    #[test]
+    fn keys_move_the_focus_round() {
+        let mut ui = Ui::new();
+        buttons(&mut ui);
+        ui.key(NavKey::Next);
+        ui.key(NavKey::Next);
+        ui.key(NavKey::Activate);
+        assert_eq!(buttons(&mut ui), [false, true, false]);
+        // going back from the first wraps round to the last
+        ui.key(NavKey::Previous);
+        ui.key(NavKey::Previous);
+        ui.key(NavKey::Activate);
+        assert_eq!(buttons(&mut ui), [false, false, true]);
+        // clicking somewhere takes the focus away
+        ui.mouse_button(true);
+        ui.mouse_button(false);
+        ui.key(NavKey::Activate);
+        assert_eq!(buttons(&mut ui), [false; 3]);
+    }
+
+    #[test]
+    fn sliders_drag_and_step() {
+        let mut ui = Ui::new();
+        let rect = Rect::new([100.0, 100.0], [200.0, 20.0]);
+        let mut value = 50.0;
+        let frame = |ui: &mut Ui, value: &mut f32| {
+            ui.begin(SIZE);
+            let changed = ui.slider("value", value, 0.0, 100.0, rect);
+            ui.finish();
+            changed
+        };
+
+        ui.mouse_moved([150.0, 110.0]);
+        ui.mouse_button(true);
+        assert!(frame(&mut ui, &mut value));
+        assert_eq!(value, 25.0);
+        // it keeps following the mouse past its ends until let go
+        ui.mouse_moved([400.0, 300.0]);
+        ui.mouse_button(false);
+        assert!(frame(&mut ui, &mut value));
+        assert_eq!(value, 100.0);
+
+        ui.key(NavKey::Next);
+        ui.key(NavKey::Left);
+        ui.key(NavKey::Left);
+        assert!(frame(&mut ui, &mut value));
+        assert!((value - 90.0).abs() < 1e-3);
+        assert!(!frame(&mut ui, &mut value));
+    }
+}
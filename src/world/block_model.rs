@@ -0,0 +1,205 @@
+use std::{collections::HashMap, path::Path};
+
+use serde::Deserialize;
+
+use crate::support::data::{load_ron, DataError};
+use crate::world::collision::Aabb;
+use crate::world::voxel::{BlockType, Voxel};
+
+// north is -z, east is +x, south is +z, west is -x
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Default, Deserialize)]
+pub enum Facing {
+    #[default]
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Facing {
+    // the side a horizontal direction points towards most
+    pub fn looking(x: f32, z: f32) -> Facing {
+        if x.abs() > z.abs() {
+            if x > 0.0 {
+                Facing::East
+            } else {
+                Facing::West
+            }
+        } else if z > 0.0 {
+            Facing::South
+        } else {
+            Facing::North
+        }
+    }
+
+    // turns a point in block space around the block's vertical centre line,
+    // models are authored facing north
+    pub fn rotate(&self, p: [f32; 3]) -> [f32; 3] {
+        match self {
+            Facing::North => p,
+            Facing::East => [1.0 - p[2], p[1], p[0]],
+            Facing::South => [1.0 - p[0], p[1], 1.0 - p[2]],
+            Facing::West => [p[2], p[1], 1.0 - p[0]],
+        }
+    }
+
+    pub fn rotate_box(&self, b: &Aabb) -> Aabb {
+        let a = self.rotate(b.min);
+        let c = self.rotate(b.max);
+        Aabb::new(
+            [a[0].min(c[0]), a[1].min(c[1]), a[2].min(c[2])],
+            [a[0].max(c[0]), a[1].max(c[1]), a[2].max(c[2])],
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Default, Deserialize)]
+pub enum Half {
+    #[default]
+    Bottom,
+    Top,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub enum BlockShape {
+    Empty,
+    Cube,
+    Slab(Half),
+    // the tall step is on the north side, turned by the voxel's facing like everything else
+    Stairs,
+    // two crossed quads, used for plants. has no collision
+    Cross,
+    // surface height follows the fluid level. has no collision
+    Fluid,
+    Custom(Vec<Aabb>),
+    // drawn like Custom, but collides as if every box went up to BARRIER_HEIGHT so it
+    // can't be jumped over or stood on. for fences
+    Barrier(Vec<Aabb>),
+}
+
+const FULL: Aabb = Aabb::new([0.0, 0.0, 0.0], [1.0, 1.0, 1.0]);
+const BOTTOM_SLAB: Aabb = Aabb::new([0.0, 0.0, 0.0], [1.0, 0.5, 1.0]);
+const TOP_SLAB: Aabb = Aabb::new([0.0, 0.5, 0.0], [1.0, 1.0, 1.0]);
+const STAIR_STEP: Aabb = Aabb::new([0.0, 0.5, 0.0], [1.0, 1.0, 0.5]);
+// higher than anything can jump
+pub const BARRIER_HEIGHT: f32 = 1.5;
+
+impl BlockShape {
+    // the boxes making up the shape in block space as authored, before any state is applied
+    pub fn boxes(&self) -> Vec<Aabb> {
+        match self {
+            BlockShape::Empty | BlockShape::Cross | BlockShape::Fluid => Vec::new(),
+            BlockShape::Cube => vec![FULL],
+            BlockShape::Slab(Half::Bottom) => vec![BOTTOM_SLAB],
+            BlockShape::Slab(Half::Top) => vec![TOP_SLAB],
+            BlockShape::Stairs => vec![BOTTOM_SLAB, STAIR_STEP],
+            BlockShape::Custom(boxes) | BlockShape::Barrier(boxes) => boxes.clone(),
+        }
+    }
+
+    // the boxes things bump into, in block space as authored
+    pub fn collision_boxes(&self) -> Vec<Aabb> {
+        match self {
+            BlockShape::Barrier(boxes) => boxes
+                .iter()
+                .map(|b| Aabb::new(b.min, [b.max[0], BARRIER_HEIGHT, b.max[2]]))
+                .collect(),
+            shape => shape.boxes(),
+        }
+    }
+
+    // only full cubes hide the faces of their neighbours
+    pub fn is_full_cube(&self) -> bool {
+        *self == BlockShape::Cube
+    }
+}
+
+static EMPTY: BlockShape = BlockShape::Empty;
+static CUBE: BlockShape = BlockShape::Cube;
+static TOP_SLAB_SHAPE: BlockShape = BlockShape::Slab(Half::Top);
+
+// which shape each block type uses. anything not listed is a cube
+pub struct BlockModels {
+    shapes: HashMap<BlockType, BlockShape>,
+}
+
+impl Default for BlockModels {
+    fn default() -> Self {
+        let mut shapes = HashMap::new();
+        shapes.insert(BlockType::PlankSlab, BlockShape::Slab(Half::Bottom));
+        shapes.insert(BlockType::PlankStairs, BlockShape::Stairs);
+        shapes.insert(BlockType::TallGrass, BlockShape::Cross);
+        shapes.insert(BlockType::Water, BlockShape::Fluid);
+        shapes.insert(BlockType::Lava, BlockShape::Fluid);
+        BlockModels { shapes }
+    }
+}
+
+impl BlockModels {
+    // the built in shapes with anything from the data file laid over the top
+    pub fn load(path: impl AsRef<Path>) -> Result<BlockModels, DataError> {
+        let mut models = BlockModels::default();
+        let shapes: HashMap<BlockType, BlockShape> = load_ron(path)?;
+        models.shapes.extend(shapes);
+        Ok(models)
+    }
+
+    pub fn set_shape(&mut self, block_type: BlockType, shape: BlockShape) {
+        self.shapes.insert(block_type, shape);
+    }
+
+    pub fn shape(&self, voxel: &Voxel) -> &BlockShape {
+        if voxel.block_type.is_air() {
+            return &EMPTY;
+        }
+        self.shapes.get(&voxel.block_type).unwrap_or(&CUBE)
+    }
+
+    // the shape with the voxel's half state applied
+    fn placed_shape(&self, voxel: &Voxel) -> &BlockShape {
+        match self.shape(voxel) {
+            BlockShape::Slab(Half::Bottom) if voxel.state.half() == Half::Top => &TOP_SLAB_SHAPE,
+            shape => shape,
+        }
+    }
+
+    // the shape's boxes turned to match the voxel's facing and half state
+    pub fn boxes(&self, voxel: &Voxel) -> Vec<Aabb> {
+        let facing = voxel.state.facing();
+        let boxes = self.placed_shape(voxel).boxes();
+        boxes.iter().map(|b| facing.rotate_box(b)).collect()
+    }
+
+    // like `boxes`, but what things bump into rather than what's drawn
+    pub fn collision_boxes(&self, voxel: &Voxel) -> Vec<Aabb> {
+        let facing = voxel.state.facing();
+        let boxes = self.placed_shape(voxel).collision_boxes();
+        boxes.iter().map(|b| facing.rotate_box(b)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::voxel::BlockType;
+
+    #[test]
+    fn stairs_are_turned_once_by_their_facing() {
+        let models = BlockModels::default();
+        let stairs = Voxel {
+            block_type: BlockType::PlankStairs,
+            ..Voxel::default()
+        };
+        let step = |facing: Facing| {
+            let voxel = Voxel {
+                state: stairs.state.with_facing(facing),
+                ..stairs
+            };
+            models.boxes(&voxel)[1]
+        };
+        assert_eq!(step(Facing::North), STAIR_STEP);
+        assert_eq!(step(Facing::East).min, [0.5, 0.5, 0.0]);
+        assert_eq!(step(Facing::South).min, [0.0, 0.5, 0.5]);
+        assert_eq!(step(Facing::West).max, [0.5, 1.0, 1.0]);
+    }
+}
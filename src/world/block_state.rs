@@ -0,0 +1,138 @@
+use serde::Deserialize;
+
+use crate::world::block_model::{Facing, Half};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Default, Deserialize)]
+pub enum Axis {
+    X,
+    #[default]
+    Y,
+    Z,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub enum Property {
+    Facing,
+    Axis,
+    Half,
+    Growth,
+    Open,
+    Wetness,
+    // fluids: 0 is a source, 1 to 7 is flowing and gets weaker as it goes up, 8 is falling
+    Level,
+}
+
+impl Property {
+    // (shift, width) of the property inside the packed state
+    const fn layout(&self) -> (u16, u16) {
+        match self {
+            Property::Facing => (0, 2),
+            Property::Axis => (2, 2),
+            Property::Half => (4, 1),
+            Property::Growth => (5, 3),
+            Property::Open => (8, 1),
+            Property::Wetness => (9, 3),
+            Property::Level => (12, 4),
+        }
+    }
+
+    pub fn max_value(&self) -> u8 {
+        match self {
+            Property::Axis => 2,
+            _ => ((1u16 << self.layout().1) - 1) as u8,
+        }
+    }
+
+    fn mask(&self) -> u16 {
+        let (shift, width) = self.layout();
+        ((1u16 << width) - 1) << shift
+    }
+}
+
+// every state property of a voxel packed into 16 bits. which properties a block
+// actually uses is declared by the block registry, unused bits stay zero
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub struct BlockState(pub u16);
+
+impl BlockState {
+    pub fn get(&self, property: Property) -> u8 {
+        let (shift, _) = property.layout();
+        ((self.0 & property.mask()) >> shift) as u8
+    }
+
+    // out of range values are clamped to the biggest one the property allows
+    pub fn with(self, property: Property, value: u8) -> BlockState {
+        let (shift, _) = property.layout();
+        let value = value.min(property.max_value()) as u16;
+        BlockState((self.0 & !property.mask()) | (value << shift))
+    }
+
+    // false if any bits are set that don't belong to one of `properties`
+    pub fn uses_only(&self, properties: &[Property]) -> bool {
+        let allowed = properties.iter().fold(0, |mask, p| mask | p.mask());
+        self.0 & !allowed == 0
+    }
+
+    pub fn facing(&self) -> Facing {
+        match self.get(Property::Facing) {
+            0 => Facing::North,
+            1 => Facing::East,
+            2 => Facing::South,
+            _ => Facing::West,
+        }
+    }
+
+    pub fn with_facing(self, facing: Facing) -> BlockState {
+        let value = match facing {
+            Facing::North => 0,
+            Facing::East => 1,
+            Facing::South => 2,
+            Facing::West => 3,
+        };
+        self.with(Property::Facing, value)
+    }
+
+    pub fn axis(&self) -> Axis {
+        match self.get(Property::Axis) {
+            1 => Axis::X,
+            2 => Axis::Z,
+            _ => Axis::Y,
+        }
+    }
+
+    pub fn with_axis(self, axis: Axis) -> BlockState {
+        let value = match axis {
+            Axis::Y => 0,
+            Axis::X => 1,
+            Axis::Z => 2,
+        };
+        self.with(Property::Axis, value)
+    }
+
+    pub fn half(&self) -> Half {
+        match self.get(Property::Half) {
+            0 => Half::Bottom,
+            _ => Half::Top,
+        }
+    }
+
+    pub fn with_half(self, half: Half) -> BlockState {
+        self.with(Property::Half, (half == Half::Top) as u8)
+    }
+
+    pub fn growth(&self) -> u8 {
+        self.get(Property::Growth)
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.get(Property::Open) != 0
+    }
+
+    pub fn wetness(&self) -> u8 {
+        self.get(Property::Wetness)
+    }
+
+    pub fn level(&self) -> u8 {
+        self.get(Property::Level)
+    }
+}
@@ -0,0 +1,327 @@
+use std::fmt;
+
+use crate::entity::Entity;
+use crate::world::block_state::BlockState;
+use crate::world::voxel::{BlockType, Voxel};
+
+pub const CHUNK_SIZE: usize = 32;
+pub const CHUNK_VOLUME: usize = CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE;
+
+// bumped whenever the byte layout of a saved chunk changes
+const FORMAT_VERSION: u8 = 3;
+// the palette is compacted whenever it reaches this size, and then again at double
+// whatever is left, so it never outgrows the u16 indices
+const FIRST_COMPACT_AT: usize = 256;
+
+// voxels are stored as indices into a palette of every distinct voxel (type, texture and
+// state) in the chunk, so state properties cost nothing per block
+pub struct Chunk {
+    palette: Vec<Voxel>,
+    indices: Vec<u16>,
+    compact_at: usize,
+    // entities saved with the chunk while it isn't loaded into the entity store
+    pub entities: Vec<Entity>,
+    // multiplies the colour of tinted blocks like grass, from the biomes under the chunk
+    pub tint: [f32; 3],
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ChunkDecodeError {
+    UnexpectedEnd,
+    UnknownVersion(u8),
+    UnknownBlock(u8),
+    BadPaletteIndex(u16),
+    UnknownEntity(u8),
+    UnknownMob(u8),
+}
+
+impl fmt::Display for ChunkDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChunkDecodeError::UnexpectedEnd => write!(f, "chunk data ended early"),
+            ChunkDecodeError::UnknownVersion(v) => write!(f, "unknown chunk format version {v}"),
+            ChunkDecodeError::UnknownBlock(id) => write!(f, "unknown block id {id}"),
+            ChunkDecodeError::BadPaletteIndex(i) => write!(f, "palette index {i} out of range"),
+            ChunkDecodeError::UnknownEntity(tag) => write!(f, "unknown entity type {tag}"),
+            ChunkDecodeError::UnknownMob(id) => write!(f, "unknown mob kind {id}"),
+        }
+    }
+}
+
+impl std::error::Error for ChunkDecodeError {}
+
+impl Default for Chunk {
+    fn default() -> Self {
+        Chunk::new()
+    }
+}
+
+impl Chunk {
+    pub fn new() -> Chunk {
+        Chunk {
+            palette: vec![Voxel::default()],
+            indices: vec![0; CHUNK_VOLUME],
+            compact_at: FIRST_COMPACT_AT,
+            entities: Vec::new(),
+            tint: [1.0; 3],
+        }
+    }
+
+    fn index(x: usize, y: usize, z: usize) -> usize {
+        (y * CHUNK_SIZE + z) * CHUNK_SIZE + x
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> Voxel {
+        self.palette[self.indices[Chunk::index(x, y, z)] as usize]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, z: usize, voxel: Voxel) {
+        let entry = match self.palette.iter().position(|v| *v == voxel) {
+            Some(entry) => entry,
+            None => {
+                // entries nothing uses any more pile up as blocks change
+                if self.palette.len() >= self.compact_at {
+                    self.compact();
+                    self.compact_at = (self.palette.len() * 2).max(FIRST_COMPACT_AT);
+                }
+                self.palette.push(voxel);
+                self.palette.len() - 1
+            }
+        };
+        self.indices[Chunk::index(x, y, z)] = entry as u16;
+    }
+
+    pub fn palette(&self) -> &[Voxel] {
+        &self.palette
+    }
+
+    // drops palette entries nothing points at any more
+    pub fn compact(&mut self) {
+        let mut remap = vec![None; self.palette.len()];
+        let mut palette = Vec::new();
+        for index in self.indices.iter_mut() {
+            let new = *remap[*index as usize].get_or_insert_with(|| {
+                palette.push(self.palette[*index as usize]);
+                (palette.len() - 1) as u16
+            });
+            *index = new;
+        }
+        self.palette = palette;
+    }
+
+    // layout: version, palette length, palette entries (block id, texture column,
+    // texture row, state), then one index per voxel, then the entity count and entities,
+    // then the tint. indices are a single byte when the palette is small enough
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut chunk = Chunk {
+            palette: self.palette.clone(),
+            indices: self.indices.clone(),
+            compact_at: self.compact_at,
+            entities: Vec::new(),
+            tint: self.tint,
+        };
+        chunk.compact();
+
+        let mut bytes = vec![FORMAT_VERSION];
+        bytes.extend((chunk.palette.len() as u16).to_le_bytes());
+        for voxel in &chunk.palette {
+            bytes.push(voxel.block_type.id());
+            bytes.push(voxel.texture_position[0] as u8);
+            bytes.push(voxel.texture_position[1] as u8);
+            bytes.extend(voxel.state.0.to_le_bytes());
+        }
+
+        if chunk.palette.len() <= 256 {
+            bytes.extend(chunk.indices.iter().map(|&i| i as u8));
+        } else {
+            for index in &chunk.indices {
+                bytes.extend(index.to_le_bytes());
+            }
+        }
+
+        bytes.extend((self.entities.len() as u16).to_le_bytes());
+        for entity in &self.entities {
+            entity.encode(&mut bytes);
+        }
+        for channel in self.tint {
+            bytes.extend(channel.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Chunk, ChunkDecodeError> {
+        let mut reader = Reader { bytes, at: 0 };
+
+        // version 1 is the same without entities, and 2 without the tint
+        let version = reader.u8()?;
+        if version == 0 || version > FORMAT_VERSION {
+            return Err(ChunkDecodeError::UnknownVersion(version));
+        }
+
+        let palette_len = reader.u16()? as usize;
+        let mut palette = Vec::with_capacity(palette_len);
+        for _ in 0..palette_len {
+            let id = reader.u8()?;
+            let block_type = BlockType::from_id(id).ok_or(ChunkDecodeError::UnknownBlock(id))?;
+            let texture_position = [reader.u8()? as i8, reader.u8()? as i8];
+            let state = BlockState(reader.u16()?);
+            palette.push(Voxel {
+                block_type,
+                texture_position,
+                state,
+            });
+        }
+
+        let mut indices = Vec::with_capacity(CHUNK_VOLUME);
+        for _ in 0..CHUNK_VOLUME {
+            let index = if palette_len <= 256 {
+                reader.u8()? as u16
+            } else {
+                reader.u16()?
+            };
+            if index as usize >= palette_len {
+                return Err(ChunkDecodeError::BadPaletteIndex(index));
+            }
+            indices.push(index);
+        }
+
+        let mut entities = Vec::new();
+        if version >= 2 {
+            let count = reader.u16()?;
+            for _ in 0..count {
+                entities.push(Entity::decode(&mut reader)?);
+            }
+        }
+        let mut tint = [1.0; 3];
+        if version >= 3 {
+            tint = [reader.f32()?, reader.f32()?, reader.f32()?];
+        }
+
+        Ok(Chunk {
+            compact_at: (palette.len() * 2).max(FIRST_COMPACT_AT),
+            palette,
+            indices,
+            entities,
+            tint,
+        })
+    }
+}
+
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl Reader<'_> {
+    pub(crate) fn u8(&mut self) -> Result<u8, ChunkDecodeError> {
+        let byte = *self
+            .bytes
+            .get(self.at)
+            .ok_or(ChunkDecodeError::UnexpectedEnd)?;
+        self.at += 1;
+        Ok(byte)
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, ChunkDecodeError> {
+        Ok(u16::from_le_bytes([self.u8()?, self.u8()?]))
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, ChunkDecodeError> {
+        Ok(u32::from_le_bytes([
+            self.u8()?,
+            self.u8()?,
+            self.u8()?,
+            self.u8()?,
+        ]))
+    }
+
+    pub(crate) fn f32(&mut self) -> Result<f32, ChunkDecodeError> {
+        Ok(f32::from_bits(self.u32()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::block_model::{Facing, Half};
+    use crate::world::block_state::Property;
+
+    // a different voxel for every n, with a state to go with it
+    fn distinct(n: usize) -> Voxel {
+        let block_type = [BlockType::PlankStairs, BlockType::PlankSlab, BlockType::Log][n % 3];
+        let state = BlockState::default()
+            .with_facing([Facing::North, Facing::East, Facing::South, Facing::West][n % 4])
+            .with_half([Half::Bottom, Half::Top][n % 2])
+            .with(Property::Growth, (n % 8) as u8);
+        Voxel {
+            block_type,
+            texture_position: [(n % 100) as i8, (n / 100) as i8],
+            state,
+        }
+    }
+
+    fn assert_same_voxels(a: &Chunk, b: &Chunk) {
+        for y in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    assert_eq!(a.get(x, y, z), b.get(x, y, z), "at {x} {y} {z}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn small_palettes_round_trip() {
+        let mut chunk = Chunk::new();
+        for i in 0..20 {
+            chunk.set(i, i % 7, 31 - i, distinct(i));
+        }
+        chunk.tint = [0.5, 1.0, 1.5];
+        let bytes = chunk.to_bytes();
+        // one byte per index
+        assert!(bytes.len() < CHUNK_VOLUME + 200);
+        let read = Chunk::from_bytes(&bytes).unwrap();
+        assert_same_voxels(&chunk, &read);
+        assert_eq!(read.tint, chunk.tint);
+    }
+
+    #[test]
+    fn palettes_needing_wide_indices_round_trip() {
+        let mut chunk = Chunk::new();
+        for i in 0..1000 {
+            chunk.set(i % CHUNK_SIZE, i / CHUNK_SIZE, 5, distinct(i));
+        }
+        assert!(chunk.palette().len() > 256);
+        let bytes = chunk.to_bytes();
+        assert!(bytes.len() > CHUNK_VOLUME * 2);
+        assert_same_voxels(&chunk, &Chunk::from_bytes(&bytes).unwrap());
+    }
+
+    #[test]
+    fn palette_forgets_voxels_that_were_overwritten() {
+        let mut chunk = Chunk::new();
+        for i in 0..100_000 {
+            chunk.set(1, 2, 3, distinct(i));
+        }
+        assert!(chunk.palette().len() <= FIRST_COMPACT_AT);
+        assert_eq!(chunk.get(1, 2, 3), distinct(99_999));
+        assert_eq!(chunk.get(0, 0, 0), Voxel::default());
+    }
+
+    #[test]
+    fn truncated_chunks_are_an_error() {
+        let bytes = Chunk::new().to_bytes();
+        for len in [0, 1, 3, bytes.len() - 1] {
+            assert_eq!(
+                Chunk::from_bytes(&bytes[..len]).err(),
+                Some(ChunkDecodeError::UnexpectedEnd)
+            );
+        }
+        let mut bytes = bytes;
+        bytes[0] = 99;
+        assert_eq!(
+            Chunk::from_bytes(&bytes).err(),
+            Some(ChunkDecodeError::UnknownVersion(99))
+        );
+    }
+}
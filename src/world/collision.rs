@@ -0,0 +1,229 @@
+use serde::Deserialize;
+
+use crate::world::World;
+
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize)]
+pub struct Aabb {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl Aabb {
+    pub const fn new(min: [f32; 3], max: [f32; 3]) -> Aabb {
+        Aabb { min, max }
+    }
+
+    pub fn offset(&self, by: [f32; 3]) -> Aabb {
+        Aabb {
+            min: [self.min[0] + by[0], self.min[1] + by[1], self.min[2] + by[2]],
+            max: [self.max[0] + by[0], self.max[1] + by[1], self.max[2] + by[2]],
+        }
+    }
+
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        (0..3).all(|axis| self.min[axis] < other.max[axis] && self.max[axis] > other.min[axis])
+    }
+
+    // how far this box can move along `axis` by `delta` before hitting `other`
+    fn clip_axis(&self, other: &Aabb, axis: usize, delta: f32) -> f32 {
+        let overlaps = (0..3)
+            .filter(|&a| a != axis)
+            .all(|a| self.min[a] < other.max[a] && self.max[a] > other.min[a]);
+        if !overlaps {
+            return delta;
+        }
+
+        if delta > 0.0 && self.max[axis] <= other.min[axis] {
+            delta.min(other.min[axis] - self.max[axis])
+        } else if delta < 0.0 && self.min[axis] >= other.max[axis] {
+            delta.max(other.max[axis] - self.min[axis])
+        } else {
+            delta
+        }
+    }
+}
+
+// every collision box of every block the swept box could touch, in world space
+fn nearby_boxes(world: &World, aabb: &Aabb, delta: [f32; 3]) -> Vec<Aabb> {
+    let mut min = [0; 3];
+    let mut max = [0; 3];
+    for axis in 0..3 {
+        let lo = aabb.min[axis] + delta[axis].min(0.0);
+        let hi = aabb.max[axis] + delta[axis].max(0.0);
+        // slightly below so barriers taller than a block (fences) are found
+        min[axis] = lo.floor() as i32 - 1;
+        max[axis] = hi.floor() as i32;
+    }
+
+    let mut boxes = Vec::new();
+    for x in min[0]..=max[0] {
+        for y in min[1]..=max[1] {
+            for z in min[2]..=max[2] {
+                let voxel = world.get_voxel([x, y, z]);
+                let offset = [x as f32, y as f32, z as f32];
+                for b in world.models.collision_boxes(&voxel) {
+                    boxes.push(b.offset(offset));
+                }
+            }
+        }
+    }
+    boxes
+}
+
+// moves `aabb` by `delta` one axis at a time, stopping at block collision boxes.
+// returns the moved box and which axes were blocked
+pub fn move_and_collide(world: &World, aabb: Aabb, delta: [f32; 3]) -> (Aabb, [bool; 3]) {
+    let boxes = nearby_boxes(world, &aabb, delta);
+    let mut moved = aabb;
+    let mut blocked = [false; 3];
+
+    // y first so walking off a ledge and landing resolve the same way every frame
+    for axis in [1, 0, 2] {
+        let mut d = delta[axis];
+        for b in &boxes {
+            d = moved.clip_axis(b, axis, d);
+        }
+        blocked[axis] = d != delta[axis];
+        let mut by = [0.0; 3];
+        by[axis] = d;
+        moved = moved.offset(by);
+    }
+
+    (moved, blocked)
+}
+
+pub fn intersects_world(world: &World, aabb: &Aabb) -> bool {
+    nearby_boxes(world, aabb, [0.0; 3])
+        .iter()
+        .any(|b| b.intersects(aabb))
+}
+
+// what happened during one `Body::step`
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Step {
+    pub blocked: [bool; 3],
+    // how fast the body was falling, if it landed this tick
+    pub landed: Option<f32>,
+}
+
+// anything that moves through the world and bumps into blocks, the player included.
+// positions are the middle of the bottom of the box
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Body {
+    pub position: [f32; 3],
+    // where the body was at the start of the tick, for drawing between ticks
+    pub previous_position: [f32; 3],
+    pub velocity: [f32; 3],
+    pub half_width: f32,
+    pub height: f32,
+    pub on_ground: bool,
+}
+
+impl Body {
+    pub fn new(position: [f32; 3], half_width: f32, height: f32) -> Body {
+        Body {
+            position,
+            previous_position: position,
+            velocity: [0.0; 3],
+            half_width,
+            height,
+            on_ground: false,
+        }
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        let [x, y, z] = self.position;
+        let w = self.half_width;
+        Aabb::new([x - w, y, z - w], [x + w, y + self.height, z + w])
+    }
+
+    // the position part of the way from last tick to this one
+    pub fn interpolated(&self, alpha: f32) -> [f32; 3] {
+        let mut p = self.position;
+        for (axis, v) in p.iter_mut().enumerate() {
+            *v += (self.previous_position[axis] - self.position[axis]) * (1.0 - alpha);
+        }
+        p
+    }
+
+    // moves by the velocity, stopping at blocks. blocked axes lose their velocity
+    pub fn step(&mut self, world: &World) -> Step {
+        let falling_speed = -self.velocity[1];
+        let (moved, hit) = move_and_collide(world, self.aabb(), self.velocity);
+        self.position = [
+            (moved.min[0] + moved.max[0]) / 2.0,
+            moved.min[1],
+            (moved.min[2] + moved.max[2]) / 2.0,
+        ];
+
+        let landed = hit[1] && self.velocity[1] < 0.0;
+        for (velocity, hit) in self.velocity.iter_mut().zip(hit) {
+            if hit {
+                *velocity = 0.0;
+            }
+        }
+        let was_on_ground = self.on_ground;
+        self.on_ground = landed;
+        Step {
+            blocked: hit,
+            landed: (landed && !was_on_ground).then_some(falling_speed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::block_model::{BlockModels, BARRIER_HEIGHT};
+    use crate::world::registry::BlockRegistry;
+    use crate::world::voxel::{BlockType, Voxel};
+
+    fn world() -> World {
+        let models = BlockModels::load("assets/data/block_models.ron").unwrap();
+        let mut world = World::new(BlockRegistry::default(), models);
+        let stone = Voxel {
+            block_type: BlockType::Stone,
+            ..Voxel::default()
+        };
+        let fence = Voxel {
+            block_type: BlockType::Fence,
+            ..Voxel::default()
+        };
+        for x in -2..4 {
+            world.set_voxel([x, -1, 0], stone);
+        }
+        world.set_voxel([2, 0, 0], fence);
+        world
+    }
+
+    // a box the size of the player, standing at `x` on top of block `y`
+    fn player(x: f32, y: f32) -> Aabb {
+        Aabb::new([x - 0.3, y, 0.2], [x + 0.3, y + 1.8, 0.8])
+    }
+
+    #[test]
+    fn boxes_stop_at_blocks() {
+        let world = world();
+        let (moved, blocked) = move_and_collide(&world, player(0.5, 0.5), [0.0, -1.0, 0.0]);
+        assert_eq!(moved.min[1], 0.0);
+        assert_eq!(blocked, [false, true, false]);
+
+        let (moved, blocked) = move_and_collide(&world, player(0.5, 0.0), [0.5, 0.0, 0.0]);
+        assert!((moved.min[0] - 0.7).abs() < 1e-6);
+        assert!(!blocked[0]);
+        assert!(intersects_world(&world, &player(0.5, -0.5)));
+        assert!(!intersects_world(&world, &player(0.5, 0.0)));
+    }
+
+    #[test]
+    fn fences_are_too_tall_to_get_over() {
+        let world = world();
+        // even from a block's height up, walking into the rails stops at them
+        let (moved, blocked) = move_and_collide(&world, player(1.0, 1.0), [1.0, 0.0, 0.0]);
+        assert!(blocked[0]);
+        assert_eq!(moved.max[0], 2.0);
+        // and falling onto it lands half a block above the drawn post
+        let (moved, _) = move_and_collide(&world, player(2.5, 2.0), [0.0, -2.0, 0.0]);
+        assert_eq!(moved.min[1], BARRIER_HEIGHT);
+    }
+}
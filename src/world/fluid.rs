@@ -0,0 +1,498 @@
+use std::collections::{BTreeSet, HashSet, VecDeque};
+
+use crate::world::block_state::{BlockState, Property};
+use crate::world::voxel::{BlockType, Voxel};
+use crate::world::World;
+
+pub const SOURCE: u8 = 0;
+pub const FALLING: u8 = 8;
+
+const HORIZONTAL: [[i32; 3]; 4] = [[1, 0, 0], [-1, 0, 0], [0, 0, 1], [0, 0, -1]];
+const NEIGHBOURS: [[i32; 3]; 6] = [
+    [1, 0, 0],
+    [-1, 0, 0],
+    [0, 1, 0],
+    [0, -1, 0],
+    [0, 0, 1],
+    [0, 0, -1],
+];
+
+fn add(a: [i32; 3], b: [i32; 3]) -> [i32; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FluidKind {
+    Water,
+    Lava,
+}
+
+impl FluidKind {
+    pub fn of(block_type: BlockType) -> Option<FluidKind> {
+        match block_type {
+            BlockType::Water => Some(FluidKind::Water),
+            BlockType::Lava => Some(FluidKind::Lava),
+            _ => None,
+        }
+    }
+
+    pub fn block_type(&self) -> BlockType {
+        match self {
+            FluidKind::Water => BlockType::Water,
+            FluidKind::Lava => BlockType::Lava,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct FluidRules {
+    // ticks between a fluid block changing and it reacting
+    pub tick_delay: u64,
+    // how much the level goes up per block of sideways flow, it stops once it would pass 7
+    pub level_drop: u8,
+    // a flowing block between two sources becomes a source itself
+    pub infinite_sources: bool,
+    // how far sideways flow looks for a drop to head towards
+    pub slope_distance: u32,
+}
+
+impl FluidRules {
+    pub fn water() -> FluidRules {
+        FluidRules {
+            tick_delay: 5,
+            level_drop: 1,
+            infinite_sources: true,
+            slope_distance: 4,
+        }
+    }
+
+    pub fn lava() -> FluidRules {
+        FluidRules {
+            tick_delay: 30,
+            level_drop: 2,
+            infinite_sources: false,
+            slope_distance: 2,
+        }
+    }
+}
+
+// cellular automaton for water and lava. every change to a fluid (or next to one)
+// schedules an update a few ticks later, so flow spreads one block per update
+pub struct FluidSimulator {
+    pub water: FluidRules,
+    pub lava: FluidRules,
+    tick: u64,
+    scheduled: BTreeSet<(u64, [i32; 3])>,
+    // blocks set since the last tick returned them
+    changed: Vec<[i32; 3]>,
+}
+
+impl Default for FluidSimulator {
+    fn default() -> Self {
+        FluidSimulator::new(FluidRules::water(), FluidRules::lava())
+    }
+}
+
+impl FluidSimulator {
+    pub fn new(water: FluidRules, lava: FluidRules) -> FluidSimulator {
+        FluidSimulator {
+            water,
+            lava,
+            tick: 0,
+            scheduled: BTreeSet::new(),
+            changed: Vec::new(),
+        }
+    }
+
+    pub fn rules(&self, kind: FluidKind) -> FluidRules {
+        match kind {
+            FluidKind::Water => self.water,
+            FluidKind::Lava => self.lava,
+        }
+    }
+
+    pub fn pending(&self) -> usize {
+        self.scheduled.len()
+    }
+
+    // call whenever a block is placed or broken so nearby fluids react
+    pub fn block_changed(&mut self, world: &World, pos: [i32; 3]) {
+        self.schedule(world, pos);
+        for offset in NEIGHBOURS {
+            self.schedule(world, add(pos, offset));
+        }
+    }
+
+    fn schedule(&mut self, world: &World, pos: [i32; 3]) {
+        if let Some(kind) = FluidKind::of(world.get_voxel(pos).block_type) {
+            let due = self.tick + self.rules(kind).tick_delay;
+            self.scheduled.insert((due, pos));
+        }
+    }
+
+    // runs the updates that are due and returns every block they changed
+    pub fn tick(&mut self, world: &mut World) -> Vec<[i32; 3]> {
+        self.tick += 1;
+        while let Some(&(due, pos)) = self.scheduled.first() {
+            if due > self.tick {
+                break;
+            }
+            self.scheduled.pop_first();
+            self.update(world, pos);
+        }
+        std::mem::take(&mut self.changed)
+    }
+
+    fn set(&mut self, world: &mut World, pos: [i32; 3], voxel: Voxel) {
+        world.set_voxel(pos, voxel);
+        self.changed.push(pos);
+        self.block_changed(world, pos);
+    }
+
+    fn fluid_voxel(world: &World, kind: FluidKind, level: u8) -> Voxel {
+        let block_type = kind.block_type();
+        Voxel {
+            block_type,
+            texture_position: world.blocks.get(block_type).texture,
+            state: BlockState::default().with(Property::Level, level),
+        }
+    }
+
+    fn update(&mut self, world: &mut World, pos: [i32; 3]) {
+        let voxel = world.get_voxel(pos);
+        let Some(kind) = FluidKind::of(voxel.block_type) else {
+            return;
+        };
+        let rules = self.rules(kind);
+
+        // water cools lava into stone
+        if kind == FluidKind::Lava
+            && NEIGHBOURS
+                .iter()
+                .any(|&o| world.get_voxel(add(pos, o)).block_type == BlockType::Water)
+        {
+            let stone = world.blocks.default_voxel(BlockType::Stone);
+            self.set(world, pos, stone);
+            return;
+        }
+
+        let level = voxel.state.level();
+        if level != SOURCE {
+            match self.flowing_level(world, pos, kind, rules) {
+                None => {
+                    self.set(world, pos, Voxel::default());
+                    return;
+                }
+                Some(new) if new != level => {
+                    let voxel = FluidSimulator::fluid_voxel(world, kind, new);
+                    self.set(world, pos, voxel);
+                    return;
+                }
+                Some(_) => (),
+            }
+        }
+
+        self.spread(world, pos, kind, rules, level);
+    }
+
+    // the level a non source block should have given what's around it, None if it's
+    // no longer being fed
+    fn flowing_level(
+        &self,
+        world: &World,
+        pos: [i32; 3],
+        kind: FluidKind,
+        rules: FluidRules,
+    ) -> Option<u8> {
+        if FluidKind::of(world.get_voxel(add(pos, [0, 1, 0])).block_type) == Some(kind) {
+            return Some(FALLING);
+        }
+
+        let mut sources = 0;
+        let mut best: Option<u8> = None;
+        for offset in HORIZONTAL {
+            let neighbour = world.get_voxel(add(pos, offset));
+            if FluidKind::of(neighbour.block_type) != Some(kind) {
+                continue;
+            }
+            let level = neighbour.state.level();
+            if level == SOURCE {
+                sources += 1;
+            }
+            // falling fluid spreads as if it were a source
+            let effective = if level >= FALLING { 0 } else { level };
+            let fed = effective + rules.level_drop;
+            best = Some(best.map_or(fed, |b| b.min(fed)));
+        }
+
+        if rules.infinite_sources && sources >= 2 {
+            let below = world.get_voxel(add(pos, [0, -1, 0]));
+            let below_is_source =
+                FluidKind::of(below.block_type) == Some(kind) && below.state.level() == SOURCE;
+            if below.block_type.is_solid() || below_is_source {
+                return Some(SOURCE);
+            }
+        }
+
+        best.filter(|&level| level < FALLING)
+    }
+
+    fn can_flow_into(world: &World, pos: [i32; 3], kind: FluidKind, level: u8) -> bool {
+        let voxel = world.get_voxel(pos);
+        match FluidKind::of(voxel.block_type) {
+            Some(other) if other != kind => true,
+            Some(_) => {
+                let existing = voxel.state.level();
+                existing != SOURCE && existing < FALLING && existing > level
+            }
+            None => !voxel.block_type.is_solid(),
+        }
+    }
+
+    fn flow_into(&mut self, world: &mut World, pos: [i32; 3], kind: FluidKind, level: u8) {
+        let existing = world.get_voxel(pos);
+        let voxel = match FluidKind::of(existing.block_type) {
+            Some(other) if other != kind => world.blocks.default_voxel(BlockType::Stone),
+            _ => FluidSimulator::fluid_voxel(world, kind, level),
+        };
+        self.set(world, pos, voxel);
+    }
+
+    fn spread(
+        &mut self,
+        world: &mut World,
+        pos: [i32; 3],
+        kind: FluidKind,
+        rules: FluidRules,
+        level: u8,
+    ) {
+        let below = add(pos, [0, -1, 0]);
+        if FluidSimulator::can_flow_into(world, below, kind, FALLING) {
+            self.flow_into(world, below, kind, FALLING);
+            return;
+        }
+        // resting on more of the same fluid, nothing to spread over
+        if FluidKind::of(world.get_voxel(below).block_type) == Some(kind) && level != SOURCE {
+            return;
+        }
+
+        let next = if level >= FALLING {
+            rules.level_drop
+        } else {
+            level + rules.level_drop
+        };
+        if next >= FALLING {
+            return;
+        }
+
+        for offset in self.flow_directions(world, pos, kind, rules, next) {
+            self.flow_into(world, add(pos, offset), kind, next);
+        }
+    }
+
+    // sideways directions the fluid should go in. if there's a drop within reach only the
+    // directions with the shortest way to it are used, otherwise every open direction is
+    fn flow_directions(
+        &self,
+        world: &World,
+        pos: [i32; 3],
+        kind: FluidKind,
+        rules: FluidRules,
+        level: u8,
+    ) -> Vec<[i32; 3]> {
+        let open: Vec<[i32; 3]> = HORIZONTAL
+            .into_iter()
+            .filter(|&o| FluidSimulator::can_flow_into(world, add(pos, o), kind, level))
+            .collect();
+
+        let distances: Vec<Option<u32>> = open
+            .iter()
+            .map(|&o| {
+                FluidSimulator::distance_to_drop(world, add(pos, o), kind, rules.slope_distance)
+            })
+            .collect();
+
+        let Some(shortest) = distances.iter().flatten().min().copied() else {
+            return open;
+        };
+        open.into_iter()
+            .zip(distances)
+            .filter(|(_, d)| *d == Some(shortest))
+            .map(|(o, _)| o)
+            .collect()
+    }
+
+    fn distance_to_drop(world: &World, start: [i32; 3], kind: FluidKind, max: u32) -> Option<u32> {
+        let mut seen = HashSet::from([start]);
+        let mut queue = VecDeque::from([(start, 0)]);
+
+        while let Some((pos, distance)) = queue.pop_front() {
+            if FluidSimulator::can_flow_into(world, add(pos, [0, -1, 0]), kind, FALLING) {
+                return Some(distance);
+            }
+            if distance == max {
+                continue;
+            }
+            for offset in HORIZONTAL {
+                let next = add(pos, offset);
+                if !world.get_voxel(next).block_type.is_solid() && seen.insert(next) {
+                    queue.push_back((next, distance + 1));
+                }
+            }
+        }
+        None
+    }
+}
+
+// how high the top of a fluid block is drawn, taller the stronger the flow
+pub fn surface_height(world: &World, pos: [i32; 3]) -> f32 {
+    let voxel = world.get_voxel(pos);
+    let above = world.get_voxel(add(pos, [0, 1, 0]));
+    if FluidKind::of(above.block_type).is_some() {
+        return 1.0;
+    }
+    match voxel.state.level() {
+        level if level >= FALLING => 1.0,
+        level => 1.0 - (level as f32 + 1.0) / 9.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a stone floor from -size to size at y = 0
+    fn floor(size: i32) -> World {
+        let mut world = World::default();
+        for x in -size..=size {
+            for z in -size..=size {
+                world.set_voxel([x, 0, z], world.blocks.default_voxel(BlockType::Stone));
+            }
+        }
+        world
+    }
+
+    fn place(sim: &mut FluidSimulator, world: &mut World, pos: [i32; 3], kind: FluidKind) {
+        let voxel = FluidSimulator::fluid_voxel(world, kind, SOURCE);
+        world.set_voxel(pos, voxel);
+        sim.block_changed(world, pos);
+    }
+
+    fn run(sim: &mut FluidSimulator, world: &mut World, ticks: u32) {
+        for _ in 0..ticks {
+            sim.tick(world);
+        }
+    }
+
+    fn block(world: &World, pos: [i32; 3]) -> BlockType {
+        world.get_voxel(pos).block_type
+    }
+
+    #[test]
+    fn water_spreads_seven_blocks_on_flat_ground() {
+        let mut world = floor(12);
+        let mut sim = FluidSimulator::default();
+        place(&mut sim, &mut world, [0, 1, 0], FluidKind::Water);
+        run(&mut sim, &mut world, 200);
+
+        assert_eq!(block(&world, [7, 1, 0]), BlockType::Water);
+        assert_eq!(world.get_voxel([7, 1, 0]).state.level(), 7);
+        assert_eq!(block(&world, [8, 1, 0]), BlockType::Air);
+        assert_eq!(block(&world, [-7, 1, 0]), BlockType::Water);
+        assert_eq!(sim.pending(), 0);
+    }
+
+    #[test]
+    fn water_falls_off_ledges() {
+        let mut world = floor(6);
+        let mut sim = FluidSimulator::default();
+        let stone = world.blocks.default_voxel(BlockType::Stone);
+        world.set_voxel([0, 3, 0], stone);
+        place(&mut sim, &mut world, [0, 4, 0], FluidKind::Water);
+        run(&mut sim, &mut world, 200);
+
+        // spreads off the pillar then drops straight down to the floor
+        assert_eq!(world.get_voxel([1, 4, 0]).state.level(), 1);
+        assert_eq!(world.get_voxel([1, 3, 0]).state.level(), FALLING);
+        assert_eq!(world.get_voxel([1, 1, 0]).state.level(), FALLING);
+        assert_eq!(block(&world, [3, 1, 0]), BlockType::Water);
+    }
+
+    #[test]
+    fn flow_prefers_the_way_downhill() {
+        let mut world = floor(6);
+        let mut sim = FluidSimulator::default();
+        // a hole two blocks east of the source
+        world.set_voxel([2, 0, 0], Voxel::default());
+        place(&mut sim, &mut world, [0, 1, 0], FluidKind::Water);
+        run(&mut sim, &mut world, 8);
+
+        assert_eq!(block(&world, [1, 1, 0]), BlockType::Water);
+        assert_eq!(block(&world, [-1, 1, 0]), BlockType::Air);
+        assert_eq!(block(&world, [0, 1, 1]), BlockType::Air);
+    }
+
+    #[test]
+    fn removing_the_source_drains_the_flow() {
+        let mut world = floor(10);
+        let mut sim = FluidSimulator::default();
+        place(&mut sim, &mut world, [0, 1, 0], FluidKind::Water);
+        run(&mut sim, &mut world, 200);
+
+        world.set_voxel([0, 1, 0], Voxel::default());
+        sim.block_changed(&world, [0, 1, 0]);
+        run(&mut sim, &mut world, 400);
+
+        for x in -8..=8 {
+            assert_eq!(block(&world, [x, 1, 0]), BlockType::Air, "x = {x}");
+        }
+    }
+
+    #[test]
+    fn two_sources_make_a_third_only_when_infinite() {
+        for infinite in [true, false] {
+            let mut world = floor(6);
+            let mut water = FluidRules::water();
+            water.infinite_sources = infinite;
+            let mut sim = FluidSimulator::new(water, FluidRules::lava());
+            place(&mut sim, &mut world, [-1, 1, 0], FluidKind::Water);
+            place(&mut sim, &mut world, [1, 1, 0], FluidKind::Water);
+            run(&mut sim, &mut world, 200);
+
+            let level = world.get_voxel([0, 1, 0]).state.level();
+            assert_eq!(level == SOURCE, infinite);
+        }
+    }
+
+    #[test]
+    fn water_meeting_lava_makes_stone() {
+        let mut world = floor(6);
+        let mut sim = FluidSimulator::default();
+        place(&mut sim, &mut world, [3, 1, 0], FluidKind::Lava);
+        place(&mut sim, &mut world, [-1, 1, 0], FluidKind::Water);
+        run(&mut sim, &mut world, 400);
+
+        assert_eq!(block(&world, [3, 1, 0]), BlockType::Stone);
+        assert!(!(-6..=6).any(|x| block(&world, [x, 1, 0]) == BlockType::Lava));
+    }
+
+    #[test]
+    fn surface_height_follows_level() {
+        let mut world = World::default();
+        world.set_voxel(
+            [0, 0, 0],
+            FluidSimulator::fluid_voxel(&world, FluidKind::Water, SOURCE),
+        );
+        world.set_voxel(
+            [1, 0, 0],
+            FluidSimulator::fluid_voxel(&world, FluidKind::Water, 6),
+        );
+        assert!(surface_height(&world, [0, 0, 0]) > surface_height(&world, [1, 0, 0]));
+
+        world.set_voxel(
+            [1, 1, 0],
+            FluidSimulator::fluid_voxel(&world, FluidKind::Water, FALLING),
+        );
+        assert_eq!(surface_height(&world, [1, 0, 0]), 1.0);
+    }
+}
@@ -0,0 +1,84 @@
+use crate::world::fluid::FluidKind;
+use crate::world::{split_pos, World};
+
+pub const MAX_LIGHT: u8 = 15;
+// in ticks, so a day lasts twenty minutes
+pub const DAY_LENGTH: u64 = 24000;
+const DUSK: u64 = 12000;
+const NIGHT: u64 = 13800;
+const DAWN: u64 = 22200;
+// moonlight
+const NIGHT_LIGHT: u8 = 4;
+// how far light from lava reaches, losing one level per block
+const LAVA_REACH: i32 = 6;
+
+// how bright the sky is at `time` ticks into the game. the game starts in the morning
+pub fn daylight(time: u64) -> u8 {
+    let t = time % DAY_LENGTH;
+    let range = (MAX_LIGHT - NIGHT_LIGHT) as f32;
+    let level = if t < DUSK {
+        MAX_LIGHT as f32
+    } else if t < NIGHT {
+        MAX_LIGHT as f32 - range * (t - DUSK) as f32 / (NIGHT - DUSK) as f32
+    } else if t < DAWN {
+        NIGHT_LIGHT as f32
+    } else {
+        NIGHT_LIGHT as f32 + range * (t - DAWN) as f32 / (DAY_LENGTH - DAWN) as f32
+    };
+    level.round() as u8
+}
+
+// whether nothing solid is between this block and the sky. anything above the loaded
+// chunks is open sky
+pub fn sees_sky(world: &World, pos: [i32; 3]) -> bool {
+    let mut above = [pos[0], pos[1] + 1, pos[2]];
+    while world.chunks.contains_key(&split_pos(above).0) {
+        if world.get_voxel(above).block_type.is_solid() {
+            return false;
+        }
+        above[1] += 1;
+    }
+    true
+}
+
+// light given off by lava nearby
+fn block_light(world: &World, pos: [i32; 3]) -> u8 {
+    let mut light = 0;
+    for x in -LAVA_REACH..=LAVA_REACH {
+        for y in -LAVA_REACH..=LAVA_REACH {
+            for z in -LAVA_REACH..=LAVA_REACH {
+                let distance = x.abs() + y.abs() + z.abs();
+                if distance > LAVA_REACH {
+                    continue;
+                }
+                let at = [pos[0] + x, pos[1] + y, pos[2] + z];
+                if FluidKind::of(world.get_voxel(at).block_type) == Some(FluidKind::Lava) {
+                    light = light.max(MAX_LIGHT as i32 - 1 - distance);
+                }
+            }
+        }
+    }
+    light as u8
+}
+
+// a rough light level for the air block at `pos`, from 0 to MAX_LIGHT. there's no light
+// spreading yet, so sky light only reaches blocks straight under open sky
+pub fn light_level(world: &World, pos: [i32; 3], daylight: u8) -> u8 {
+    let sky = if sees_sky(world, pos) { daylight } else { 0 };
+    sky.max(block_light(world, pos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn days_are_bright_and_nights_dark() {
+        assert_eq!(daylight(0), MAX_LIGHT);
+        assert_eq!(daylight(6000), MAX_LIGHT);
+        assert_eq!(daylight(18000), NIGHT_LIGHT);
+        assert_eq!(daylight(DAY_LENGTH + 6000), MAX_LIGHT);
+        let dusk = daylight((DUSK + NIGHT) / 2);
+        assert!(dusk > NIGHT_LIGHT && dusk < MAX_LIGHT);
+    }
+}
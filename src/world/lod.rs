@@ -0,0 +1,396 @@
+use crate::support::camera::CameraState;
+use crate::world::block_model::BlockShape;
+use crate::world::chunk::CHUNK_SIZE;
+use crate::world::collision::Aabb;
+use crate::world::mesher::{self, mesh_chunk, PackedMesh, Shade, Tinted, FACES};
+use crate::world::{ChunkPos, World};
+
+// cell sizes in blocks, and how far from the camera a chunk's centre can be to use each.
+// anything further than the last uses COARSEST
+const LOD_RANGES: [(u32, f32); 3] = [(1, 64.0), (2, 128.0), (4, 192.0)];
+pub const COARSEST: u32 = 8;
+// how far past a boundary a chunk has to get before it changes level, so chunks sitting
+// on one don't keep flipping back and forth
+const HYSTERESIS: f32 = 8.0;
+// skirts hang down from the edges of a chunk's surface to cover the gaps where the
+// chunk next door is drawn at another level. they sit just inside the chunk so they
+// don't z-fight with real faces on its edge
+const SKIRT_DEPTH: i32 = COARSEST as i32;
+const SKIRT_INSET: f32 = 1.0 / 16.0;
+
+// the range of distances a level covers
+fn lod_range(lod: u32) -> (f32, f32) {
+    let mut near = 0.0;
+    for (level, far) in LOD_RANGES {
+        if level == lod {
+            return (near, far);
+        }
+        near = far;
+    }
+    (near, f32::INFINITY)
+}
+
+// the cell size for a chunk `distance` blocks from the camera. a chunk already drawn at
+// `current` keeps it until it's HYSTERESIS past the edge of that level's range
+pub fn choose_lod(distance: f32, current: Option<u32>) -> u32 {
+    if let Some(current) = current {
+        let (near, far) = lod_range(current);
+        if distance >= near - HYSTERESIS && distance < far + HYSTERESIS {
+            return current;
+        }
+    }
+    LOD_RANGES
+        .iter()
+        .find(|(_, far)| distance < *far)
+        .map_or(COARSEST, |(level, _)| *level)
+}
+
+// how far the camera is from the middle of a chunk
+pub fn chunk_distance(camera: &CameraState, chunk_pos: ChunkPos) -> f32 {
+    let (x, y, z) = camera.position();
+    let half = CHUNK_SIZE as f32 / 2.0;
+    let centre = chunk_pos.map(|c| (c * CHUNK_SIZE as i32) as f32 + half);
+    let d = [centre[0] - x, centre[1] - y, centre[2] - z];
+    (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt()
+}
+
+// whether a block counts towards filling a cell. plants and the like are too small to
+// see from far away, but water is kept so lakes don't turn into holes
+fn fills(world: &World, pos: [i32; 3]) -> bool {
+    matches!(
+        world.models.shape(&world.get_voxel(pos)),
+        BlockShape::Cube | BlockShape::Fluid
+    )
+}
+
+// a chunk cut into cubes `step` blocks wide, with a ring of cells from the chunks around
+// it so faces on the edge can see what's next to them
+struct Cells {
+    step: i32,
+    // cells across the chunk
+    size: i32,
+    // the tile each cell is drawn with and whether it's tinted, None for cells left empty
+    tiles: Vec<Option<([i8; 2], bool)>>,
+}
+
+impl Cells {
+    fn new(world: &World, chunk_pos: ChunkPos, step: i32) -> Cells {
+        let size = CHUNK_SIZE as i32 / step;
+        let origin = chunk_pos.map(|c| c * CHUNK_SIZE as i32);
+        let span = (size + 2) as usize;
+        let mut tiles = Vec::with_capacity(span * span * span);
+        for z in -1..=size {
+            for y in -1..=size {
+                for x in -1..=size {
+                    let corner = [
+                        origin[0] + x * step,
+                        origin[1] + y * step,
+                        origin[2] + z * step,
+                    ];
+                    tiles.push(cell_tile(world, corner, step));
+                }
+            }
+        }
+        Cells { step, size, tiles }
+    }
+
+    // `cell` counts from the chunk's corner, -1 and `size` being the ring outside it
+    fn get(&self, cell: [i32; 3]) -> Option<([i8; 2], bool)> {
+        let span = self.size + 2;
+        let [x, y, z] = cell.map(|c| c + 1);
+        self.tiles[(x + span * (y + span * z)) as usize]
+    }
+}
+
+// a cell is filled when at least half its blocks are, and takes the texture of the
+// highest one so grassy ground stays green from above
+fn cell_tile(world: &World, corner: [i32; 3], step: i32) -> Option<([i8; 2], bool)> {
+    let mut filled = 0;
+    let mut top = None;
+    // bottom up, so the last block found is the highest
+    for y in 0..step {
+        for z in 0..step {
+            for x in 0..step {
+                let pos = [corner[0] + x, corner[1] + y, corner[2] + z];
+                if fills(world, pos) {
+                    filled += 1;
+                    let voxel = world.get_voxel(pos);
+                    let tinted = world.blocks.get(voxel.block_type).tinted;
+                    top = Some((voxel.texture_position, tinted));
+                }
+            }
+        }
+    }
+    if filled * 2 >= step * step * step {
+        top
+    } else {
+        None
+    }
+}
+
+// sky light for a face, from the block just outside the middle of it
+fn face_light(world: &World, corner: [i32; 3], step: i32, axis: usize, positive: bool) -> u8 {
+    let mut outside = corner.map(|c| c + step / 2);
+    outside[axis] = if positive {
+        corner[axis] + step
+    } else {
+        corner[axis] - 1
+    };
+    mesher::sky(world, outside)
+}
+
+// the packed mesh for a chunk with blocks merged into cells `step` wide, 1, 2, 4 or 8.
+// detail 1 is the full mesh. every level gets skirts since any chunk can end up next to
+// one drawn at another level
+pub fn mesh_chunk_lod(world: &World, chunk_pos: ChunkPos, step: u32) -> PackedMesh {
+    if !world.chunks.contains_key(&chunk_pos) {
+        return PackedMesh::new(chunk_pos);
+    }
+    let step = step as i32;
+    let cells = Cells::new(world, chunk_pos, step);
+    let mut mesh = if step == 1 {
+        mesh_chunk(world, chunk_pos)
+    } else {
+        let mut mesh = PackedMesh::new(chunk_pos);
+        push_cells(&mut mesh, world, &cells);
+        mesh
+    };
+    push_skirts(&mut mesh, world, &cells);
+    mesh
+}
+
+fn push_cells(mesh: &mut PackedMesh, world: &World, cells: &Cells) {
+    let step = cells.step;
+    let cube = Aabb::new([0.0; 3], [step as f32; 3]);
+    for z in 0..cells.size {
+        for y in 0..cells.size {
+            for x in 0..cells.size {
+                let Some((tile, tinted)) = cells.get([x, y, z]) else {
+                    continue;
+                };
+                let corner = cell_corner(mesh, cells, [x, y, z]);
+                for (axis, positive) in FACES {
+                    let mut next = [x, y, z];
+                    next[axis] += if positive { 1 } else { -1 };
+                    if cells.get(next).is_some() {
+                        continue;
+                    }
+                    let shade = Shade {
+                        ao: 0,
+                        light: face_light(world, corner, step, axis, positive),
+                    };
+                    let mut sink = Tinted { sink: mesh, tinted };
+                    mesher::push_face(&mut sink, corner, &cube, axis, positive, tile, [shade; 4]);
+                }
+            }
+        }
+    }
+}
+
+fn cell_corner(mesh: &PackedMesh, cells: &Cells, cell: [i32; 3]) -> [i32; 3] {
+    [0, 1, 2].map(|a| mesh.origin[a] + cell[a] * cells.step)
+}
+
+// walls down from the top of the surface along each side of the chunk, and a lid over
+// ground that carries on just into the chunk above, which that chunk might not draw if
+// it's coarser
+fn push_skirts(mesh: &mut PackedMesh, world: &World, cells: &Cells) {
+    let step = cells.step;
+    let last = cells.size - 1;
+    push_lid(mesh, world, cells);
+    for (axis, positive) in FACES {
+        if axis == 1 {
+            continue;
+        }
+        let across = 2 - axis;
+        for a in 0..cells.size {
+            for y in 0..cells.size {
+                let mut cell = [0; 3];
+                cell[axis] = if positive { last } else { 0 };
+                cell[across] = a;
+                cell[1] = y;
+                let Some((tile, tinted)) = cells.get(cell) else {
+                    continue;
+                };
+                if cells.get([cell[0], y + 1, cell[2]]).is_some() {
+                    continue;
+                }
+
+                let corner = cell_corner(mesh, cells, cell);
+                // down to SKIRT_DEPTH below the surface, but not out of the chunk
+                let depth = SKIRT_DEPTH.min(y * step + step);
+                let mut min = [0.0; 3];
+                let mut max = [step as f32; 3];
+                min[1] = (step - depth) as f32;
+                min[axis] = SKIRT_INSET;
+                max[axis] = step as f32 - SKIRT_INSET;
+                let shade = Shade {
+                    ao: 0,
+                    light: face_light(world, corner, step, axis, positive),
+                };
+                mesher::push_face(
+                    &mut Tinted { sink: mesh, tinted },
+                    corner,
+                    &Aabb::new(min, max),
+                    axis,
+                    positive,
+                    tile,
+                    [shade; 4],
+                );
+            }
+        }
+    }
+}
+
+fn push_lid(mesh: &mut PackedMesh, world: &World, cells: &Cells) {
+    let step = cells.step;
+    let top = cells.size - 1;
+    for z in 0..cells.size {
+        for x in 0..cells.size {
+            let cell = [x, top, z];
+            let Some((tile, tinted)) = cells.get(cell) else {
+                continue;
+            };
+            // with nothing above, the cell's own top face is already drawn
+            if cells.get([x, top + 1, z]).is_none() {
+                continue;
+            }
+            // ground that goes on up further is drawn by the chunk above at any level
+            let corner = cell_corner(mesh, cells, cell);
+            let above = [corner[0] + step / 2, corner[1] + step, corner[2] + step / 2];
+            let surface_near =
+                (0..SKIRT_DEPTH).any(|dy| !fills(world, [above[0], above[1] + dy, above[2]]));
+            if !surface_near {
+                continue;
+            }
+
+            let mut max = [step as f32; 3];
+            max[1] -= SKIRT_INSET;
+            let shade = Shade {
+                ao: 0,
+                light: face_light(world, corner, step, 1, true),
+            };
+            let lid = Aabb::new([0.0; 3], max);
+            let mut sink = Tinted { sink: mesh, tinted };
+            mesher::push_face(&mut sink, corner, &lid, 1, true, tile, [shade; 4]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::support::packed::{face_index, POSITION_STEPS};
+    use crate::world::block_model::BlockModels;
+    use crate::world::registry::BlockRegistry;
+    use crate::world::voxel::{BlockType, Voxel};
+
+    // stone over chunk [0, 0, 0]'s columns from y = 0 up to and including `height`
+    fn ground(height: i32) -> World {
+        let mut world = World::new(BlockRegistry::default(), BlockModels::default());
+        for x in 0..CHUNK_SIZE as i32 {
+            for z in 0..CHUNK_SIZE as i32 {
+                for y in 0..=height {
+                    let voxel = Voxel {
+                        block_type: BlockType::Stone,
+                        ..Voxel::default()
+                    };
+                    world.set_voxel([x, y, z], voxel);
+                }
+            }
+        }
+        world
+    }
+
+    // the y of every face pointing up, in blocks from the chunk's corner
+    fn tops(mesh: &PackedMesh) -> Vec<f32> {
+        let up = face_index([0.0, 1.0, 0.0]);
+        let mut tops: Vec<f32> = mesh
+            .vertices
+            .iter()
+            .map(|v| v.unpack())
+            .filter(|v| v.face == up)
+            .map(|v| v.position[1] as f32 / POSITION_STEPS)
+            .collect();
+        tops.dedup();
+        tops
+    }
+
+    #[test]
+    fn picks_levels_by_distance() {
+        assert_eq!(choose_lod(10.0, None), 1);
+        assert_eq!(choose_lod(100.0, None), 2);
+        assert_eq!(choose_lod(150.0, None), 4);
+        assert_eq!(choose_lod(1000.0, None), COARSEST);
+        // just over a boundary keeps what it had, well over it changes
+        assert_eq!(choose_lod(66.0, Some(1)), 1);
+        assert_eq!(choose_lod(62.0, Some(2)), 2);
+        assert_eq!(choose_lod(80.0, Some(1)), 2);
+        assert_eq!(choose_lod(40.0, Some(4)), 1);
+    }
+
+    #[test]
+    fn coarser_levels_have_fewer_vertices() {
+        let world = ground(15);
+        let counts: Vec<usize> = [1, 2, 4, 8]
+            .map(|step| mesh_chunk_lod(&world, [0, 0, 0], step).vertices.len())
+            .to_vec();
+        assert!(
+            counts.windows(2).all(|pair| pair[1] < pair[0]),
+            "{counts:?}"
+        );
+        // the surface stays where it was at every level
+        for step in [2, 4, 8] {
+            assert_eq!(tops(&mesh_chunk_lod(&world, [0, 0, 0], step)), [16.0]);
+        }
+    }
+
+    #[test]
+    fn cells_fill_when_half_full() {
+        // 16 of the 32 rows are stone, so an 8 wide cell reaching y = 16 is half full and
+        // one reaching y = 24 is empty
+        let world = ground(15);
+        let cells = Cells::new(&world, [0, 0, 0], 8);
+        assert!(cells.get([0, 1, 0]).is_some());
+        assert!(cells.get([0, 2, 0]).is_none());
+        // outside the loaded chunk
+        assert!(cells.get([-1, 0, 0]).is_none());
+    }
+
+    #[test]
+    fn lids_cover_ground_just_into_the_chunk_above() {
+        let lid = |world: &World| {
+            tops(&mesh_chunk_lod(world, [0, 0, 0], 4)) == [CHUNK_SIZE as f32 - SKIRT_INSET]
+        };
+        // two blocks up into the next chunk, which at 4x would be drawn as nothing
+        assert!(lid(&ground(33)));
+        // deep enough that the chunk above draws it whatever its level
+        assert!(!lid(&ground(CHUNK_SIZE as i32 + SKIRT_DEPTH)));
+    }
+
+    #[test]
+    fn skirts_hang_from_every_side() {
+        let world = ground(15);
+        let mesh = mesh_chunk_lod(&world, [0, 0, 0], 4);
+        for (axis, positive) in [(0, false), (0, true), (2, false), (2, true)] {
+            let mut normal = [0.0; 3];
+            normal[axis] = if positive { 1.0 } else { -1.0 };
+            let face = face_index(normal);
+            let inset = (SKIRT_INSET * POSITION_STEPS) as u16;
+            let edge = if positive {
+                CHUNK_SIZE as u16 * POSITION_STEPS as u16 - inset
+            } else {
+                inset
+            };
+            // the ground goes past the chunk's edges in none of the directions, so the
+            // real faces are on the edge and the skirts are just inside
+            let skirts = mesh
+                .vertices
+                .iter()
+                .map(|v| v.unpack())
+                .filter(|v| v.face == face && v.position[axis] == edge)
+                .count();
+            assert_eq!(skirts, 4 * (CHUNK_SIZE / 4), "axis {axis} {positive}");
+        }
+    }
+}
@@ -0,0 +1,500 @@
+use crate::support::packed::{self, PackedVertex, VertexFields, AO_LEVELS};
+use crate::support::rendering_info::ChunkVertex;
+use crate::world::block_model::BlockShape;
+use crate::world::chunk::CHUNK_SIZE;
+use crate::world::collision::Aabb;
+use crate::world::fluid::{self, FluidKind};
+use crate::world::light::{sees_sky, MAX_LIGHT};
+use crate::world::{ChunkPos, World};
+
+// how far overlays sit out from the block so they don't z-fight with it
+const OVERLAY_OFFSET: f32 = 0.002;
+// no ambient occlusion, full light
+pub(super) const LIT: Shade = Shade {
+    ao: 0,
+    light: MAX_LIGHT,
+};
+
+#[derive(Copy, Clone)]
+pub(super) struct Shade {
+    // index into AO_LEVELS
+    pub ao: u8,
+    pub light: u8,
+}
+
+// one face as the mesher sees it, before it's turned into vertices of either kind
+pub(super) struct Quad {
+    // going round the quad's edge
+    corners: [[f32; 3]; 4],
+    normal: [f32; 3],
+    tile: [i8; 2],
+    // 0 to 1 across the tile at each corner
+    uv: [[f32; 2]; 4],
+    shade: [Shade; 4],
+    // coloured by the chunk's biome tint
+    tinted: bool,
+}
+
+impl Quad {
+    // indices into the corners for two triangles. the winding is fixed up so the face is
+    // front facing from the side `normal` points to
+    fn order(&self) -> [u32; 6] {
+        let c = self.corners;
+        let n = cross(sub(c[1], c[0]), sub(c[2], c[0]));
+        let normal = self.normal;
+        // the camera is left handed and culls clockwise, so front faces wind
+        // anticlockwise round the normal
+        if n[0] * normal[0] + n[1] * normal[1] + n[2] * normal[2] > 0.0 {
+            [0, 1, 2, 0, 2, 3]
+        } else {
+            [0, 2, 1, 0, 3, 2]
+        }
+    }
+}
+
+pub(super) trait QuadSink {
+    fn push_quad(&mut self, quad: Quad);
+}
+
+// passes quads on to another sink, marking them tinted if `tinted` is set. for grass and
+// leaves
+pub(super) struct Tinted<'a, S: QuadSink> {
+    pub sink: &'a mut S,
+    pub tinted: bool,
+}
+
+impl<S: QuadSink> QuadSink for Tinted<'_, S> {
+    fn push_quad(&mut self, mut quad: Quad) {
+        quad.tinted |= self.tinted;
+        self.sink.push_quad(quad);
+    }
+}
+
+// float vertices in world space, for things that move or are only drawn for a moment
+#[derive(Default)]
+pub struct ChunkMesh {
+    pub vertices: Vec<ChunkVertex>,
+    pub indices: Vec<u32>,
+}
+
+impl ChunkMesh {
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    // all six faces of a box already in world space, for things that aren't blocks
+    pub fn push_aabb(&mut self, b: &Aabb) {
+        for (axis, positive) in FACES {
+            push_face(self, [0, 0, 0], b, axis, positive, [0, 0], [LIT; 4]);
+        }
+    }
+}
+
+impl QuadSink for ChunkMesh {
+    fn push_quad(&mut self, quad: Quad) {
+        let start = self.vertices.len() as u32;
+        for i in 0..4 {
+            let shade = quad.shade[i];
+            self.vertices.push(ChunkVertex::new(
+                quad.corners[i],
+                quad.normal,
+                atlas_uv(quad.tile, quad.uv[i][0], quad.uv[i][1]),
+                [
+                    AO_LEVELS[shade.ao as usize],
+                    shade.light as f32 / MAX_LIGHT as f32,
+                ],
+            ));
+        }
+        self.indices.extend(quad.order().map(|i| start + i));
+    }
+}
+
+// a chunk's blocks packed two u32s a vertex, with positions relative to the chunk's
+// corner. the shader adds `origin` back on
+pub struct PackedMesh {
+    pub origin: [i32; 3],
+    pub vertices: Vec<PackedVertex>,
+    pub indices: Vec<u32>,
+}
+
+impl PackedMesh {
+    pub fn new(chunk_pos: ChunkPos) -> PackedMesh {
+        PackedMesh {
+            origin: chunk_pos.map(|c| c * CHUNK_SIZE as i32),
+            vertices: Vec::new(),
+            indices: Vec::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+}
+
+impl QuadSink for PackedMesh {
+    fn push_quad(&mut self, quad: Quad) {
+        let start = self.vertices.len() as u32;
+        let face = packed::face_index(quad.normal);
+        let tile = quad.tile.map(|t| t as u8);
+        for i in 0..4 {
+            let local = [0, 1, 2].map(|a| quad.corners[i][a] - self.origin[a] as f32);
+            let fields = VertexFields {
+                position: packed::quantise_position(local),
+                face,
+                tile,
+                uv: packed::quantise_uv(quad.uv[i]),
+                ao: quad.shade[i].ao,
+                light: quad.shade[i].light,
+                tinted: quad.tinted,
+            };
+            self.vertices.push(fields.pack());
+        }
+        self.indices.extend(quad.order().map(|i| start + i));
+    }
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+// the six face directions as (axis, positive side)
+pub(super) const FACES: [(usize, bool); 6] = [
+    (0, false),
+    (0, true),
+    (1, false),
+    (1, true),
+    (2, false),
+    (2, true),
+];
+
+// where `u` and `v`, 0 to 1 across a tile, land in the atlas. the atlas is uploaded
+// bottom row first, so rows count down from the top
+fn atlas_uv(tile: [i8; 2], u: f32, v: f32) -> [f32; 2] {
+    let tiles = packed::ATLAS_TILES as f32;
+    [
+        (tile[0] as f32 + u) / tiles,
+        1.0 - (tile[1] as f32 + 1.0 - v) / tiles,
+    ]
+}
+
+// the axes a face's texture runs along. sides keep up as up
+fn texture_axes(axis: usize) -> (usize, usize) {
+    match axis {
+        0 => (2, 1),
+        1 => (0, 2),
+        _ => (0, 1),
+    }
+}
+
+// the edges of the face run along `u` and `v`, corners go round from (min, min)
+fn face_axes(axis: usize) -> (usize, usize) {
+    ((axis + 1) % 3, (axis + 2) % 3)
+}
+
+pub(super) fn push_face(
+    mesh: &mut impl QuadSink,
+    block: [i32; 3],
+    b: &Aabb,
+    axis: usize,
+    positive: bool,
+    tile: [i8; 2],
+    shade: [Shade; 4],
+) {
+    let origin = [block[0] as f32, block[1] as f32, block[2] as f32];
+    let (u, v) = face_axes(axis);
+    let fixed = if positive { b.max[axis] } else { b.min[axis] };
+    let corner = |cu: f32, cv: f32| {
+        let mut p = [0.0; 3];
+        p[axis] = fixed + origin[axis];
+        p[u] = cu + origin[u];
+        p[v] = cv + origin[v];
+        p
+    };
+    let corners = [
+        corner(b.min[u], b.min[v]),
+        corner(b.max[u], b.min[v]),
+        corner(b.max[u], b.max[v]),
+        corner(b.min[u], b.max[v]),
+    ];
+
+    // the face's position within the block picks the spot on the tile, so partial boxes
+    // show part of the texture rather than squashing all of it
+    let (tu, tv) = texture_axes(axis);
+    let uv = corners.map(|p| {
+        let along = |a: usize| (p[a] - origin[a]).clamp(0.0, 1.0);
+        [along(tu), along(tv)]
+    });
+
+    let mut normal = [0.0; 3];
+    normal[axis] = if positive { 1.0 } else { -1.0 };
+    mesh.push_quad(Quad {
+        corners,
+        normal,
+        tile,
+        uv,
+        shade,
+        tinted: false,
+    });
+}
+
+pub(super) fn neighbour(block: [i32; 3], axis: usize, positive: bool) -> [i32; 3] {
+    let mut neighbour = block;
+    neighbour[axis] += if positive { 1 } else { -1 };
+    neighbour
+}
+
+pub(super) fn is_opaque(world: &World, pos: [i32; 3]) -> bool {
+    world.models.shape(&world.get_voxel(pos)).is_full_cube()
+}
+
+// ambient occlusion for each corner of a face, in the same order `push_face` goes round
+// them, from the blocks touching each corner on the open side of the face
+fn face_ao(world: &World, block: [i32; 3], axis: usize, positive: bool) -> [u8; 4] {
+    let outside = neighbour(block, axis, positive);
+    let (u, v) = face_axes(axis);
+    [(-1, -1), (1, -1), (1, 1), (-1, 1)].map(|(du, dv)| {
+        let mut side_u = outside;
+        side_u[u] += du;
+        let mut side_v = outside;
+        side_v[v] += dv;
+        let mut diagonal = side_u;
+        diagonal[v] += dv;
+
+        let a = is_opaque(world, side_u);
+        let b = is_opaque(world, side_v);
+        if a && b {
+            3
+        } else {
+            a as u8 + b as u8 + is_opaque(world, diagonal) as u8
+        }
+    })
+}
+
+// full sky light under open sky, none otherwise. there's no light spreading yet
+pub(super) fn sky(world: &World, pos: [i32; 3]) -> u8 {
+    if sees_sky(world, pos) {
+        MAX_LIGHT
+    } else {
+        0
+    }
+}
+
+fn push_box(mesh: &mut impl QuadSink, world: &World, block: [i32; 3], b: &Aabb, tile: [i8; 2]) {
+    for (axis, positive) in FACES {
+        // faces flush with the block edge are hidden by a full cube next door
+        let on_edge = if positive {
+            b.max[axis] >= 1.0
+        } else {
+            b.min[axis] <= 0.0
+        };
+        let outside = neighbour(block, axis, positive);
+        if on_edge && is_opaque(world, outside) {
+            continue;
+        }
+        let light = sky(world, if on_edge { outside } else { block });
+        let ao = if on_edge {
+            face_ao(world, block, axis, positive)
+        } else {
+            [0; 4]
+        };
+        push_face(
+            mesh,
+            block,
+            b,
+            axis,
+            positive,
+            tile,
+            ao.map(|ao| Shade { ao, light }),
+        );
+    }
+}
+
+fn push_fluid(mesh: &mut impl QuadSink, world: &World, block: [i32; 3], tile: [i8; 2]) {
+    let kind = FluidKind::of(world.get_voxel(block).block_type);
+    let height = fluid::surface_height(world, block);
+    let b = Aabb::new([0.0, 0.0, 0.0], [1.0, height, 1.0]);
+    let shade = [Shade {
+        ao: 0,
+        light: sky(world, block),
+    }; 4];
+
+    for (axis, positive) in FACES {
+        let voxel = world.get_voxel(neighbour(block, axis, positive));
+        if FluidKind::of(voxel.block_type) == kind {
+            continue;
+        }
+        // a lowered surface can still be seen under a cube sitting on top of it
+        let lowered_top = axis == 1 && positive && height < 1.0;
+        if world.models.shape(&voxel).is_full_cube() && !lowered_top {
+            continue;
+        }
+        push_face(mesh, block, &b, axis, positive, tile, shade);
+    }
+}
+
+fn push_cross(mesh: &mut impl QuadSink, world: &World, block: [i32; 3], tile: [i8; 2]) {
+    let [x, y, z] = [block[0] as f32, block[1] as f32, block[2] as f32];
+    // pulled in a little so the plant doesn't poke into the next block's corners
+    let (lo, hi) = (0.15, 0.85);
+    let diagonals = [
+        ([x + lo, z + lo], [x + hi, z + hi]),
+        ([x + hi, z + lo], [x + lo, z + hi]),
+    ];
+    let uv = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]];
+    let shade = [Shade {
+        ao: 0,
+        light: sky(world, block),
+    }; 4];
+
+    for (a, b) in diagonals {
+        let corners = [
+            [a[0], y, a[1]],
+            [b[0], y, b[1]],
+            [b[0], y + 1.0, b[1]],
+            [a[0], y + 1.0, a[1]],
+        ];
+        let n = cross(sub(corners[1], corners[0]), [0.0, 1.0, 0.0]);
+        let len = (n[0] * n[0] + n[2] * n[2]).sqrt();
+        let normal = [n[0] / len, 0.0, n[2] / len];
+        // both sides are visible
+        for normal in [normal, [-normal[0], 0.0, -normal[2]]] {
+            mesh.push_quad(Quad {
+                corners,
+                normal,
+                tile,
+                uv,
+                shade,
+                tinted: false,
+            });
+        }
+    }
+}
+
+// every face of a block's boxes textured with one atlas tile, for drawing things like the
+// crack texture on top of it
+pub fn overlay_mesh(world: &World, block: [i32; 3], tile: [i8; 2]) -> ChunkMesh {
+    let mut mesh = ChunkMesh::default();
+    for b in world.models.boxes(&world.get_voxel(block)) {
+        let grown = Aabb::new(
+            b.min.map(|v| v - OVERLAY_OFFSET),
+            b.max.map(|v| v + OVERLAY_OFFSET),
+        );
+        for (axis, positive) in FACES {
+            push_face(&mut mesh, block, &grown, axis, positive, tile, [LIT; 4]);
+        }
+    }
+    mesh
+}
+
+// builds the packed mesh for one chunk
+pub fn mesh_chunk(world: &World, chunk_pos: ChunkPos) -> PackedMesh {
+    let mut mesh = PackedMesh::new(chunk_pos);
+    let Some(chunk) = world.chunks.get(&chunk_pos) else {
+        return mesh;
+    };
+
+    let size = CHUNK_SIZE as i32;
+    for y in 0..CHUNK_SIZE {
+        for z in 0..CHUNK_SIZE {
+            for x in 0..CHUNK_SIZE {
+                let voxel = chunk.get(x, y, z);
+                let block = [
+                    chunk_pos[0] * size + x as i32,
+                    chunk_pos[1] * size + y as i32,
+                    chunk_pos[2] * size + z as i32,
+                ];
+
+                let tile = voxel.texture_position;
+                let mut sink = Tinted {
+                    sink: &mut mesh,
+                    tinted: world.blocks.get(voxel.block_type).tinted,
+                };
+                match world.models.shape(&voxel) {
+                    BlockShape::Empty => (),
+                    BlockShape::Cross => push_cross(&mut sink, world, block, tile),
+                    BlockShape::Fluid => push_fluid(&mut sink, world, block, tile),
+                    _ => {
+                        for b in world.models.boxes(&voxel) {
+                            push_box(&mut sink, world, block, &b, tile);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    mesh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::support::camera::CameraState;
+    use crate::world::block_model::BlockModels;
+    use crate::world::registry::BlockRegistry;
+    use crate::world::voxel::{BlockType, Voxel};
+
+    // where a point lands on screen, -1 to 1 with y up
+    fn project(camera: &CameraState, p: [f32; 3]) -> [f32; 2] {
+        let transform = |m: [[f32; 4]; 4], v: [f32; 4]| {
+            [0, 1, 2, 3].map(|row| (0..4).map(|col| m[col][row] * v[col]).sum::<f32>())
+        };
+        let view = transform(camera.get_view(), [p[0], p[1], p[2], 1.0]);
+        let clip = transform(camera.get_perspective(), view);
+        [clip[0] / clip[3], clip[1] / clip[3]]
+    }
+
+    // the renderer culls clockwise triangles, so the faces of a box the camera can see
+    // have to go anticlockwise on screen and the ones facing away clockwise
+    #[test]
+    fn faces_towards_the_camera_wind_anticlockwise() {
+        let mut camera = CameraState::new();
+        camera.set_position((0.5, 0.5, 3.0));
+        camera.set_direction((0.0, 0.0, -1.0));
+        let mut mesh = ChunkMesh::default();
+        mesh.push_aabb(&Aabb::new([0.0; 3], [1.0; 3]));
+
+        let mut checked = 0;
+        for triangle in mesh.indices.chunks(3) {
+            let vertices = [0, 1, 2].map(|i| mesh.vertices[triangle[i] as usize]);
+            let facing = vertices[0].normal[2];
+            // the sides are edge on, so only the front and back say anything
+            if facing == 0 {
+                continue;
+            }
+            let [a, b, c] = vertices.map(|v| project(&camera, v.position));
+            let area = (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]);
+            assert_eq!(area > 0.0, facing > 0, "normal z {facing}");
+            checked += 1;
+        }
+        assert_eq!(checked, 4);
+    }
+
+    #[test]
+    fn only_tinted_blocks_are_tinted() {
+        let blocks = BlockRegistry::load("assets/data/blocks.ron").unwrap();
+        let mut world = World::new(blocks, BlockModels::default());
+        let block = |block_type| Voxel {
+            block_type,
+            ..Voxel::default()
+        };
+        world.set_voxel([0, 0, 0], block(BlockType::Grass));
+        world.set_voxel([4, 0, 0], block(BlockType::Stone));
+
+        let mesh = mesh_chunk(&world, [0, 0, 0]);
+        assert!(!mesh.vertices.is_empty());
+        for vertex in mesh.vertices {
+            let fields = vertex.unpack();
+            let grass = fields.position[0] < 4 * packed::POSITION_STEPS as u16;
+            assert_eq!(fields.tinted, grass);
+        }
+    }
+}
@@ -1 +1,71 @@
-pub mod voxel;
\ No newline at end of file
+pub mod block_model;
+pub mod block_state;
+pub mod chunk;
+pub mod collision;
+pub mod fluid;
+pub mod light;
+pub mod lod;
+pub mod mesher;
+pub mod raycast;
+pub mod registry;
+pub mod visibility;
+pub mod voxel;
+
+use std::collections::HashMap;
+
+use block_model::BlockModels;
+use chunk::{Chunk, CHUNK_SIZE};
+use registry::BlockRegistry;
+use voxel::Voxel;
+
+pub type ChunkPos = [i32; 3];
+
+// splits a world block position into the chunk it's in and the position inside that chunk
+pub fn split_pos(pos: [i32; 3]) -> (ChunkPos, [usize; 3]) {
+    let size = CHUNK_SIZE as i32;
+    let chunk = [
+        pos[0].div_euclid(size),
+        pos[1].div_euclid(size),
+        pos[2].div_euclid(size),
+    ];
+    let local = [
+        pos[0].rem_euclid(size) as usize,
+        pos[1].rem_euclid(size) as usize,
+        pos[2].rem_euclid(size) as usize,
+    ];
+    (chunk, local)
+}
+
+#[derive(Default)]
+pub struct World {
+    pub chunks: HashMap<ChunkPos, Chunk>,
+    pub blocks: BlockRegistry,
+    pub models: BlockModels,
+}
+
+impl World {
+    pub fn new(blocks: BlockRegistry, models: BlockModels) -> World {
+        World {
+            chunks: HashMap::new(),
+            blocks,
+            models,
+        }
+    }
+
+    // anything outside of the loaded chunks reads as air
+    pub fn get_voxel(&self, pos: [i32; 3]) -> Voxel {
+        let (chunk, local) = split_pos(pos);
+        match self.chunks.get(&chunk) {
+            Some(chunk) => chunk.get(local[0], local[1], local[2]),
+            None => Voxel::default(),
+        }
+    }
+
+    pub fn set_voxel(&mut self, pos: [i32; 3], voxel: Voxel) {
+        let (chunk, local) = split_pos(pos);
+        self.chunks
+            .entry(chunk)
+            .or_default()
+            .set(local[0], local[1], local[2], voxel);
+    }
+}
@@ -0,0 +1,72 @@
+use crate::world::fluid::FluidKind;
+use crate::world::World;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RayHit {
+    pub block: [i32; 3],
+    // the empty block the ray came through just before the hit, where placing goes
+    pub previous: [i32; 3],
+}
+
+// steps block by block along the ray (amanatides and woo) until it finds something
+// that isn't air or fluid
+pub fn raycast(
+    world: &World,
+    origin: [f32; 3],
+    direction: [f32; 3],
+    max_distance: f32,
+) -> Option<RayHit> {
+    let len =
+        (direction[0] * direction[0] + direction[1] * direction[1] + direction[2] * direction[2])
+            .sqrt();
+    if len == 0.0 {
+        return None;
+    }
+    let dir = [direction[0] / len, direction[1] / len, direction[2] / len];
+
+    let mut block = [
+        origin[0].floor() as i32,
+        origin[1].floor() as i32,
+        origin[2].floor() as i32,
+    ];
+    let mut step = [0; 3];
+    let mut next = [f32::INFINITY; 3];
+    let mut delta = [f32::INFINITY; 3];
+    for axis in 0..3 {
+        if dir[axis] > 0.0 {
+            step[axis] = 1;
+            delta[axis] = 1.0 / dir[axis];
+            next[axis] = (block[axis] as f32 + 1.0 - origin[axis]) * delta[axis];
+        } else if dir[axis] < 0.0 {
+            step[axis] = -1;
+            delta[axis] = -1.0 / dir[axis];
+            next[axis] = (origin[axis] - block[axis] as f32) * delta[axis];
+        }
+    }
+
+    let mut previous = block;
+    let mut travelled = 0.0;
+    while travelled <= max_distance {
+        let voxel = world.get_voxel(block);
+        if !voxel.block_type.is_air() && FluidKind::of(voxel.block_type).is_none() {
+            return Some(RayHit { block, previous });
+        }
+
+        let axis = if next[0] < next[1] {
+            if next[0] < next[2] {
+                0
+            } else {
+                2
+            }
+        } else if next[1] < next[2] {
+            1
+        } else {
+            2
+        };
+        previous = block;
+        block[axis] += step[axis];
+        travelled = next[axis];
+        next[axis] += delta[axis];
+    }
+    None
+}
@@ -0,0 +1,132 @@
+use std::{collections::HashMap, path::Path};
+
+use serde::Deserialize;
+
+use crate::item::tool::{ToolTier, ToolType};
+use crate::support::data::{load_ron, DataError};
+use crate::world::block_state::{BlockState, Property};
+use crate::world::voxel::{BlockType, Voxel};
+
+fn one() -> (u32, u32) {
+    (1, 1)
+}
+
+fn always() -> f32 {
+    1.0
+}
+
+fn unit_hardness() -> f32 {
+    1.0
+}
+
+// one line of a block's loot table. `item` is a name from items.ron
+#[derive(Clone, Debug, Deserialize)]
+pub struct DropEntry {
+    pub item: String,
+    // inclusive range
+    #[serde(default = "one")]
+    pub count: (u32, u32),
+    #[serde(default = "always")]
+    pub chance: f32,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct BlockDef {
+    pub texture: [i8; 2],
+    // every property the block has and the value it's placed with
+    #[serde(default)]
+    pub properties: HashMap<Property, u8>,
+    // left out, a block drops the item that places it
+    #[serde(default)]
+    pub drops: Option<Vec<DropEntry>>,
+    // how long the block takes to break, negative for unbreakable
+    #[serde(default = "unit_hardness")]
+    pub hardness: f32,
+    // the tool that breaks it fastest
+    #[serde(default)]
+    pub tool: Option<ToolType>,
+    // the lowest tier of `tool` that gets drops from it, anything does if left out
+    #[serde(default)]
+    pub tier: Option<ToolTier>,
+    // coloured by the biome it's in, for grass and leaves
+    #[serde(default)]
+    pub tinted: bool,
+}
+
+// by hand so a block built in code is as hard as one left out of the data file
+impl Default for BlockDef {
+    fn default() -> BlockDef {
+        BlockDef {
+            texture: [0, 0],
+            properties: HashMap::new(),
+            drops: None,
+            hardness: unit_hardness(),
+            tool: None,
+            tier: None,
+            tinted: false,
+        }
+    }
+}
+
+impl BlockDef {
+    pub fn default_state(&self) -> BlockState {
+        self.properties
+            .iter()
+            .fold(BlockState::default(), |state, (&p, &value)| state.with(p, value))
+    }
+}
+
+#[derive(Default)]
+pub struct BlockRegistry {
+    defs: HashMap<BlockType, BlockDef>,
+    // handed out for blocks the data file doesn't mention
+    missing: BlockDef,
+}
+
+impl BlockRegistry {
+    pub fn load(path: impl AsRef<Path>) -> Result<BlockRegistry, DataError> {
+        Ok(BlockRegistry {
+            defs: load_ron(path)?,
+            missing: BlockDef::default(),
+        })
+    }
+
+    pub fn register(&mut self, block_type: BlockType, def: BlockDef) {
+        self.defs.insert(block_type, def);
+    }
+
+    pub fn get(&self, block_type: BlockType) -> &BlockDef {
+        self.defs.get(&block_type).unwrap_or(&self.missing)
+    }
+
+    pub fn has_property(&self, block_type: BlockType, property: Property) -> bool {
+        self.get(block_type).properties.contains_key(&property)
+    }
+
+    // a freshly placed block with its texture and default state filled in
+    pub fn default_voxel(&self, block_type: BlockType) -> Voxel {
+        let def = self.get(block_type);
+        Voxel {
+            block_type,
+            texture_position: def.texture,
+            state: def.default_state(),
+        }
+    }
+
+    // None if the block doesn't have that property
+    pub fn set_property(&self, voxel: Voxel, property: Property, value: u8) -> Option<Voxel> {
+        if !self.has_property(voxel.block_type, property) {
+            return None;
+        }
+        Some(Voxel {
+            state: voxel.state.with(property, value),
+            ..voxel
+        })
+    }
+
+    // true if the voxel only uses properties its block declares
+    pub fn is_valid(&self, voxel: &Voxel) -> bool {
+        let properties: Vec<Property> = self.get(voxel.block_type).properties.keys().copied().collect();
+        voxel.state.uses_only(&properties)
+    }
+}
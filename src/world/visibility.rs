@@ -0,0 +1,322 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::world::chunk::{Chunk, CHUNK_SIZE, CHUNK_VOLUME};
+use crate::world::{ChunkPos, World};
+
+// faces of a chunk are numbered like the mesher's: axis * 2, plus one on the positive side.
+// the opposite face is then `face ^ 1`
+pub const CHUNK_FACES: usize = 6;
+// from the centre of a chunk to a corner
+const CHUNK_RADIUS: f32 = CHUNK_SIZE as f32 * 0.866;
+
+fn face(axis: usize, positive: bool) -> usize {
+    axis * 2 + positive as usize
+}
+
+fn step(pos: ChunkPos, face: usize) -> ChunkPos {
+    let mut next = pos;
+    next[face / 2] += if face % 2 == 1 { 1 } else { -1 };
+    next
+}
+
+// which faces of a chunk can see each other through the chunk, one bit for each pair
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Visibility(u64);
+
+impl Visibility {
+    pub const CLOSED: Visibility = Visibility(0);
+    pub const OPEN: Visibility = Visibility((1 << (CHUNK_FACES * CHUNK_FACES)) - 1);
+
+    pub fn connects(self, a: usize, b: usize) -> bool {
+        self.0 & (1 << (a * CHUNK_FACES + b)) != 0
+    }
+
+    fn connect(&mut self, a: usize, b: usize) {
+        self.0 |= 1 << (a * CHUNK_FACES + b);
+        self.0 |= 1 << (b * CHUNK_FACES + a);
+    }
+
+    // flood fills the chunk's see-through blocks. every pair of faces one filled region
+    // touches can see each other
+    pub fn compute(world: &World, chunk: &Chunk) -> Visibility {
+        let size = CHUNK_SIZE;
+        let index = |p: [usize; 3]| p[0] + size * (p[1] + size * p[2]);
+        let mut blocked = vec![false; CHUNK_VOLUME];
+        for z in 0..size {
+            for y in 0..size {
+                for x in 0..size {
+                    let voxel = chunk.get(x, y, z);
+                    blocked[index([x, y, z])] = world.models.shape(&voxel).is_full_cube();
+                }
+            }
+        }
+
+        let mut visibility = Visibility::CLOSED;
+        let mut seen = blocked.clone();
+        let mut stack = Vec::new();
+        // a region that doesn't reach the edge can't connect anything, so filling from
+        // the edges is enough
+        for start in edge_blocks() {
+            if seen[index(start)] {
+                continue;
+            }
+            seen[index(start)] = true;
+            stack.push(start);
+            let mut touched = [false; CHUNK_FACES];
+            while let Some(p) = stack.pop() {
+                for axis in 0..3 {
+                    for positive in [false, true] {
+                        let mut next = p;
+                        if positive {
+                            if p[axis] == size - 1 {
+                                touched[face(axis, true)] = true;
+                                continue;
+                            }
+                            next[axis] += 1;
+                        } else {
+                            if p[axis] == 0 {
+                                touched[face(axis, false)] = true;
+                                continue;
+                            }
+                            next[axis] -= 1;
+                        }
+                        if !seen[index(next)] {
+                            seen[index(next)] = true;
+                            stack.push(next);
+                        }
+                    }
+                }
+            }
+            for a in 0..CHUNK_FACES {
+                for b in 0..CHUNK_FACES {
+                    if touched[a] && touched[b] {
+                        visibility.connect(a, b);
+                    }
+                }
+            }
+        }
+        visibility
+    }
+}
+
+// every block on the outside of a chunk
+fn edge_blocks() -> impl Iterator<Item = [usize; 3]> {
+    let last = CHUNK_SIZE - 1;
+    (0..CHUNK_SIZE).flat_map(move |z| {
+        (0..CHUNK_SIZE).flat_map(move |y| {
+            (0..CHUNK_SIZE)
+                .map(move |x| [x, y, z])
+                .filter(move |p| p.iter().any(|&c| c == 0 || c == last))
+        })
+    })
+}
+
+// the chunks that might be seen from `eye`, found by walking out from the chunk it's in.
+// a chunk is only walked through between faces that can see each other, never back
+// towards the camera, and never behind it. chunks `visibility` knows nothing about are
+// the edge of the world
+pub fn visible_chunks(
+    eye: [f32; 3],
+    look: [f32; 3],
+    visibility: impl Fn(ChunkPos) -> Option<Visibility>,
+) -> HashSet<ChunkPos> {
+    let size = CHUNK_SIZE as f32;
+    let start = eye.map(|e| (e / size).floor() as i32);
+    let length = (look[0] * look[0] + look[1] * look[1] + look[2] * look[2]).sqrt();
+    let look = look.map(|l| l / length);
+    let behind = |pos: ChunkPos| {
+        let centre = pos.map(|c| (c as f32 + 0.5) * size);
+        let d = [0, 1, 2].map(|a| centre[a] - eye[a]);
+        d[0] * look[0] + d[1] * look[1] + d[2] * look[2] < -CHUNK_RADIUS
+    };
+
+    let mut visible = HashSet::from([start]);
+    // a chunk can be walked through again when it's entered by a different face, since
+    // that can lead out of faces the first way in couldn't
+    let mut entered_by = HashSet::new();
+    // each chunk with the face it was entered through and every direction taken to get
+    // there, as a bit per face
+    let mut queue = VecDeque::new();
+    for out in 0..CHUNK_FACES {
+        queue.push_back((step(start, out), out ^ 1, 1u8 << out));
+    }
+    while let Some((pos, entered, directions)) = queue.pop_front() {
+        let Some(chunk) = visibility(pos) else {
+            continue;
+        };
+        if pos == start || behind(pos) || !entered_by.insert((pos, entered)) {
+            continue;
+        }
+        visible.insert(pos);
+        for out in 0..CHUNK_FACES {
+            // turning back the way we came can't find anything new
+            if directions & (1 << (out ^ 1)) != 0 || !chunk.connects(entered, out) {
+                continue;
+            }
+            let next = step(pos, out);
+            if !entered_by.contains(&(next, out ^ 1)) {
+                queue.push_back((next, out ^ 1, directions | 1 << out));
+            }
+        }
+    }
+    visible
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use crate::world::block_model::BlockModels;
+    use crate::world::registry::BlockRegistry;
+    use crate::world::voxel::{BlockType, Voxel};
+
+    fn voxel(block_type: BlockType) -> Voxel {
+        Voxel {
+            block_type,
+            ..Voxel::default()
+        }
+    }
+
+    fn world() -> World {
+        World::new(BlockRegistry::default(), BlockModels::default())
+    }
+
+    fn filled(block_type: BlockType) -> Chunk {
+        let mut chunk = Chunk::new();
+        for z in 0..CHUNK_SIZE {
+            for y in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    chunk.set(x, y, z, voxel(block_type));
+                }
+            }
+        }
+        chunk
+    }
+
+    fn pairs(visibility: Visibility) -> usize {
+        (0..CHUNK_FACES)
+            .flat_map(|a| (0..CHUNK_FACES).map(move |b| (a, b)))
+            .filter(|&(a, b)| visibility.connects(a, b))
+            .count()
+    }
+
+    #[test]
+    fn empty_chunks_see_through_and_solid_ones_dont() {
+        let world = world();
+        assert_eq!(Visibility::compute(&world, &Chunk::new()), Visibility::OPEN);
+        assert_eq!(
+            Visibility::compute(&world, &filled(BlockType::Stone)),
+            Visibility::CLOSED
+        );
+    }
+
+    #[test]
+    fn walls_split_a_chunk() {
+        let world = world();
+        let mut chunk = Chunk::new();
+        for z in 0..CHUNK_SIZE {
+            for y in 0..CHUNK_SIZE {
+                chunk.set(16, y, z, voxel(BlockType::Stone));
+            }
+        }
+        let visibility = Visibility::compute(&world, &chunk);
+        let (west, east, up, north) = (face(0, false), face(0, true), face(1, true), face(2, true));
+        assert!(!visibility.connects(west, east));
+        assert!(visibility.connects(west, up));
+        assert!(visibility.connects(east, north));
+        // both halves touch the top, bottom, and both z sides
+        assert!(visibility.connects(up, north));
+    }
+
+    #[test]
+    fn tunnels_connect_only_their_ends() {
+        let world = world();
+        let mut chunk = filled(BlockType::Stone);
+        for x in 0..CHUNK_SIZE {
+            chunk.set(x, 5, 9, voxel(BlockType::Air));
+        }
+        // a pocket of air that doesn't reach any face
+        chunk.set(20, 20, 20, voxel(BlockType::Air));
+        let visibility = Visibility::compute(&world, &chunk);
+        let (west, east) = (face(0, false), face(0, true));
+        assert!(visibility.connects(west, east));
+        assert!(visibility.connects(east, west));
+        // a face always sees itself through an opening in it
+        assert!(visibility.connects(west, west));
+        assert_eq!(pairs(visibility), 4);
+        // plants don't block, only full cubes do
+        let plant = voxel(BlockType::TallGrass);
+        let mut chunk = filled(BlockType::Stone);
+        for y in 0..CHUNK_SIZE {
+            chunk.set(3, y, 3, plant);
+        }
+        let visibility = Visibility::compute(&world, &chunk);
+        assert!(visibility.connects(face(1, false), face(1, true)));
+    }
+
+    // a row of chunks along x with the camera in the first one
+    fn row(graph: &HashMap<ChunkPos, Visibility>, look: [f32; 3]) -> HashSet<ChunkPos> {
+        visible_chunks([16.0, 16.0, 16.0], look, |pos| graph.get(&pos).copied())
+    }
+
+    #[test]
+    fn walks_out_through_open_chunks() {
+        let mut graph = HashMap::new();
+        for x in -3..=3 {
+            graph.insert([x, 0, 0], Visibility::OPEN);
+        }
+        let ahead = row(&graph, [1.0, 0.0, 0.0]);
+        assert!(ahead.contains(&[3, 0, 0]));
+        // wholly behind the camera
+        assert!(!ahead.contains(&[-1, 0, 0]));
+        assert!(row(&graph, [-1.0, 0.0, 0.0]).contains(&[-3, 0, 0]));
+
+        // a solid chunk hides everything past it, but is drawn itself
+        graph.insert([2, 0, 0], Visibility::CLOSED);
+        let blocked = row(&graph, [1.0, 0.0, 0.0]);
+        assert!(blocked.contains(&[2, 0, 0]));
+        assert!(!blocked.contains(&[3, 0, 0]));
+    }
+
+    #[test]
+    fn cant_see_round_corners_a_chunk_doesnt_connect() {
+        // an L of chunks: along x, then up. the corner chunk lets x in but only out the
+        // far side, so the chunk above it can't be seen
+        let (west, east) = (face(0, false), face(0, true));
+        let mut straight = Visibility::CLOSED;
+        straight.connect(west, east);
+        let mut graph = HashMap::from([
+            ([0, 0, 0], Visibility::OPEN),
+            ([1, 0, 0], straight),
+            ([2, 0, 0], Visibility::OPEN),
+            ([1, 1, 0], Visibility::OPEN),
+        ]);
+        let seen = row(&graph, [1.0, 0.0, 0.0]);
+        assert!(seen.contains(&[2, 0, 0]));
+        assert!(!seen.contains(&[1, 1, 0]));
+
+        graph.insert([1, 0, 0], Visibility::OPEN);
+        assert!(row(&graph, [1.0, 0.0, 0.0]).contains(&[1, 1, 0]));
+    }
+
+    #[test]
+    fn chunks_entered_again_by_another_face_are_walked_through_again() {
+        // [1, 1, 0] is reached from below first, by way of [1, 0, 0], but only sees
+        // through from west to east. coming in from the west through [0, 1, 0] is the
+        // only way on to [2, 1, 0]
+        let (west, east) = (face(0, false), face(0, true));
+        let mut straight = Visibility::CLOSED;
+        straight.connect(west, east);
+        let graph = HashMap::from([
+            ([0, 0, 0], Visibility::OPEN),
+            ([1, 0, 0], Visibility::OPEN),
+            ([0, 1, 0], Visibility::OPEN),
+            ([1, 1, 0], straight),
+            ([2, 1, 0], Visibility::OPEN),
+        ]);
+        let seen = row(&graph, [1.0, 1.0, 0.0]);
+        assert!(seen.contains(&[1, 1, 0]));
+        assert!(seen.contains(&[2, 1, 0]));
+    }
+}
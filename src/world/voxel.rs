@@ -1,28 +1,97 @@
-#[derive(Eq, PartialEq, Default, Copy, Clone, Debug)]
+use serde::Deserialize;
+
+use crate::world::block_state::BlockState;
+
+#[derive(Eq, PartialEq, Hash, Default, Copy, Clone, Debug, Deserialize)]
+#[repr(u8)]
 pub enum BlockType
 {
 	#[default]
 	Air,
 	Grass,
 	Dirt,
+	Planks,
+	PlankSlab,
+	PlankStairs,
+	TallGrass,
+	Fence,
+	Stone,
+	Water,
+	Lava,
+	Log,
+	Leaves,
+	Cobblestone,
+	CoalOre,
+	IronOre,
+	Sand,
+	Snow,
+	CraftingTable,
 }
 
 impl BlockType {
+    // in id order, ids are what gets written to disk so only ever add to the end
+    pub const ALL: [BlockType; 19] = [
+        BlockType::Air,
+        BlockType::Grass,
+        BlockType::Dirt,
+        BlockType::Planks,
+        BlockType::PlankSlab,
+        BlockType::PlankStairs,
+        BlockType::TallGrass,
+        BlockType::Fence,
+        BlockType::Stone,
+        BlockType::Water,
+        BlockType::Lava,
+        BlockType::Log,
+        BlockType::Leaves,
+        BlockType::Cobblestone,
+        BlockType::CoalOre,
+        BlockType::IronOre,
+        BlockType::Sand,
+        BlockType::Snow,
+        BlockType::CraftingTable,
+    ];
+
+    pub fn id(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn from_id(id: u8) -> Option<BlockType> {
+        BlockType::ALL.get(id as usize).copied()
+    }
+
     pub fn is_solid(&self) -> bool {
         match self {
             BlockType::Air => false,
             BlockType::Grass => true,
             BlockType::Dirt => true,
+            BlockType::Planks => true,
+            BlockType::PlankSlab => true,
+            BlockType::PlankStairs => true,
+            BlockType::TallGrass => false,
+            BlockType::Fence => true,
+            BlockType::Stone => true,
+            BlockType::Water => false,
+            BlockType::Lava => false,
+            BlockType::Log => true,
+            BlockType::Leaves => true,
+            BlockType::Cobblestone => true,
+            BlockType::CoalOre => true,
+            BlockType::IronOre => true,
+            BlockType::Sand => true,
+            BlockType::Snow => true,
+            BlockType::CraftingTable => true,
         }
     }
     pub fn is_air(&self) -> bool {
-        !self.is_solid()
+        *self == BlockType::Air
     }
 }
 
-#[derive(Default, Copy, Clone, Debug)]
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Voxel
 {
 	pub block_type: BlockType,
-    pub texture_position: [i8; 2]
-}
\ No newline at end of file
+    pub texture_position: [i8; 2],
+    pub state: BlockState,
+}
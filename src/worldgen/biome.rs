@@ -0,0 +1,278 @@
+use crate::support::rng::hash;
+use crate::world::voxel::BlockType;
+use crate::worldgen::noise::Noise;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Biome {
+    Plains,
+    Forest,
+    Desert,
+    Tundra,
+    Mountains,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct BiomeSettings {
+    pub surface: BlockType,
+    // the few blocks between the surface and the stone
+    pub subsurface: BlockType,
+    pub base_height: f32,
+    pub height_scale: f32,
+    // multiplies the chance of trees and plants
+    pub vegetation: f32,
+    // multiplies the colour of grass and leaves, whose textures are plains green
+    pub tint: [f32; 3],
+}
+
+impl Biome {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Biome::Plains => "plains",
+            Biome::Forest => "forest",
+            Biome::Desert => "desert",
+            Biome::Tundra => "tundra",
+            Biome::Mountains => "mountains",
+        }
+    }
+
+    pub fn settings(&self) -> BiomeSettings {
+        match self {
+            Biome::Plains => BiomeSettings {
+                surface: BlockType::Grass,
+                subsurface: BlockType::Dirt,
+                base_height: 40.0,
+                height_scale: 8.0,
+                vegetation: 0.3,
+                tint: [1.0, 1.0, 1.0],
+            },
+            Biome::Forest => BiomeSettings {
+                surface: BlockType::Grass,
+                subsurface: BlockType::Dirt,
+                base_height: 42.0,
+                height_scale: 12.0,
+                vegetation: 1.0,
+                tint: [0.7, 0.85, 0.7],
+            },
+            Biome::Desert => BiomeSettings {
+                surface: BlockType::Sand,
+                subsurface: BlockType::Sand,
+                base_height: 38.0,
+                height_scale: 6.0,
+                vegetation: 0.0,
+                tint: [1.8, 1.1, 0.6],
+            },
+            Biome::Tundra => BiomeSettings {
+                surface: BlockType::Snow,
+                subsurface: BlockType::Dirt,
+                base_height: 41.0,
+                height_scale: 10.0,
+                vegetation: 0.1,
+                tint: [1.2, 1.05, 1.8],
+            },
+            Biome::Mountains => BiomeSettings {
+                surface: BlockType::Stone,
+                subsurface: BlockType::Stone,
+                base_height: 56.0,
+                height_scale: 36.0,
+                vegetation: 0.15,
+                tint: [1.1, 0.95, 1.3],
+            },
+        }
+    }
+
+    // temperature and humidity both roughly -1.0 to 1.0
+    pub fn classify(temperature: f32, humidity: f32) -> Biome {
+        if temperature < -0.3 {
+            if humidity < 0.0 {
+                Biome::Mountains
+            } else {
+                Biome::Tundra
+            }
+        } else if temperature > 0.3 && humidity < 0.0 {
+            Biome::Desert
+        } else if humidity > 0.15 {
+            Biome::Forest
+        } else {
+            Biome::Plains
+        }
+    }
+}
+
+// how far apart the samples used to blend terrain height between biomes are
+const BLEND_STEP: i32 = 4;
+const BLEND_RADIUS: i32 = 2;
+// how far apart the columns averaged for a tint are
+const TINT_STEP: i32 = 8;
+
+pub struct BiomeMap {
+    temperature: Noise,
+    humidity: Noise,
+}
+
+impl BiomeMap {
+    pub fn new(seed: u64) -> BiomeMap {
+        BiomeMap {
+            temperature: Noise::new(hash(seed, &[1])),
+            humidity: Noise::new(hash(seed, &[2])),
+        }
+    }
+
+    pub fn climate(&self, x: i32, z: i32) -> (f32, f32) {
+        let (x, z) = (x as f32 / 384.0, z as f32 / 384.0);
+        (
+            self.temperature.fractal_2d(x, z, 3) * 1.6,
+            self.humidity.fractal_2d(x, z, 3) * 1.6,
+        )
+    }
+
+    pub fn biome_at(&self, x: i32, z: i32) -> Biome {
+        let (temperature, humidity) = self.climate(x, z);
+        Biome::classify(temperature, humidity)
+    }
+
+    // base height and height scale averaged over the nearby biomes so there are no
+    // cliffs where two biomes meet
+    pub fn blended_height(&self, x: i32, z: i32) -> (f32, f32) {
+        let mut base = 0.0;
+        let mut scale = 0.0;
+        let mut total = 0.0;
+        for dx in -BLEND_RADIUS..=BLEND_RADIUS {
+            for dz in -BLEND_RADIUS..=BLEND_RADIUS {
+                let weight = 1.0 / (1.0 + (dx * dx + dz * dz) as f32);
+                let settings = self
+                    .biome_at(x + dx * BLEND_STEP, z + dz * BLEND_STEP)
+                    .settings();
+                base += settings.base_height * weight;
+                scale += settings.height_scale * weight;
+                total += weight;
+            }
+        }
+        (base / total, scale / total)
+    }
+
+    // the biome tint averaged over a `size` wide square of columns with its corner at
+    // (x, z), so it changes gradually from one chunk to the next
+    pub fn average_tint(&self, x: i32, z: i32, size: i32) -> [f32; 3] {
+        let mut tint = [0.0; 3];
+        let mut count = 0.0;
+        for dx in (0..size).step_by(TINT_STEP as usize) {
+            for dz in (0..size).step_by(TINT_STEP as usize) {
+                let column = self.biome_at(x + dx, z + dz).settings().tint;
+                for (total, c) in tint.iter_mut().zip(column) {
+                    *total += c;
+                }
+                count += 1.0;
+            }
+        }
+        tint.map(|t| t / count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Biome; 5] = [
+        Biome::Plains,
+        Biome::Forest,
+        Biome::Desert,
+        Biome::Tundra,
+        Biome::Mountains,
+    ];
+
+    // the biome of every 64th column over a wide square
+    fn samples(map: &BiomeMap) -> Vec<([i32; 2], Biome)> {
+        let mut samples = Vec::new();
+        for x in (-4096..4096).step_by(64) {
+            for z in (-4096..4096).step_by(64) {
+                samples.push(([x, z], map.biome_at(x, z)));
+            }
+        }
+        samples
+    }
+
+    #[test]
+    fn climate_picks_the_biome() {
+        assert_eq!(Biome::classify(-0.5, -0.5), Biome::Mountains);
+        assert_eq!(Biome::classify(-0.5, 0.5), Biome::Tundra);
+        assert_eq!(Biome::classify(0.5, -0.5), Biome::Desert);
+        assert_eq!(Biome::classify(0.0, 0.5), Biome::Forest);
+        assert_eq!(Biome::classify(0.0, 0.0), Biome::Plains);
+    }
+
+    #[test]
+    fn every_biome_turns_up_and_the_same_seed_agrees() {
+        let found = samples(&BiomeMap::new(3));
+        for biome in ALL {
+            assert!(
+                found.iter().any(|(_, b)| *b == biome),
+                "no {}",
+                biome.name()
+            );
+        }
+        assert_eq!(found, samples(&BiomeMap::new(3)));
+        assert_ne!(found, samples(&BiomeMap::new(4)));
+    }
+
+    #[test]
+    fn height_blends_between_biomes() {
+        let map = BiomeMap::new(3);
+        let settings = |x: i32, z: i32| map.biome_at(x, z).settings();
+        let reach = BLEND_STEP * BLEND_RADIUS;
+        let mut found_border = false;
+        for x in (-4096..4096).step_by(16) {
+            let z = 0;
+            let (base, scale) = map.blended_height(x, z);
+            let nearby: Vec<BiomeSettings> = (-reach..=reach)
+                .step_by(BLEND_STEP as usize)
+                .flat_map(|dx| {
+                    (-reach..=reach)
+                        .step_by(BLEND_STEP as usize)
+                        .map(move |dz| (dx, dz))
+                })
+                .map(|(dx, dz)| settings(x + dx, z + dz))
+                .collect();
+            let lowest = nearby
+                .iter()
+                .map(|s| s.base_height)
+                .fold(f32::MAX, f32::min);
+            let highest = nearby
+                .iter()
+                .map(|s| s.base_height)
+                .fold(f32::MIN, f32::max);
+            assert!(base >= lowest - 0.001 && base <= highest + 0.001);
+
+            if lowest == highest {
+                // well inside one biome it's that biome's own settings
+                assert!((base - settings(x, z).base_height).abs() < 0.001);
+                assert!((scale - settings(x, z).height_scale).abs() < 0.001);
+            } else {
+                found_border = true;
+                // and across a border it doesn't jump in one step
+                let (next, _) = map.blended_height(x + 1, z);
+                assert!((next - base).abs() < (highest - lowest) / 2.0);
+            }
+        }
+        assert!(found_border);
+    }
+
+    #[test]
+    fn tint_is_averaged_over_the_area() {
+        let map = BiomeMap::new(3);
+        let ([x, z], biome) = samples(&map)[0];
+        assert_eq!(map.average_tint(x, z, 1), biome.settings().tint);
+
+        let tint = map.average_tint(-512, -512, 1024);
+        for (c, channel) in tint.into_iter().enumerate() {
+            let lowest = ALL
+                .iter()
+                .map(|b| b.settings().tint[c])
+                .fold(f32::MAX, f32::min);
+            let highest = ALL
+                .iter()
+                .map(|b| b.settings().tint[c])
+                .fold(f32::MIN, f32::max);
+            assert!(channel >= lowest && channel <= highest);
+        }
+    }
+}
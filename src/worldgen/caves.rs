@@ -0,0 +1,178 @@
+use crate::support::rng::{hash, Rng};
+use crate::world::chunk::{Chunk, CHUNK_SIZE};
+use crate::world::voxel::{BlockType, Voxel};
+use crate::world::ChunkPos;
+use crate::worldgen::noise::Noise;
+
+// how many chunks away a worm can start and still reach into a chunk
+const WORM_REACH: i32 = 2;
+
+// carves tunnels ("worms") and big open caverns out of the terrain. both only depend on
+// the seed and position, so each chunk carves its own part without waiting on others
+pub struct CaveCarver {
+    seed: u64,
+    cavern_noise: Noise,
+    pub worms_per_chunk: u32,
+    pub worm_chance: f32,
+    pub worm_length: (i32, i32),
+    pub worm_radius: (f32, f32),
+    pub worm_y_range: (i32, i32),
+    // caverns open up where the noise goes above this
+    pub cavern_threshold: f32,
+    // caverns stay at least this far under the surface
+    pub cavern_min_depth: i32,
+}
+
+impl CaveCarver {
+    pub fn new(seed: u64) -> CaveCarver {
+        CaveCarver {
+            seed: hash(seed, &[3]),
+            cavern_noise: Noise::new(hash(seed, &[4])),
+            worms_per_chunk: 2,
+            worm_chance: 0.4,
+            worm_length: (40, 100),
+            worm_radius: (1.2, 2.8),
+            worm_y_range: (-24, 48),
+            cavern_threshold: 0.45,
+            cavern_min_depth: 12,
+        }
+    }
+
+    pub fn is_cavern(&self, x: i32, y: i32, z: i32, surface: i32) -> bool {
+        if y > surface - self.cavern_min_depth {
+            return false;
+        }
+        // squashed vertically so caverns are wider than they are tall
+        let n = self
+            .cavern_noise
+            .fractal_3d(x as f32 / 40.0, y as f32 / 20.0, z as f32 / 40.0, 2);
+        n > self.cavern_threshold
+    }
+
+    // `surface` gives the terrain height of a column, used to keep caverns underground
+    pub fn carve(
+        &self,
+        chunk: &mut Chunk,
+        chunk_pos: ChunkPos,
+        surface: impl Fn(i32, i32) -> i32,
+    ) {
+        let size = CHUNK_SIZE as i32;
+        let origin = [
+            chunk_pos[0] * size,
+            chunk_pos[1] * size,
+            chunk_pos[2] * size,
+        ];
+
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                let (wx, wz) = (origin[0] + x as i32, origin[2] + z as i32);
+                let height = surface(wx, wz);
+                for y in 0..CHUNK_SIZE {
+                    if self.is_cavern(wx, origin[1] + y as i32, wz, height) {
+                        carve_voxel(chunk, [x, y, z]);
+                    }
+                }
+            }
+        }
+
+        for cx in -WORM_REACH..=WORM_REACH {
+            for cy in -1..=1 {
+                for cz in -WORM_REACH..=WORM_REACH {
+                    let start_chunk = [chunk_pos[0] + cx, chunk_pos[1] + cy, chunk_pos[2] + cz];
+                    self.carve_worms_from(chunk, origin, start_chunk);
+                }
+            }
+        }
+    }
+
+    // replays every worm that starts in `start_chunk`, only touching blocks inside `chunk`
+    fn carve_worms_from(&self, chunk: &mut Chunk, origin: [i32; 3], start_chunk: ChunkPos) {
+        let size = CHUNK_SIZE as i32;
+        let mut rng = Rng::new(hash(
+            self.seed,
+            &[
+                start_chunk[0] as i64,
+                start_chunk[1] as i64,
+                start_chunk[2] as i64,
+            ],
+        ));
+
+        for _ in 0..self.worms_per_chunk {
+            if !rng.chance(self.worm_chance) {
+                continue;
+            }
+            let mut pos = [
+                (start_chunk[0] * size + rng.range(0, size)) as f32,
+                (start_chunk[1] * size + rng.range(0, size)) as f32,
+                (start_chunk[2] * size + rng.range(0, size)) as f32,
+            ];
+            let mut yaw = rng.next_f32() * std::f32::consts::TAU;
+            let mut pitch = (rng.next_f32() - 0.5) * 0.5;
+            let length = rng.range(self.worm_length.0, self.worm_length.1);
+            let (min_radius, max_radius) = self.worm_radius;
+            let in_range =
+                pos[1] as i32 >= self.worm_y_range.0 && pos[1] as i32 <= self.worm_y_range.1;
+
+            for step in 0..length {
+                // fattest in the middle, narrowing towards both ends
+                let t = step as f32 / length as f32;
+                let radius =
+                    min_radius + (max_radius - min_radius) * (t * std::f32::consts::PI).sin();
+                if in_range {
+                    carve_sphere(chunk, origin, pos, radius);
+                }
+
+                pos[0] += yaw.cos() * pitch.cos();
+                pos[1] += pitch.sin();
+                pos[2] += yaw.sin() * pitch.cos();
+                yaw += (rng.next_f32() - 0.5) * 0.5;
+                // pulled back towards level so worms don't dive straight down
+                pitch = pitch * 0.8 + (rng.next_f32() - 0.5) * 0.3;
+            }
+        }
+    }
+}
+
+fn carve_voxel(chunk: &mut Chunk, local: [usize; 3]) {
+    let existing = chunk.get(local[0], local[1], local[2]).block_type;
+    // leave fluids and anything a feature built alone
+    let carvable = matches!(
+        existing,
+        BlockType::Stone
+            | BlockType::Dirt
+            | BlockType::Grass
+            | BlockType::Sand
+            | BlockType::Snow
+            | BlockType::CoalOre
+            | BlockType::IronOre
+    );
+    if carvable {
+        chunk.set(local[0], local[1], local[2], Voxel::default());
+    }
+}
+
+fn carve_sphere(chunk: &mut Chunk, origin: [i32; 3], centre: [f32; 3], radius: f32) {
+    let size = CHUNK_SIZE as i32;
+    let mut lo = [0; 3];
+    let mut hi = [0; 3];
+    for axis in 0..3 {
+        lo[axis] = ((centre[axis] - radius).floor() as i32 - origin[axis]).max(0);
+        hi[axis] = ((centre[axis] + radius).ceil() as i32 - origin[axis]).min(size - 1);
+        if lo[axis] > hi[axis] {
+            return;
+        }
+    }
+
+    for x in lo[0]..=hi[0] {
+        for y in lo[1]..=hi[1] {
+            for z in lo[2]..=hi[2] {
+                let dx = (origin[0] + x) as f32 + 0.5 - centre[0];
+                let dy = (origin[1] + y) as f32 + 0.5 - centre[1];
+                let dz = (origin[2] + z) as f32 + 0.5 - centre[2];
+                if dx * dx + dy * dy + dz * dz < radius * radius {
+                    carve_voxel(chunk, [x as usize, y as usize, z as usize]);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use crate::support::rng::Rng;
+use crate::world::block_state::Axis;
+use crate::world::voxel::BlockType;
+use crate::worldgen::FeatureWriter;
+
+// which existing blocks a feature is allowed to overwrite
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Replace {
+    // air and anything else you can walk through, like tall grass
+    NonSolid,
+    Block(BlockType),
+    Any,
+}
+
+impl Replace {
+    pub fn allows(&self, existing: BlockType) -> bool {
+        match self {
+            Replace::NonSolid => !existing.is_solid(),
+            Replace::Block(block_type) => existing == *block_type,
+            Replace::Any => true,
+        }
+    }
+}
+
+// a fixed arrangement of blocks placed on the surface, offsets are from the block
+// above the ground at the structure's corner
+#[derive(Clone, Debug)]
+pub struct Structure {
+    pub name: &'static str,
+    pub blocks: Vec<([i32; 3], BlockType)>,
+}
+
+impl Structure {
+    // a broken ring of cobblestone wall with a plank floor
+    pub fn ruin() -> Structure {
+        let mut blocks = Vec::new();
+        for x in 0..5 {
+            for z in 0..5 {
+                blocks.push(([x, -1, z], BlockType::Planks));
+                let edge = x == 0 || x == 4 || z == 0 || z == 4;
+                if edge {
+                    // the walls crumble away towards one corner
+                    for y in 0..(4 - (x + z) / 3) {
+                        blocks.push(([x, y, z], BlockType::Cobblestone));
+                    }
+                }
+            }
+        }
+        Structure {
+            name: "ruin",
+            blocks,
+        }
+    }
+
+    pub fn well() -> Structure {
+        let mut blocks = Vec::new();
+        for x in 0..3 {
+            for z in 0..3 {
+                let centre = x == 1 && z == 1;
+                for y in -3..0 {
+                    let block = if centre {
+                        BlockType::Water
+                    } else {
+                        BlockType::Cobblestone
+                    };
+                    blocks.push(([x, y, z], block));
+                }
+                if !centre {
+                    blocks.push(([x, 0, z], BlockType::Cobblestone));
+                }
+            }
+        }
+        blocks.push(([0, 1, 0], BlockType::Fence));
+        blocks.push(([2, 1, 2], BlockType::Fence));
+        Structure {
+            name: "well",
+            blocks,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Feature {
+    Tree {
+        min_height: i32,
+        max_height: i32,
+    },
+    // a random walk of ore through stone somewhere between min_y and max_y
+    OreVein {
+        ore: BlockType,
+        size: u32,
+        min_y: i32,
+        max_y: i32,
+    },
+    Boulder {
+        block: BlockType,
+        radius: i32,
+    },
+    Structure(Structure),
+    // a scattered patch of plants on grass
+    Plants {
+        block: BlockType,
+        count: u32,
+        spread: i32,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct FeatureConfig {
+    pub feature: Feature,
+    // attempts per chunk, each of which goes ahead with `chance`
+    pub tries: u32,
+    pub chance: f32,
+}
+
+impl Feature {
+    // features placed underground pick a height within this range, everything else sits
+    // on the surface
+    pub fn depth_range(&self) -> Option<(i32, i32)> {
+        match self {
+            Feature::OreVein { min_y, max_y, .. } => Some((*min_y, *max_y)),
+            _ => None,
+        }
+    }
+
+    // vegetation is thinned out or boosted by the biome it lands in
+    pub fn is_vegetation(&self) -> bool {
+        matches!(self, Feature::Tree { .. } | Feature::Plants { .. })
+    }
+
+    // `origin` is the first air block above the ground for surface features
+    pub fn place(&self, writer: &mut FeatureWriter, origin: [i32; 3], rng: &mut Rng) {
+        let [x, y, z] = origin;
+        match self {
+            Feature::Tree {
+                min_height,
+                max_height,
+            } => {
+                let height = rng.range(*min_height, *max_height + 1);
+
+                let top = y + height - 1;
+                for ly in (top - 2)..=(top + 1) {
+                    let radius: i32 = if ly > top - 1 { 1 } else { 2 };
+                    for lx in -radius..=radius {
+                        for lz in -radius..=radius {
+                            // knock the corners off so it's less of a cube
+                            let corner = lx.abs() == radius && lz.abs() == radius;
+                            if corner && (ly > top || rng.chance(0.5)) {
+                                continue;
+                            }
+                            writer.set([x + lx, ly, z + lz], BlockType::Leaves, Replace::NonSolid);
+                        }
+                    }
+                }
+
+                let mut log = writer.world.blocks.default_voxel(BlockType::Log);
+                log.state = log.state.with_axis(Axis::Y);
+                writer.set([x, y - 1, z], BlockType::Dirt, Replace::Any);
+                for ty in y..y + height {
+                    writer.set_voxel([x, ty, z], log, Replace::Any);
+                }
+            }
+            Feature::OreVein { ore, size, .. } => {
+                let mut pos = origin;
+                for _ in 0..*size {
+                    writer.set(pos, *ore, Replace::Block(BlockType::Stone));
+                    let axis = rng.range(0, 3) as usize;
+                    pos[axis] += if rng.chance(0.5) { 1 } else { -1 };
+                }
+            }
+            Feature::Boulder { block, radius } => {
+                let r = *radius;
+                for bx in -r..=r {
+                    for by in -r..=r {
+                        for bz in -r..=r {
+                            let d = bx * bx + by * by + bz * bz;
+                            if d <= r * r + rng.range(0, 2) {
+                                writer.set([x + bx, y + by, z + bz], *block, Replace::Any);
+                            }
+                        }
+                    }
+                }
+            }
+            Feature::Structure(structure) => {
+                for &(offset, block) in &structure.blocks {
+                    let pos = [x + offset[0], y + offset[1], z + offset[2]];
+                    writer.set(pos, block, Replace::Any);
+                }
+            }
+            Feature::Plants {
+                block,
+                count,
+                spread,
+            } => {
+                for _ in 0..*count {
+                    let px = x + rng.range(-spread, spread + 1);
+                    let pz = z + rng.range(-spread, spread + 1);
+                    // only where the ground is at the same height as the middle of the patch
+                    let ground = writer.get([px, y - 1, pz]).map(|v| v.block_type);
+                    if ground == Some(BlockType::Grass) {
+                        writer.set([px, y, pz], *block, Replace::Block(BlockType::Air));
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn default_features() -> Vec<FeatureConfig> {
+    vec![
+        FeatureConfig {
+            feature: Feature::OreVein {
+                ore: BlockType::CoalOre,
+                size: 12,
+                min_y: 0,
+                max_y: 48,
+            },
+            tries: 12,
+            chance: 1.0,
+        },
+        FeatureConfig {
+            feature: Feature::OreVein {
+                ore: BlockType::IronOre,
+                size: 6,
+                min_y: 0,
+                max_y: 32,
+            },
+            tries: 8,
+            chance: 1.0,
+        },
+        FeatureConfig {
+            feature: Feature::Boulder {
+                block: BlockType::Cobblestone,
+                radius: 1,
+            },
+            tries: 1,
+            chance: 0.3,
+        },
+        FeatureConfig {
+            feature: Feature::Structure(Structure::ruin()),
+            tries: 1,
+            chance: 0.02,
+        },
+        FeatureConfig {
+            feature: Feature::Structure(Structure::well()),
+            tries: 1,
+            chance: 0.02,
+        },
+        FeatureConfig {
+            feature: Feature::Plants {
+                block: BlockType::TallGrass,
+                count: 24,
+                spread: 4,
+            },
+            tries: 4,
+            chance: 1.0,
+        },
+        FeatureConfig {
+            feature: Feature::Tree {
+                min_height: 4,
+                max_height: 6,
+            },
+            tries: 6,
+            chance: 0.8,
+        },
+    ]
+}
@@ -0,0 +1,406 @@
+pub mod biome;
+pub mod caves;
+pub mod features;
+pub mod noise;
+
+use std::collections::{HashMap, HashSet};
+
+use crate::support::rng::{hash, Rng};
+use crate::world::chunk::{Chunk, CHUNK_SIZE};
+use crate::world::voxel::{BlockType, Voxel};
+use crate::world::{split_pos, ChunkPos, World};
+use biome::{Biome, BiomeMap};
+use caves::CaveCarver;
+use features::{FeatureConfig, Replace};
+use noise::Noise;
+
+// blocks a feature wanted to put in a chunk that hasn't been generated yet. they get
+// written as soon as that chunk is, however long that takes, so features never end at a
+// chunk edge
+#[derive(Default)]
+pub struct PendingWrites {
+    writes: HashMap<ChunkPos, Vec<([i32; 3], Voxel, Replace)>>,
+}
+
+impl PendingWrites {
+    pub fn len(&self) -> usize {
+        self.writes.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+
+    fn push(&mut self, pos: [i32; 3], voxel: Voxel, replace: Replace) {
+        let (chunk, _) = split_pos(pos);
+        self.writes
+            .entry(chunk)
+            .or_default()
+            .push((pos, voxel, replace));
+    }
+
+    fn apply(&mut self, world: &mut World, chunk: ChunkPos) {
+        for (pos, voxel, replace) in self.writes.remove(&chunk).unwrap_or_default() {
+            if replace.allows(world.get_voxel(pos).block_type) {
+                world.set_voxel(pos, voxel);
+            }
+        }
+    }
+}
+
+// what features write through. blocks in chunks that exist go straight in, the rest
+// wait in the pending queue
+pub struct FeatureWriter<'a> {
+    world: &'a mut World,
+    pending: &'a mut PendingWrites,
+    generated: &'a HashSet<ChunkPos>,
+    // the chunk being decorated
+    chunk: ChunkPos,
+}
+
+impl FeatureWriter<'_> {
+    // None outside the chunk being decorated. that chunk only holds its own terrain and
+    // features so far, whatever order its neighbours were generated in
+    pub fn get(&self, pos: [i32; 3]) -> Option<Voxel> {
+        let (chunk, _) = split_pos(pos);
+        (chunk == self.chunk).then(|| self.world.get_voxel(pos))
+    }
+
+    pub fn set(&mut self, pos: [i32; 3], block_type: BlockType, replace: Replace) {
+        let voxel = self.world.blocks.default_voxel(block_type);
+        self.set_voxel(pos, voxel, replace);
+    }
+
+    pub fn set_voxel(&mut self, pos: [i32; 3], voxel: Voxel, replace: Replace) {
+        let (chunk, _) = split_pos(pos);
+        if !self.generated.contains(&chunk) || !self.world.chunks.contains_key(&chunk) {
+            self.pending.push(pos, voxel, replace);
+        } else if replace.allows(self.world.get_voxel(pos).block_type) {
+            self.world.set_voxel(pos, voxel);
+        }
+    }
+}
+
+// how far 3d noise can push the ground above or below the height map, in blocks
+const OVERHANG: i32 = 9;
+
+pub struct WorldGenerator {
+    pub seed: u64,
+    pub features: Vec<FeatureConfig>,
+    pub biomes: BiomeMap,
+    pub caves: CaveCarver,
+    height_noise: Noise,
+    density_noise: Noise,
+    pending: PendingWrites,
+    generated: HashSet<ChunkPos>,
+}
+
+impl WorldGenerator {
+    pub fn new(seed: u64) -> WorldGenerator {
+        WorldGenerator {
+            seed,
+            features: features::default_features(),
+            biomes: BiomeMap::new(seed),
+            caves: CaveCarver::new(seed),
+            height_noise: Noise::new(hash(seed, &[0])),
+            density_noise: Noise::new(hash(seed, &[5])),
+            pending: PendingWrites::default(),
+            generated: HashSet::new(),
+        }
+    }
+
+    // the height the terrain is pulled towards in a column. overhangs and cliffs can put
+    // the real ground up to OVERHANG blocks off it, see terrain_top
+    pub fn surface_height(&self, x: i32, z: i32) -> i32 {
+        let n = self
+            .height_noise
+            .fractal_2d(x as f32 / 96.0, z as f32 / 96.0, 4);
+        let (base, scale) = self.biomes.blended_height(x, z);
+        (base + n * scale) as i32
+    }
+
+    // above zero is solid. the height map biases it so it's solid below the surface and
+    // air above, then 3d noise bends the boundary into overhangs
+    pub fn density(&self, x: i32, y: i32, z: i32, surface_height: i32) -> f32 {
+        let bias = (surface_height - y) as f32 / OVERHANG as f32;
+        if bias.abs() >= 1.0 {
+            return bias;
+        }
+        let n = self
+            .density_noise
+            .fractal_3d(x as f32 / 40.0, y as f32 / 28.0, z as f32 / 40.0, 3);
+        bias + n
+    }
+
+    // y of the highest solid terrain block in a column, before caves are carved
+    pub fn terrain_top(&self, x: i32, z: i32) -> i32 {
+        let height = self.surface_height(x, z);
+        ((height - OVERHANG)..=(height + OVERHANG))
+            .rev()
+            .find(|&y| self.density(x, y, z, height) > 0.0)
+            .unwrap_or(height - OVERHANG)
+    }
+
+    pub fn biome_at(&self, x: i32, z: i32) -> Biome {
+        self.biomes.biome_at(x, z)
+    }
+
+    pub fn is_generated(&self, chunk: ChunkPos) -> bool {
+        self.generated.contains(&chunk)
+    }
+
+    // a chunk generated earlier has been loaded back in. anything features wanted to write
+    // into it while it was away goes in now
+    pub fn chunk_loaded(&mut self, world: &mut World, chunk_pos: ChunkPos) {
+        self.pending.apply(world, chunk_pos);
+    }
+
+    pub fn pending_writes(&self) -> usize {
+        self.pending.len()
+    }
+
+    fn fill_terrain(&self, world: &World, chunk_pos: ChunkPos) -> Chunk {
+        let mut chunk = Chunk::new();
+        let stone = world.blocks.default_voxel(BlockType::Stone);
+        let size = CHUNK_SIZE as i32;
+        let min_y = chunk_pos[1] * size;
+
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                let world_x = chunk_pos[0] * size + x as i32;
+                let world_z = chunk_pos[2] * size + z as i32;
+                let height = self.surface_height(world_x, world_z);
+                let settings = self.biome_at(world_x, world_z).settings();
+                let surface = world.blocks.default_voxel(settings.surface);
+                let subsurface = world.blocks.default_voxel(settings.subsurface);
+
+                // walks down from a few blocks above the chunk counting how deep under
+                // open air each block is, so ground under an overhang still gets a surface
+                let mut depth = 0;
+                for world_y in (min_y..min_y + size + 4).rev() {
+                    if self.density(world_x, world_y, world_z, height) <= 0.0 {
+                        depth = 0;
+                        continue;
+                    }
+                    depth += 1;
+                    if world_y >= min_y + size {
+                        continue;
+                    }
+
+                    let voxel = match depth {
+                        1 => surface,
+                        2..=4 => subsurface,
+                        _ => stone,
+                    };
+                    chunk.set(x, (world_y - min_y) as usize, z, voxel);
+                }
+            }
+        }
+
+        self.caves
+            .carve(&mut chunk, chunk_pos, |x, z| self.surface_height(x, z));
+        chunk.tint = self
+            .biomes
+            .average_tint(chunk_pos[0] * size, chunk_pos[2] * size, size);
+        chunk
+    }
+
+    pub fn generate_chunk(&mut self, world: &mut World, chunk_pos: ChunkPos) {
+        if self.generated.contains(&chunk_pos) {
+            return;
+        }
+        let chunk = self.fill_terrain(world, chunk_pos);
+        world.chunks.insert(chunk_pos, chunk);
+        self.generated.insert(chunk_pos);
+
+        // decorated before anything neighbours queued up for this chunk is written. a
+        // neighbour generated later writes straight in once this chunk is decorated too,
+        // so either way the chunk's own features go first and theirs land on top
+        self.decorate(world, chunk_pos);
+        self.pending.apply(world, chunk_pos);
+    }
+
+    // features are placed by the chunk their origin falls in, or their ground for ones on
+    // the surface, each with its own random stream, and only look at that chunk, so what
+    // a chunk places doesn't depend on what order chunks are generated in. where features
+    // from two different chunks overlap, which of them ends up on top can still depend on
+    // the order
+    fn decorate(&mut self, world: &mut World, chunk_pos: ChunkPos) {
+        let size = CHUNK_SIZE as i32;
+        let min_y = chunk_pos[1] * size;
+        let max_y = min_y + size - 1;
+
+        for (index, config) in self.features.iter().enumerate() {
+            let mut rng = Rng::new(hash(
+                self.seed,
+                &[
+                    chunk_pos[0] as i64,
+                    chunk_pos[1] as i64,
+                    chunk_pos[2] as i64,
+                    index as i64,
+                ],
+            ));
+
+            for _ in 0..config.tries {
+                let x = chunk_pos[0] * size + rng.range(0, size);
+                let z = chunk_pos[2] * size + rng.range(0, size);
+                // surface features belong to the chunk the ground under them is in, so
+                // it can be checked
+                let (y, ground) = match config.feature.depth_range() {
+                    Some((lo, hi)) => {
+                        let (lo, hi) = (lo.max(min_y), hi.min(max_y));
+                        if lo > hi {
+                            break;
+                        }
+                        (rng.range(lo, hi + 1), None)
+                    }
+                    None => {
+                        // skip the density scan when the surface can't be in this chunk
+                        let height = self.surface_height(x, z);
+                        if height + OVERHANG < min_y || height - OVERHANG > max_y {
+                            continue;
+                        }
+                        let ground = self.terrain_top(x, z);
+                        (ground + 1, Some(ground))
+                    }
+                };
+                let mut chance = config.chance;
+                if config.feature.is_vegetation() {
+                    chance *= self.biome_at(x, z).settings().vegetation;
+                }
+                let owner_y = ground.unwrap_or(y);
+                if !rng.chance(chance) || owner_y < min_y || owner_y > max_y {
+                    continue;
+                }
+                // terrain_top is from before caves were carved, which can leave nothing
+                // under a surface feature
+                if ground.is_some_and(|g| !world.get_voxel([x, g, z]).block_type.is_solid()) {
+                    continue;
+                }
+
+                let mut writer = FeatureWriter {
+                    world,
+                    pending: &mut self.pending,
+                    generated: &self.generated,
+                    chunk: chunk_pos,
+                };
+                config.feature.place(&mut writer, [x, y, z], &mut rng);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::block_model::BlockModels;
+    use crate::world::registry::BlockRegistry;
+
+    fn new_world() -> World {
+        let blocks = BlockRegistry::load("assets/data/blocks.ron").unwrap();
+        World::new(blocks, BlockModels::default())
+    }
+
+    // a 2x2x2 block of chunks with the ground running through it
+    fn region(generator: &WorldGenerator) -> Vec<ChunkPos> {
+        let y = generator.terrain_top(32, 32).div_euclid(CHUNK_SIZE as i32);
+        let mut region = Vec::new();
+        for x in 0..2 {
+            for dy in 0..2 {
+                for z in 0..2 {
+                    region.push([x, y - 1 + dy, z]);
+                }
+            }
+        }
+        region
+    }
+
+    // generates `region` forwards and backwards and checks every chunk comes out the same
+    fn assert_order_doesnt_matter(seed: u64) {
+        let mut generator = WorldGenerator::new(seed);
+        let mut world = new_world();
+        let order = region(&generator);
+        for &pos in &order {
+            generator.generate_chunk(&mut world, pos);
+        }
+
+        let mut reversed_generator = WorldGenerator::new(seed);
+        let mut reversed = new_world();
+        for &pos in order.iter().rev() {
+            reversed_generator.generate_chunk(&mut reversed, pos);
+        }
+
+        for pos in order {
+            assert!(
+                world.chunks[&pos].to_bytes() == reversed.chunks[&pos].to_bytes(),
+                "chunk {pos:?} differs with seed {seed}"
+            );
+        }
+        assert_eq!(
+            generator.pending_writes(),
+            reversed_generator.pending_writes()
+        );
+    }
+
+    #[test]
+    fn plants_dont_depend_on_generation_order() {
+        // a seed where patches used to spill into neighbours only if they were generated
+        assert_order_doesnt_matter(1);
+    }
+
+    #[test]
+    fn neighbours_features_land_on_top_either_way() {
+        // a seed where a neighbour's features and this chunk's own used to overlap
+        // differently depending on which was generated first
+        assert_order_doesnt_matter(3);
+    }
+
+    #[test]
+    fn trees_arent_planted_over_caves() {
+        let mut generator = WorldGenerator::new(4);
+        generator.features = vec![FeatureConfig {
+            feature: features::Feature::Tree {
+                min_height: 4,
+                max_height: 6,
+            },
+            tries: 64,
+            chance: 1.0,
+        }];
+        // caverns through about half the ground, right up to the surface
+        generator.caves.cavern_threshold = 0.0;
+        generator.caves.cavern_min_depth = -2 * OVERHANG;
+        generator.caves.worms_per_chunk = 0;
+
+        let mut world = new_world();
+        let region = region(&generator);
+        for &pos in &region {
+            generator.generate_chunk(&mut world, pos);
+        }
+
+        let mut trees = 0;
+        for chunk_pos in region {
+            let size = CHUNK_SIZE as i32;
+            let origin = chunk_pos.map(|c| c * size);
+            for x in origin[0]..origin[0] + size {
+                for z in origin[2]..origin[2] + size {
+                    for y in origin[1]..origin[1] + size {
+                        let below = world.get_voxel([x, y - 1, z]).block_type;
+                        if world.get_voxel([x, y, z]).block_type != BlockType::Log
+                            || below != BlockType::Dirt
+                        {
+                            continue;
+                        }
+                        trees += 1;
+                        let surface = generator.surface_height(x, z);
+                        assert!(
+                            !generator.caves.is_cavern(x, y - 1, z, surface),
+                            "tree at {:?} was planted over a cave",
+                            [x, y, z]
+                        );
+                    }
+                }
+            }
+        }
+        assert!(trees > 0);
+    }
+}
@@ -0,0 +1,124 @@
+use crate::support::rng::hash;
+
+// gradient noise that only depends on the seed and the position, so chunks can be
+// generated in any order and still line up
+#[derive(Clone, Debug)]
+pub struct Noise {
+    seed: u64,
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+const GRADIENTS_3D: [[f32; 3]; 12] = [
+    [1.0, 1.0, 0.0],
+    [-1.0, 1.0, 0.0],
+    [1.0, -1.0, 0.0],
+    [-1.0, -1.0, 0.0],
+    [1.0, 0.0, 1.0],
+    [-1.0, 0.0, 1.0],
+    [1.0, 0.0, -1.0],
+    [-1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0],
+    [0.0, -1.0, 1.0],
+    [0.0, 1.0, -1.0],
+    [0.0, -1.0, -1.0],
+];
+
+impl Noise {
+    pub fn new(seed: u64) -> Noise {
+        Noise { seed }
+    }
+
+    fn gradient_2d(&self, x: i32, z: i32) -> [f32; 2] {
+        let h = hash(self.seed, &[x as i64, z as i64]);
+        let angle = (h >> 40) as f32 / (1u64 << 24) as f32 * std::f32::consts::TAU;
+        [angle.cos(), angle.sin()]
+    }
+
+    fn gradient_3d(&self, x: i32, y: i32, z: i32) -> [f32; 3] {
+        let h = hash(self.seed, &[x as i64, y as i64, z as i64]);
+        GRADIENTS_3D[(h % 12) as usize]
+    }
+
+    // roughly -1.0 to 1.0
+    pub fn get_2d(&self, x: f32, z: f32) -> f32 {
+        let (x0, z0) = (x.floor() as i32, z.floor() as i32);
+        let (fx, fz) = (x - x0 as f32, z - z0 as f32);
+
+        let dot = |cx: i32, cz: i32| {
+            let g = self.gradient_2d(x0 + cx, z0 + cz);
+            g[0] * (fx - cx as f32) + g[1] * (fz - cz as f32)
+        };
+
+        let (u, v) = (fade(fx), fade(fz));
+        let a = lerp(dot(0, 0), dot(1, 0), u);
+        let b = lerp(dot(0, 1), dot(1, 1), u);
+        lerp(a, b, v) * std::f32::consts::SQRT_2
+    }
+
+    // roughly -1.0 to 1.0
+    pub fn get_3d(&self, x: f32, y: f32, z: f32) -> f32 {
+        let (x0, y0, z0) = (x.floor() as i32, y.floor() as i32, z.floor() as i32);
+        let (fx, fy, fz) = (x - x0 as f32, y - y0 as f32, z - z0 as f32);
+
+        let dot = |cx: i32, cy: i32, cz: i32| {
+            let g = self.gradient_3d(x0 + cx, y0 + cy, z0 + cz);
+            g[0] * (fx - cx as f32) + g[1] * (fy - cy as f32) + g[2] * (fz - cz as f32)
+        };
+
+        let (u, v, w) = (fade(fx), fade(fy), fade(fz));
+        let bottom = lerp(
+            lerp(dot(0, 0, 0), dot(1, 0, 0), u),
+            lerp(dot(0, 0, 1), dot(1, 0, 1), u),
+            w,
+        );
+        let top = lerp(
+            lerp(dot(0, 1, 0), dot(1, 1, 0), u),
+            lerp(dot(0, 1, 1), dot(1, 1, 1), u),
+            w,
+        );
+        lerp(bottom, top, v)
+    }
+
+    // several octaves of noise added together, each twice the frequency and half the
+    // strength of the last. stays roughly within -1.0 to 1.0
+    pub fn fractal_2d(&self, x: f32, z: f32, octaves: u32) -> f32 {
+        let mut total = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        let mut max = 0.0;
+        for octave in 0..octaves {
+            let offset = octave as f32 * 31.7;
+            total += self.get_2d(x * frequency + offset, z * frequency - offset) * amplitude;
+            max += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        total / max
+    }
+
+    pub fn fractal_3d(&self, x: f32, y: f32, z: f32, octaves: u32) -> f32 {
+        let mut total = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        let mut max = 0.0;
+        for octave in 0..octaves {
+            let offset = octave as f32 * 31.7;
+            total += self.get_3d(
+                x * frequency + offset,
+                y * frequency - offset,
+                z * frequency + offset,
+            ) * amplitude;
+            max += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        total / max
+    }
+}
@@ -0,0 +1,160 @@
+// renders fixed worlds offscreen and compares them with the pictures in tests/golden.
+// run with UPDATE_GOLDEN=1 to replace the pictures after a change that's meant to alter
+// what things look like. renders that don't match are written to target/golden to look at
+
+use std::path::{Path, PathBuf};
+
+use image::RgbaImage;
+use survival_project::game::Game;
+use survival_project::render::headless::Headless;
+use survival_project::render::Renderer;
+use survival_project::support::camera::CameraState;
+use survival_project::world::block_model::BlockModels;
+use survival_project::world::registry::BlockRegistry;
+use survival_project::world::voxel::BlockType;
+use survival_project::world::World;
+
+const SIZE: (u32, u32) = (320, 240);
+// how far apart a channel can be before a pixel counts as different. drivers and mesa
+// versions round a little differently
+const CHANNEL_TOLERANCE: u8 = 24;
+// the share of pixels allowed to differ, for edges that land a pixel over
+const PIXEL_TOLERANCE: f32 = 0.01;
+
+fn render(world: &World, position: [f32; 3], direction: [f32; 3]) -> RgbaImage {
+    let headless = Headless::new().expect("setting up headless rendering");
+    let mut renderer = Renderer::new(headless).expect("setting up rendering");
+    let mut camera = CameraState::new();
+    camera.set_position(position.into());
+    camera.set_direction(direction.into());
+    camera.set_aspect_ratio(SIZE.0 as f32 / SIZE.1 as f32);
+    renderer.update_chunks(world, &camera, world.chunks.keys().copied());
+    renderer
+        .render_image(world, &camera, SIZE)
+        .expect("rendering")
+}
+
+// the share of pixels further apart than CHANNEL_TOLERANCE
+fn difference(a: &RgbaImage, b: &RgbaImage) -> f32 {
+    let different = a
+        .pixels()
+        .zip(b.pixels())
+        .filter(|(a, b)| {
+            a.0.iter()
+                .zip(b.0.iter())
+                .any(|(a, b)| a.abs_diff(*b) > CHANNEL_TOLERANCE)
+        })
+        .count();
+    different as f32 / (a.width() * a.height()) as f32
+}
+
+fn check(name: &str, actual: RgbaImage) {
+    let golden = Path::new("tests/golden").join(format!("{name}.png"));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        actual.save(&golden).unwrap();
+        return;
+    }
+
+    let expected = image::open(&golden)
+        .unwrap_or_else(|err| panic!("couldn't open {}: {err}", golden.display()))
+        .to_rgba8();
+    let failed: PathBuf = Path::new("target/golden").join(format!("{name}.png"));
+    let mismatch = if expected.dimensions() != actual.dimensions() {
+        Some(format!(
+            "is {:?} but {} is {:?}",
+            actual.dimensions(),
+            golden.display(),
+            expected.dimensions()
+        ))
+    } else {
+        let difference = difference(&expected, &actual);
+        (difference > PIXEL_TOLERANCE).then(|| {
+            format!(
+                "{:.2}% of pixels differ from {}",
+                difference * 100.0,
+                golden.display()
+            )
+        })
+    };
+    if let Some(mismatch) = mismatch {
+        std::fs::create_dir_all("target/golden").unwrap();
+        actual.save(&failed).unwrap();
+        panic!("{name} {mismatch}, the render is in {}", failed.display());
+    }
+}
+
+// one of every kind of block shape on a stone floor
+fn block_shapes_world() -> World {
+    let blocks = BlockRegistry::load("assets/data/blocks.ron").unwrap();
+    let models = BlockModels::load("assets/data/block_models.ron").unwrap();
+    let mut world = World::new(blocks, models);
+    let mut place = |pos: [i32; 3], block_type: BlockType| {
+        let voxel = world.blocks.default_voxel(block_type);
+        world.set_voxel(pos, voxel);
+    };
+    for x in 0..12 {
+        for z in 0..12 {
+            place([x, 0, z], BlockType::Stone);
+        }
+    }
+    let row = [
+        BlockType::Grass,
+        BlockType::Dirt,
+        BlockType::Planks,
+        BlockType::PlankSlab,
+        BlockType::PlankStairs,
+        BlockType::Fence,
+        BlockType::TallGrass,
+        BlockType::Log,
+        BlockType::Leaves,
+        BlockType::CoalOre,
+        BlockType::Sand,
+    ];
+    for (x, block_type) in row.into_iter().enumerate() {
+        place([x as i32, 1, 4], block_type);
+    }
+    for x in 2..6 {
+        for z in 7..10 {
+            place([x, 1, z], BlockType::Water);
+        }
+    }
+    place([8, 1, 8], BlockType::Lava);
+    world
+}
+
+#[test]
+fn block_shapes() {
+    check(
+        "block_shapes",
+        render(&block_shapes_world(), [6.0, 5.0, -3.0], [0.0, -0.45, 0.89]),
+    );
+}
+
+// the same with a tint far stronger than any biome's, which only the grass and leaves
+// should pick up
+#[test]
+fn biome_tint() {
+    let mut world = block_shapes_world();
+    for chunk in world.chunks.values_mut() {
+        chunk.tint = [2.0, 0.5, 2.0];
+    }
+    let position = [6.0, 5.0, -3.0];
+    let direction = [0.0, -0.45, 0.89];
+    let tinted = render(&world, position, direction);
+    let plain = render(&block_shapes_world(), position, direction);
+    // they're a small part of the picture, so any difference at all will do
+    assert!(difference(&tinted, &plain) > 0.0);
+    check("biome_tint", tinted);
+}
+
+// the start of seed 0 from above
+#[test]
+fn generated_terrain() {
+    let mut game = Game::new(0);
+    game.generate_around([0, 0, 0], 1);
+    let top = game.generator.terrain_top(0, 0) as f32;
+    check(
+        "generated_terrain",
+        render(&game.world, [-20.0, top + 14.0, -20.0], [0.6, -0.53, 0.6]),
+    );
+}